@@ -1,8 +1,10 @@
 //! Dockerfile parser and build engine for HATAKE Desktop.
 //!
-//! Supports basic Dockerfile instructions: FROM, RUN, COPY, WORKDIR, CMD, ENV, EXPOSE.
+//! Supports basic Dockerfile instructions: FROM, RUN, COPY, WORKDIR, CMD,
+//! ENTRYPOINT, ENV, EXPOSE, LABEL, USER.
 
 use anyhow::{bail, Context, Result};
+use furukawa_domain::image::ImageConfig;
 use std::path::{Path, PathBuf};
 use tracing::info;
 
@@ -14,9 +16,11 @@ pub enum Instruction {
     Copy { src: String, dest: String },
     Workdir(String),
     Cmd(Vec<String>),
+    Entrypoint(Vec<String>),
     Env { key: String, value: String },
     Expose(u16),
     Label { key: String, value: String },
+    User(String),
 }
 
 /// Parse a Dockerfile from a string into a list of instructions.
@@ -63,7 +67,13 @@ pub fn parse_dockerfile(content: &str) -> Result<Vec<Instruction>> {
                 instructions.push(Instruction::Workdir(rest.to_string()));
             }
             "CMD" => {
-                instructions.push(Instruction::Cmd(shell_split(rest)));
+                instructions.push(Instruction::Cmd(command_form(rest)));
+            }
+            "ENTRYPOINT" => {
+                instructions.push(Instruction::Entrypoint(command_form(rest)));
+            }
+            "USER" => {
+                instructions.push(Instruction::User(rest.to_string()));
             }
             "ENV" => {
                 if let Some((key, value)) = rest.split_once('=').or_else(|| rest.split_once(' ')) {
@@ -79,11 +89,14 @@ pub fn parse_dockerfile(content: &str) -> Result<Vec<Instruction>> {
                 }
             }
             "LABEL" => {
-                if let Some((key, value)) = rest.split_once('=') {
-                    instructions.push(Instruction::Label {
-                        key: key.trim().to_string(),
-                        value: value.trim().trim_matches('"').to_string(),
-                    });
+                // One or more `key=value` pairs; values may be quoted
+                for pair in shell_split(rest) {
+                    if let Some((key, value)) = pair.split_once('=') {
+                        instructions.push(Instruction::Label {
+                            key: key.to_string(),
+                            value: value.to_string(),
+                        });
+                    }
                 }
             }
             other => {
@@ -115,10 +128,16 @@ impl BuildContext {
     }
 }
 
+/// What a build produces.
+pub struct BuildOutput {
+    /// Gzipped tarball of the build rootfs, to be fed into the ImageStore.
+    pub layer_path: PathBuf,
+    /// Image defaults collected from CMD, ENV, WORKDIR and LABEL.
+    pub config: ImageConfig,
+}
+
 /// Execute a build inside a temporary WSL2 chroot directory.
-///
-/// Returns the layer tarball path that can be fed into the ImageStore.
-pub async fn run_build(ctx: &BuildContext, output_dir: &Path) -> Result<PathBuf> {
+pub async fn run_build(ctx: &BuildContext, output_dir: &Path) -> Result<BuildOutput> {
     use tokio::process::Command;
 
     let build_id = uuid::Uuid::new_v4().to_string();
@@ -138,6 +157,7 @@ pub async fn run_build(ctx: &BuildContext, output_dir: &Path) -> Result<PathBuf>
     };
 
     let mut base_image: Option<String> = None;
+    let mut config = ImageConfig::default();
 
     for instruction in &ctx.instructions {
         match instruction {
@@ -185,6 +205,7 @@ pub async fn run_build(ctx: &BuildContext, output_dir: &Path) -> Result<PathBuf>
             }
             Instruction::Workdir(dir) => {
                 info!("[BUILD] WORKDIR {}", dir);
+                config.working_dir = Some(dir.clone());
                 let target = format!("{}{}", wsl_rootfs, dir);
                 let _ = Command::new("wsl.exe")
                     .args(["-d", &ctx.distro, "--", "mkdir", "-p", &target])
@@ -193,6 +214,7 @@ pub async fn run_build(ctx: &BuildContext, output_dir: &Path) -> Result<PathBuf>
             }
             Instruction::Env { key, value } => {
                 info!("[BUILD] ENV {}={}", key, value);
+                set_env(config.env.get_or_insert_with(Vec::new), key, value);
                 // Write to /etc/environment inside rootfs
                 let env_line = format!("{}={}", key, value);
                 let etc_env = format!("{}/etc/environment", wsl_rootfs);
@@ -206,10 +228,20 @@ pub async fn run_build(ctx: &BuildContext, output_dir: &Path) -> Result<PathBuf>
                 info!("[BUILD] EXPOSE {} (metadata only)", port);
             }
             Instruction::Label { key, value } => {
-                info!("[BUILD] LABEL {}={} (metadata only)", key, value);
+                info!("[BUILD] LABEL {}={}", key, value);
+                config.labels.get_or_insert_with(Default::default).insert(key.clone(), value.clone());
             }
             Instruction::Cmd(cmd) => {
                 info!("[BUILD] CMD {:?} (saved as image default cmd)", cmd);
+                config.cmd = Some(cmd.clone());
+            }
+            Instruction::Entrypoint(entrypoint) => {
+                info!("[BUILD] ENTRYPOINT {:?}", entrypoint);
+                config.entrypoint = Some(entrypoint.clone());
+            }
+            Instruction::User(user) => {
+                info!("[BUILD] USER {}", user);
+                config.user = Some(user.clone());
             }
         }
     }
@@ -231,7 +263,63 @@ pub async fn run_build(ctx: &BuildContext, output_dir: &Path) -> Result<PathBuf>
         bail!("Failed to create layer tarball");
     }
 
-    Ok(layer_path)
+    Ok(BuildOutput { layer_path, config })
+}
+
+/// Applies `docker commit --change` instructions to an image config. Only
+/// instructions that set image defaults are allowed; EXPOSE is refused, as
+/// images keep no exposed ports.
+pub fn apply_changes(config: &mut ImageConfig, changes: &[String]) -> Result<()> {
+    for change in changes.iter().flat_map(|c| c.lines()) {
+        let line = change.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let keyword = line.split_whitespace().next().unwrap_or(line).to_uppercase();
+        if keyword == "EXPOSE" {
+            bail!("EXPOSE is not supported as a change command");
+        }
+        if !matches!(keyword.as_str(), "CMD" | "ENTRYPOINT" | "ENV" | "LABEL" | "USER" | "WORKDIR") {
+            bail!("{} is not a valid change command", keyword);
+        }
+
+        for instruction in parse_dockerfile(line)? {
+            match instruction {
+                Instruction::Cmd(cmd) => config.cmd = Some(cmd),
+                Instruction::Entrypoint(entrypoint) => config.entrypoint = Some(entrypoint),
+                Instruction::Env { key, value } => set_env(config.env.get_or_insert_with(Vec::new), &key, &value),
+                Instruction::Label { key, value } => {
+                    config.labels.get_or_insert_with(Default::default).insert(key, value);
+                }
+                Instruction::User(user) => config.user = Some(user),
+                Instruction::Workdir(dir) => config.working_dir = Some(dir),
+                Instruction::Expose(_) | Instruction::From { .. } | Instruction::Run(_) | Instruction::Copy { .. } => {
+                    bail!("{} is not a valid change command", keyword);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sets `key` in a list of `KEY=value` variables, replacing an earlier value.
+fn set_env(env: &mut Vec<String>, key: &str, value: &str) {
+    let var = format!("{}={}", key, value);
+    match env.iter_mut().find(|e| e.split_once('=').map_or(e.as_str(), |(name, _)| name) == key) {
+        Some(existing) => *existing = var,
+        None => env.push(var),
+    }
+}
+
+/// CMD and ENTRYPOINT arguments: the exec form (a JSON array) as is, the
+/// shell form run by `/bin/sh -c`.
+fn command_form(rest: &str) -> Vec<String> {
+    if rest.starts_with('[') {
+        if let Ok(args) = serde_json::from_str::<Vec<String>>(rest) {
+            return args;
+        }
+    }
+    vec!["/bin/sh".to_string(), "-c".to_string(), rest.to_string()]
 }
 
 /// Simple shell split: splits on whitespace, respecting quoted strings.
@@ -264,3 +352,47 @@ fn shell_split(s: &str) -> Vec<String> {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_label_pairs() {
+        let instructions = parse_dockerfile("FROM alpine\nLABEL vendor=acme \"description=web app\" version=\"1.0\"").unwrap();
+        let labels: Vec<(String, String)> = instructions
+            .into_iter()
+            .filter_map(|i| match i {
+                Instruction::Label { key, value } => Some((key, value)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            vec![
+                ("vendor".to_string(), "acme".to_string()),
+                ("description".to_string(), "web app".to_string()),
+                ("version".to_string(), "1.0".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_apply_changes() {
+        let mut config = ImageConfig {
+            env: Some(vec!["PATH=/bin".to_string(), "MODE=dev".to_string()]),
+            cmd: Some(vec!["sh".to_string()]),
+            ..Default::default()
+        };
+        let changes = vec!["CMD [\"nginx\", \"-g\", \"daemon off;\"]\nENV MODE=prod".to_string(), "WORKDIR /srv".to_string()];
+        apply_changes(&mut config, &changes).unwrap();
+        assert_eq!(config.cmd, Some(vec!["nginx".to_string(), "-g".to_string(), "daemon off;".to_string()]));
+        assert_eq!(config.env, Some(vec!["PATH=/bin".to_string(), "MODE=prod".to_string()]));
+        assert_eq!(config.working_dir.as_deref(), Some("/srv"));
+
+        let err = apply_changes(&mut config, &["RUN rm -rf /".to_string()]).unwrap_err();
+        assert_eq!(err.to_string(), "RUN is not a valid change command");
+        let err = apply_changes(&mut config, &["EXPOSE 80".to_string()]).unwrap_err();
+        assert_eq!(err.to_string(), "EXPOSE is not supported as a change command");
+    }
+}
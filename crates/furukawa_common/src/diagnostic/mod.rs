@@ -31,4 +31,14 @@ impl Error {
             source: Some(Box::new(error)),
         }
     }
+
+    /// The machine-readable code of the underlying error.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// How to fix the underlying error, if it says.
+    pub fn suggestion(&self) -> Option<&str> {
+        self.suggestion.as_deref()
+    }
 }
@@ -1,6 +1,6 @@
 //! Docker Compose v3 YAML parser and multi-container runner for HATAKE Desktop.
 //!
-//! Supports: services (image, ports, volumes, environment, depends_on, command)
+//! Supports: services (image, ports, volumes, environment, labels, depends_on, command)
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
@@ -34,6 +34,8 @@ pub struct ServiceConfig {
     #[serde(default)]
     pub environment: EnvField,
     #[serde(default)]
+    pub labels: LabelsField,
+    #[serde(default)]
     pub depends_on: DependsOnField,
     pub network_mode: Option<String>,
     pub restart: Option<String>,
@@ -87,6 +89,36 @@ impl EnvField {
     }
 }
 
+/// labels field can be a map or a list of KEY=VALUE strings
+#[derive(Debug, Default, Deserialize, Clone)]
+#[serde(untagged)]
+pub enum LabelsField {
+    #[default]
+    None,
+    Map(HashMap<String, String>),
+    List(Vec<String>),
+}
+
+impl LabelsField {
+    pub fn to_map(&self) -> HashMap<String, String> {
+        match self {
+            Self::None => HashMap::new(),
+            Self::Map(m) => m.clone(),
+            Self::List(v) => v
+                .iter()
+                .map(|l| match l.split_once('=') {
+                    Some((k, v)) => (k.to_string(), v.to_string()),
+                    None => (l.clone(), String::new()),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Labels Docker Compose puts on every container of a project.
+pub const PROJECT_LABEL: &str = "com.docker.compose.project";
+pub const SERVICE_LABEL: &str = "com.docker.compose.service";
+
 /// depends_on can be a list of service names or a map
 #[derive(Debug, Default, Deserialize, Clone)]
 #[serde(untagged)]
@@ -150,6 +182,18 @@ pub struct StartedService {
     pub container_id: String,
 }
 
+/// Splits a Compose `restart` value (`no`, `always`, `unless-stopped`,
+/// `on-failure[:N]`) into Docker's `Name` / `MaximumRetryCount`.
+fn restart_policy(restart: Option<&str>) -> (&str, i64) {
+    match restart {
+        Some(policy) => match policy.split_once(':') {
+            Some((name, count)) => (name, count.parse().unwrap_or(0)),
+            None => (policy, 0),
+        },
+        None => ("no", 0),
+    }
+}
+
 /// Request to bring up Compose services by calling the furukawad API.
 pub async fn compose_up(
     compose: &ComposeFile,
@@ -185,20 +229,29 @@ pub async fn compose_up(
         let binds: Vec<String> = service.volumes.clone();
 
         let container_name = format!("{}-{}", project_name, service_name);
+        let (restart_name, max_retries) = restart_policy(service.restart.as_deref());
+
+        let mut labels = service.labels.to_map();
+        labels.insert(PROJECT_LABEL.to_string(), project_name.to_string());
+        labels.insert(SERVICE_LABEL.to_string(), service_name.clone());
+        labels.insert("com.docker.compose.container-number".to_string(), "1".to_string());
+        labels.insert("com.docker.compose.oneoff".to_string(), "False".to_string());
 
         // POST /containers/create
         let create_body = serde_json::json!({
             "Image": image,
             "Cmd": service.command.to_vec(),
             "Env": service.environment.to_vec(),
+            "Labels": labels,
             "HostConfig": {
                 "PortBindings": port_bindings,
                 "Binds": binds,
                 "NetworkMode": service.network_mode.as_deref().unwrap_or("bridge"),
+                "RestartPolicy": {"Name": restart_name, "MaximumRetryCount": max_retries},
             }
         });
 
-        let create_resp = client.post(&format!("{}/containers/create?name={}", api_base, container_name))
+        let create_resp = client.post(format!("{}/containers/create?name={}", api_base, container_name))
             .json(&create_body)
             .send()
             .await
@@ -210,7 +263,7 @@ pub async fn compose_up(
             .to_string();
 
         // POST /containers/{id}/start
-        client.post(&format!("{}/containers/{}/start", api_base, container_id))
+        client.post(format!("{}/containers/{}/start", api_base, container_id))
             .send()
             .await
             .context("Failed to start container")?;
@@ -222,7 +275,9 @@ pub async fn compose_up(
     Ok(started)
 }
 
-/// Bring down all containers for a given project (identified by labels or naming convention).
+/// Bring down all containers for a given project, found by their project
+/// label. Containers created before labels were set are matched by their
+/// `<project>-<service>` name instead.
 pub async fn compose_down(
     compose: &ComposeFile,
     api_base: &str,
@@ -231,28 +286,47 @@ pub async fn compose_down(
     let client = reqwest::Client::new();
     let order = sorted_services(compose)?;
 
-    // Stop in reverse order
-    for service_name in order.iter().rev() {
-        let container_name = format!("{}-{}", project_name, service_name);
-        info!("[COMPOSE] Stopping service '{}'", service_name);
-
-        // GET /containers/json to find the container ID by name
-        let list_resp = client.get(&format!("{}/containers/json?all=1", api_base))
-            .send()
-            .await?
-            .json::<Vec<serde_json::Value>>()
-            .await?;
-
-        for c in &list_resp {
-            let names = c.get("Names").and_then(|n: &serde_json::Value| n.as_array());
-            let id = c.get("Id").and_then(|v: &serde_json::Value| v.as_str()).unwrap_or("");
-            if let Some(names) = names {
-                if names.iter().any(|n| n.as_str().unwrap_or("").contains(&container_name)) {
-                    let _ = client.post(&format!("{}/containers/{}/stop", api_base, id)).send().await;
-                    let _ = client.delete(&format!("{}/containers/{}", api_base, id)).send().await;
-                }
+    let containers: Vec<Value> = client.get(format!("{}/containers/json", api_base))
+        .query(&[("all", "1")])
+        .send()
+        .await
+        .context("Failed to list project containers")?
+        .json()
+        .await?;
+    let service_of = |c: &Value| -> Option<String> {
+        match c["Labels"][PROJECT_LABEL].as_str() {
+            Some(project) if project == project_name => Some(c["Labels"][SERVICE_LABEL].as_str().unwrap_or("").to_string()),
+            Some(_) => None,
+            None => {
+                let names = c["Names"].as_array()?;
+                order
+                    .iter()
+                    .find(|service| {
+                        let name = format!("/{}-{}", project_name, service);
+                        names.iter().any(|n| n.as_str() == Some(name.as_str()))
+                    })
+                    .cloned()
             }
         }
+    };
+
+    // Stop in reverse order; containers of services no longer in the file go first
+    let mut containers: Vec<(Option<usize>, String, String)> = containers
+        .iter()
+        .filter_map(|c| {
+            let service = service_of(c)?;
+            let position = order.iter().position(|s| *s == service);
+            Some((position, service, c["Id"].as_str()?.to_string()))
+        })
+        .collect();
+    containers.sort_by_key(|(position, _, _)| std::cmp::Reverse(position.map_or(usize::MAX, |p| p)));
+
+    for (_, service_name, id) in containers {
+        info!("[COMPOSE] Stopping service '{}' ({})", service_name, id);
+
+        // A 404 just means it is already gone.
+        let _ = client.post(format!("{}/containers/{}/stop", api_base, id)).send().await;
+        let _ = client.delete(format!("{}/containers/{}", api_base, id)).send().await;
     }
 
     Ok(())
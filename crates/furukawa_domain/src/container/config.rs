@@ -1,8 +1,17 @@
+use super::ContainerError;
+use crate::image::ImageConfig;
+use furukawa_common::diagnostic::Error;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     pub image: String,
+    /// `None` if not given, which inherits the image's; an empty list
+    /// overrides it with nothing.
+    #[serde(default)]
+    pub entrypoint: Option<Vec<String>>,
     pub cmd: Vec<String>,
     pub port_mappings: Vec<PortMapping>,
     #[serde(default)]
@@ -11,17 +20,446 @@ pub struct Config {
     pub env: Vec<String>,
     #[serde(default)]
     pub network: String, // e.g. "bridge", "host", "none", or custom name
+    /// Signal sent on stop before escalating to SIGKILL (default SIGTERM).
+    #[serde(default)]
+    pub stop_signal: Option<String>,
+    /// Seconds to wait after `stop_signal` before SIGKILL (default 10).
+    #[serde(default)]
+    pub stop_timeout: Option<i64>,
+    #[serde(default)]
+    pub restart_policy: RestartPolicy,
+    #[serde(default)]
+    pub log_config: LogConfig,
+    /// `user`, `user:group`, `uid` or `uid:gid`; root if unset.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Working directory inside the container; `/` if unset.
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    /// Keep stdin open so that clients can attach to it.
+    #[serde(default)]
+    pub open_stdin: bool,
+    /// Close stdin once the first attached client detaches.
+    #[serde(default)]
+    pub stdin_once: bool,
+    /// Run the process on a pseudo-terminal; its output is raw, not split
+    /// into stdout and stderr.
+    #[serde(default)]
+    pub tty: bool,
+    #[serde(default)]
+    pub resources: Resources,
+    #[serde(default)]
+    pub healthcheck: Option<Healthcheck>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             image: String::new(),
+            entrypoint: None,
             cmd: Vec::new(),
             port_mappings: Vec::new(),
             volumes: Vec::new(),
             env: Vec::new(),
             network: "bridge".to_string(),
+            stop_signal: None,
+            stop_timeout: None,
+            restart_policy: RestartPolicy::No,
+            log_config: LogConfig::default(),
+            user: None,
+            working_dir: None,
+            open_stdin: false,
+            stdin_once: false,
+            tty: false,
+            resources: Resources::default(),
+            healthcheck: None,
+            labels: HashMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Fills in what the request left unset from the image's defaults, with
+    /// Docker's rules: image variables whose name is not set are added, and
+    /// the image's `Cmd` only applies if no entrypoint was given (an
+    /// entrypoint of `[""]` resets the image's without inheriting its `Cmd`).
+    pub fn apply_image_defaults(&mut self, image: &ImageConfig) {
+        for var in image.env.iter().flatten() {
+            let name = var.split_once('=').map_or(var.as_str(), |(name, _)| name);
+            if !self.env.iter().any(|e| e.split_once('=').map_or(e.as_str(), |(n, _)| n) == name) {
+                self.env.push(var.clone());
+            }
+        }
+
+        if self.entrypoint.as_ref().is_none_or(Vec::is_empty) {
+            if self.cmd.is_empty() {
+                self.cmd = image.cmd.clone().unwrap_or_default();
+            }
+            if self.entrypoint.is_none() {
+                self.entrypoint = image.entrypoint.clone();
+            }
+        }
+        if self.entrypoint.as_ref().is_some_and(|e| e.len() == 1 && e[0].is_empty()) {
+            self.entrypoint = Some(Vec::new());
+        }
+
+        if self.working_dir.is_none() {
+            self.working_dir = image.working_dir.clone().filter(|w| !w.is_empty());
+        }
+        if self.user.is_none() {
+            self.user = image.user.clone().filter(|u| !u.is_empty());
+        }
+        for (key, value) in image.labels.iter().flatten() {
+            self.labels.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+    }
+
+    /// The defaults an image committed from the container gives its own
+    /// containers.
+    pub fn image_config(&self) -> ImageConfig {
+        ImageConfig {
+            env: Some(self.env.clone()),
+            entrypoint: self.entrypoint.clone(),
+            cmd: Some(self.cmd.clone()),
+            working_dir: self.working_dir.clone(),
+            user: self.user.clone(),
+            labels: Some(self.labels.clone()),
+        }
+    }
+
+    /// The process to run: the entrypoint followed by the command.
+    pub fn command(&self) -> Vec<String> {
+        self.entrypoint.iter().flatten().chain(&self.cmd).cloned().collect()
+    }
+}
+
+/// Options of the `json-file` log driver (Docker's `HostConfig.LogConfig`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogConfig {
+    /// Rotate once the log reaches this many bytes; unlimited if `None`.
+    pub max_size: Option<u64>,
+    /// Number of files kept, including the one being written.
+    pub max_file: u32,
+    /// Gzip rotated files.
+    pub compress: bool,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            max_size: None,
+            max_file: 1,
+            compress: false,
+        }
+    }
+}
+
+impl LogConfig {
+    pub const DRIVER: &'static str = "json-file";
+
+    /// Builds the config from Docker's `Type` / `Config` pair, validating the
+    /// options the way the `json-file` driver does.
+    pub fn parse(driver: &str, options: &HashMap<String, String>) -> Result<Self, Error> {
+        let invalid = |reason: String| Err(Error::new(ContainerError::InvalidLogConfig(reason)));
+
+        if !driver.is_empty() && driver != Self::DRIVER {
+            return invalid(format!("logging driver '{}' is not supported, only {}", driver, Self::DRIVER));
+        }
+
+        let mut config = Self::default();
+        for (key, value) in options {
+            match key.as_str() {
+                "max-size" => match parse_size(value) {
+                    Some(size) => config.max_size = (size > 0).then_some(size),
+                    None => return invalid(format!("invalid max-size '{}'", value)),
+                },
+                "max-file" => match value.parse::<u32>() {
+                    Ok(n) if n >= 1 => config.max_file = n,
+                    _ => return invalid(format!("max-file must be a number of at least 1, got '{}'", value)),
+                },
+                "compress" => match value.as_str() {
+                    "1" | "true" | "TRUE" | "True" => config.compress = true,
+                    "0" | "false" | "FALSE" | "False" => config.compress = false,
+                    _ => return invalid(format!("invalid compress value '{}'", value)),
+                },
+                other => return invalid(format!("unknown log opt '{}' for {} log driver", other, Self::DRIVER)),
+            }
+        }
+
+        if config.max_file > 1 && config.max_size.is_none() {
+            return invalid("max-file cannot be set without max-size".to_string());
+        }
+        if config.compress && config.max_file < 2 {
+            return invalid("compress cannot be true when max-file is less than 2".to_string());
+        }
+        Ok(config)
+    }
+
+    /// The options in Docker's `LogConfig.Config` form.
+    pub fn options(&self) -> HashMap<String, String> {
+        let mut options = HashMap::new();
+        if let Some(max_size) = self.max_size {
+            options.insert("max-size".to_string(), max_size.to_string());
+            options.insert("max-file".to_string(), self.max_file.to_string());
+        }
+        if self.compress {
+            options.insert("compress".to_string(), "true".to_string());
+        }
+        options
+    }
+}
+
+/// Parses a size such as `10m`, `512k` or `1.5g` (binary units, as Docker's
+/// `RAMInBytes`). `-1` means unlimited and is returned as 0.
+fn parse_size(value: &str) -> Option<u64> {
+    if value == "-1" {
+        return Some(0);
+    }
+    let lower = value.trim().to_ascii_lowercase();
+    let lower = lower.strip_suffix('b').unwrap_or(&lower);
+    let lower = lower.strip_suffix('i').unwrap_or(lower);
+    let (number, multiplier) = match lower.chars().last()? {
+        'k' => (&lower[..lower.len() - 1], 1u64 << 10),
+        'm' => (&lower[..lower.len() - 1], 1 << 20),
+        'g' => (&lower[..lower.len() - 1], 1 << 30),
+        't' => (&lower[..lower.len() - 1], 1 << 40),
+        _ => (lower, 1),
+    };
+    let number: f64 = number.trim().parse().ok()?;
+    if !number.is_finite() || number < 0.0 {
+        return None;
+    }
+    Some((number * multiplier as f64) as u64)
+}
+
+/// Resource limits (Docker's `HostConfig` fields of the same names). `None`
+/// means unlimited; Docker's 0 is stored as `None`.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Resources {
+    /// Memory limit in bytes.
+    pub memory: Option<i64>,
+    /// Memory plus swap in bytes, `-1` for unlimited swap. Defaults to twice
+    /// `memory`, as in Docker.
+    pub memory_swap: Option<i64>,
+    /// CPU quota in units of 10^-9 CPUs.
+    pub nano_cpus: Option<i64>,
+    /// Relative CPU weight; Docker's default is 1024.
+    pub cpu_shares: Option<i64>,
+    /// CPUs the container may use, e.g. `0-3` or `0,2`.
+    pub cpuset_cpus: Option<String>,
+    /// Maximum number of processes; zero or negative means unlimited.
+    pub pids_limit: Option<i64>,
+}
+
+impl Resources {
+    /// Docker's minimum memory limit.
+    pub const MIN_MEMORY: i64 = 6 * 1024 * 1024;
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Checks the limits the way Docker does before creating a container.
+    /// Limits that depend on the host (e.g. the number of CPUs) are checked
+    /// by the runtime.
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |reason: &str| Err(Error::new(ContainerError::InvalidResources(reason.to_string())));
+
+        if self.memory.is_some_and(|m| m < Self::MIN_MEMORY) {
+            return invalid("Minimum memory limit allowed is 6MB");
+        }
+        match (self.memory, self.memory_swap) {
+            (None, Some(_)) => return invalid("You should always set the Memory limit when using Memoryswap limit, see usage"),
+            (Some(memory), Some(swap)) if swap != -1 && swap < memory => {
+                return invalid("Minimum memoryswap limit should be larger than memory limit, see usage");
+            }
+            _ => {}
+        }
+        if self.nano_cpus.is_some_and(|n| n < 0) {
+            return invalid("NanoCPUs cannot be negative");
+        }
+        if self.cpu_shares.is_some_and(|s| s < 0) {
+            return invalid("CPU shares cannot be negative");
+        }
+        if let Some(cpus) = &self.cpuset_cpus {
+            if parse_cpu_list(cpus).is_none() {
+                return invalid(&format!("Invalid value {} for cpuset cpus", cpus));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses a CPU list such as `0-3,5` into the CPU numbers it names.
+pub fn parse_cpu_list(list: &str) -> Option<Vec<u32>> {
+    let mut cpus = Vec::new();
+    for part in list.split(',') {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.parse::<u32>().ok()?, end.parse::<u32>().ok()?),
+            None => {
+                let cpu = part.parse().ok()?;
+                (cpu, cpu)
+            }
+        };
+        if start > end {
+            return None;
+        }
+        cpus.extend(start..=end);
+    }
+    Some(cpus)
+}
+
+/// How to probe the container's health (Docker's `Healthcheck`). Durations
+/// are in nanoseconds, as in the API; zero means Docker's default.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Healthcheck {
+    /// `["NONE"]`, `["CMD", arg...]` or `["CMD-SHELL", command]`.
+    pub test: Vec<String>,
+    /// Time between two probes.
+    pub interval: i64,
+    /// Time a probe may take before it counts as failed.
+    pub timeout: i64,
+    /// Time after the start during which failures do not count.
+    pub start_period: i64,
+    /// Consecutive failures needed to report the container unhealthy.
+    pub retries: i64,
+}
+
+impl Healthcheck {
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+    pub const DEFAULT_RETRIES: u32 = 3;
+    /// Docker's minimum for the durations (1ms).
+    const MIN_DURATION: i64 = 1_000_000;
+
+    /// Checks the settings the way Docker does before creating a container.
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |reason: String| Err(Error::new(ContainerError::InvalidHealthcheck(reason)));
+
+        match self.test.first().map(String::as_str) {
+            None | Some("NONE") => {}
+            Some("CMD") | Some("CMD-SHELL") if self.test.len() > 1 => {}
+            Some("CMD") | Some("CMD-SHELL") => return invalid(format!("{} in Healthcheck needs a command", self.test[0])),
+            Some(other) => return invalid(format!("Unknown Healthcheck Test type '{}'", other)),
+        }
+        for (name, value) in [("Interval", self.interval), ("Timeout", self.timeout), ("StartPeriod", self.start_period)] {
+            if value != 0 && value < Self::MIN_DURATION {
+                return invalid(format!("{} in Healthcheck cannot be less than 1ms", name));
+            }
+        }
+        if self.retries < 0 {
+            return invalid("Retries in Healthcheck cannot be negative".to_string());
+        }
+        Ok(())
+    }
+
+    /// The command to run inside the container, or `None` if checks are
+    /// disabled.
+    pub fn command(&self) -> Option<Vec<String>> {
+        match self.test.split_first() {
+            Some((kind, args)) if kind == "CMD" && !args.is_empty() => Some(args.to_vec()),
+            Some((kind, args)) if kind == "CMD-SHELL" && !args.is_empty() => {
+                Some(vec!["/bin/sh".to_string(), "-c".to_string(), args.join(" ")])
+            }
+            _ => None,
+        }
+    }
+
+    pub fn interval(&self) -> Duration {
+        nanos_or(self.interval, Self::DEFAULT_INTERVAL)
+    }
+
+    pub fn timeout(&self) -> Duration {
+        nanos_or(self.timeout, Self::DEFAULT_TIMEOUT)
+    }
+
+    pub fn start_period(&self) -> Duration {
+        nanos_or(self.start_period, Duration::ZERO)
+    }
+
+    pub fn retries(&self) -> u32 {
+        match self.retries {
+            0 => Self::DEFAULT_RETRIES,
+            n => u32::try_from(n).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+fn nanos_or(nanos: i64, default: Duration) -> Duration {
+    match u64::try_from(nanos) {
+        Ok(nanos) if nanos > 0 => Duration::from_nanos(nanos),
+        _ => default,
+    }
+}
+
+/// What to do when the container's process exits (Docker's `RestartPolicy`).
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    No,
+    /// Restart on non-zero exit, at most `max_retries` times (0 = unlimited).
+    OnFailure { max_retries: u32 },
+    Always,
+    UnlessStopped,
+}
+
+impl RestartPolicy {
+    /// Builds a policy from Docker's `Name` / `MaximumRetryCount` pair.
+    pub fn parse(name: &str, maximum_retry_count: i64) -> Result<Self, Error> {
+        let invalid = |reason: &str| Err(Error::new(ContainerError::InvalidRestartPolicy(reason.to_string())));
+
+        if maximum_retry_count < 0 {
+            return invalid("maximum retry count cannot be negative");
+        }
+        let policy = match name {
+            "" | "no" => Self::No,
+            "always" => Self::Always,
+            "unless-stopped" => Self::UnlessStopped,
+            "on-failure" => Self::OnFailure {
+                max_retries: u32::try_from(maximum_retry_count).unwrap_or(u32::MAX),
+            },
+            other => return invalid(&format!("unknown policy '{}'", other)),
+        };
+        if maximum_retry_count > 0 && !matches!(policy, Self::OnFailure { .. }) {
+            return invalid(&format!("maximum retry count cannot be used with restart policy '{}'", name));
+        }
+        Ok(policy)
+    }
+
+    /// Docker's name for the policy.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::No => "no",
+            Self::OnFailure { .. } => "on-failure",
+            Self::Always => "always",
+            Self::UnlessStopped => "unless-stopped",
+        }
+    }
+
+    /// Whether a container whose process just exited should be restarted.
+    /// `restart_count` is the number of restarts the policy already made.
+    pub fn should_restart(&self, exit_code: i32, restart_count: u32, manually_stopped: bool) -> bool {
+        if manually_stopped {
+            return false;
+        }
+        match self {
+            Self::No => false,
+            Self::Always | Self::UnlessStopped => true,
+            Self::OnFailure { max_retries } => {
+                exit_code != 0 && (*max_retries == 0 || restart_count < *max_retries)
+            }
+        }
+    }
+
+    /// Whether a stopped container should be started when the daemon starts.
+    pub fn start_on_boot(&self, manually_stopped: bool) -> bool {
+        match self {
+            Self::Always => true,
+            Self::UnlessStopped => !manually_stopped,
+            Self::No | Self::OnFailure { .. } => false,
         }
     }
 }
@@ -5,25 +5,84 @@ use thiserror::Error;
 pub enum ContainerError {
     #[error("Invalid transition")]
     InvalidTransition,
+    #[error("Invalid container name: {0}")]
+    InvalidName(String),
+    #[error("The container name \"/{0}\" is already in use")]
+    NameConflict(String),
+    #[error("Multiple containers match the ID prefix: {0}")]
+    AmbiguousReference(String),
+    #[error("Invalid restart policy: {0}")]
+    InvalidRestartPolicy(String),
+    #[error("Invalid log config: {0}")]
+    InvalidLogConfig(String),
+    #[error("{0}")]
+    InvalidResources(String),
+    #[error("{0}")]
+    InvalidHealthcheck(String),
 }
 
 impl Diagnosable for ContainerError {
     fn code(&self) -> String {
-        "CONTAINER_INVALID_TRANSITION".to_string()
+        match self {
+            Self::InvalidTransition => "CONTAINER_INVALID_TRANSITION".to_string(),
+            Self::InvalidName(_) => "CONTAINER_INVALID_NAME".to_string(),
+            Self::NameConflict(_) => "CONTAINER_NAME_CONFLICT".to_string(),
+            Self::AmbiguousReference(_) => "CONTAINER_AMBIGUOUS_REFERENCE".to_string(),
+            Self::InvalidRestartPolicy(_) => "CONTAINER_INVALID_RESTART_POLICY".to_string(),
+            Self::InvalidLogConfig(_) => "CONTAINER_INVALID_LOG_CONFIG".to_string(),
+            Self::InvalidResources(_) => "CONTAINER_INVALID_RESOURCES".to_string(),
+            Self::InvalidHealthcheck(_) => "CONTAINER_INVALID_HEALTHCHECK".to_string(),
+        }
     }
     fn suggestion(&self) -> Option<String> {
-        Some("Check container state before operation".to_string())
+        match self {
+            Self::InvalidTransition => Some("Check container state before operation".to_string()),
+            Self::InvalidName(_) => Some("Names must match [a-zA-Z0-9][a-zA-Z0-9_.-]+".to_string()),
+            Self::NameConflict(_) => Some("Remove or rename the existing container, or choose another name".to_string()),
+            Self::AmbiguousReference(_) => Some("Use a longer ID prefix or the container name".to_string()),
+            Self::InvalidRestartPolicy(_) => Some("Use no, always, unless-stopped or on-failure[:max-retries]".to_string()),
+            Self::InvalidLogConfig(_) => Some("Use the json-file driver with max-size, max-file and compress".to_string()),
+            Self::InvalidResources(_) => Some("Check the Memory, MemorySwap, NanoCpus, CpuShares, CpusetCpus and PidsLimit values".to_string()),
+            Self::InvalidHealthcheck(_) => Some("Test must be [\"NONE\"], [\"CMD\", ...] or [\"CMD-SHELL\", command]; durations are in nanoseconds".to_string()),
+        }
     }
 }
 
+/// Validates a user-supplied container name (Docker rules) and returns it
+/// without the optional leading `/`.
+pub fn validate_name(name: &str) -> Result<String, Error> {
+    let name = name.strip_prefix('/').unwrap_or(name);
+    let mut chars = name.chars();
+    let valid = name.len() >= 2
+        && chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(Error::new(ContainerError::InvalidName(name.to_string())))
+    }
+}
+
+/// Name given to containers created without one.
+pub fn default_name(id: &str) -> String {
+    let short: String = id.chars().filter(|c| *c != '-').take(12).collect();
+    format!("furukawa-{}", short)
+}
+
 pub mod config;
-pub use config::Config;
+pub use config::{Config, Healthcheck, LogConfig, Resources, RestartPolicy};
 pub mod state_serde;
+pub mod status;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Container<S> {
     id: String,
+    name: String,
     config: Config,
+    created_at: time::OffsetDateTime,
+    /// Restarts made by the restart policy since the last manual start.
+    restart_count: u32,
     state: S,
 }
 
@@ -31,6 +90,7 @@ pub struct Container<S> {
 pub enum AnyContainer {
     Created(Container<Created>),
     Running(Container<Running>),
+    Paused(Container<Paused>),
     Stopped(Container<Stopped>),
 }
 
@@ -39,25 +99,64 @@ impl AnyContainer {
         match self {
             Self::Created(c) => c.id(),
             Self::Running(c) => c.id(),
+            Self::Paused(c) => c.id(),
             Self::Stopped(c) => c.id(),
         }
     }
 
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Created(c) => c.name(),
+            Self::Running(c) => c.name(),
+            Self::Paused(c) => c.name(),
+            Self::Stopped(c) => c.name(),
+        }
+    }
+
     pub fn config(&self) -> &Config {
         match self {
             Self::Created(c) => c.config(),
             Self::Running(c) => c.config(),
+            Self::Paused(c) => c.config(),
             Self::Stopped(c) => c.config(),
         }
     }
 
+    pub fn created_at(&self) -> time::OffsetDateTime {
+        match self {
+            Self::Created(c) => c.created_at(),
+            Self::Running(c) => c.created_at(),
+            Self::Paused(c) => c.created_at(),
+            Self::Stopped(c) => c.created_at(),
+        }
+    }
+
+    pub fn restart_count(&self) -> u32 {
+        match self {
+            Self::Created(c) => c.restart_count(),
+            Self::Running(c) => c.restart_count(),
+            Self::Paused(c) => c.restart_count(),
+            Self::Stopped(c) => c.restart_count(),
+        }
+    }
+
     pub fn status(&self) -> &'static str {
         match self {
             Self::Created(_) => "created",
             Self::Running(_) => "running",
+            Self::Paused(_) => "paused",
             Self::Stopped(_) => "exited",
         }
     }
+
+    /// The container's process, while it has one (running or paused).
+    pub fn pid(&self) -> Option<u32> {
+        match self {
+            Self::Running(c) => Some(c.state().pid),
+            Self::Paused(c) => Some(c.state().pid),
+            _ => None,
+        }
+    }
 }
 
 impl<S> Container<S> {
@@ -65,10 +164,35 @@ impl<S> Container<S> {
         &self.id
     }
 
+    /// Container name, without Docker's leading `/`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn config(&self) -> &Config {
         &self.config
     }
 
+    pub fn created_at(&self) -> time::OffsetDateTime {
+        self.created_at
+    }
+
+    /// Sets the creation time of a container restored from storage.
+    pub fn with_created_at(mut self, created_at: time::OffsetDateTime) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    pub fn restart_count(&self) -> u32 {
+        self.restart_count
+    }
+
+    /// Sets the restart count of a container restored from storage.
+    pub fn with_restart_count(mut self, restart_count: u32) -> Self {
+        self.restart_count = restart_count;
+        self
+    }
+
     pub fn state(&self) -> &S {
         &self.state
     }
@@ -83,67 +207,224 @@ pub struct Created;
 pub struct Running {
     pub pid: u32,
     pub started_at: time::OffsetDateTime,
+    /// OS-specific start time of `pid`, so a later process that reuses the
+    /// PID is not mistaken for the container's (unknown for older rows).
+    pub process_start: Option<String>,
+}
+
+/// A running container whose processes are frozen. The process is the same
+/// as in the `Running` state it came from.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Paused {
+    pub pid: u32,
+    pub started_at: time::OffsetDateTime,
+    pub process_start: Option<String>,
 }
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Stopped {
+    /// Start of the run that ended (unknown for rows older than this field).
+    pub started_at: Option<time::OffsetDateTime>,
     pub finished_at: time::OffsetDateTime,
     pub exit_code: i32,
+    /// Stopped through the API (stop/kill) rather than exiting on its own;
+    /// restart policies leave such containers alone.
+    pub manually_stopped: bool,
+}
+
+impl Stopped {
+    /// Exit code recorded when the process ended while nobody was watching
+    /// (e.g. the daemon crashed), same as Docker.
+    pub const UNKNOWN_EXIT_CODE: i32 = 255;
 }
 
 impl Container<Created> {
-    pub fn new(id: String, config: Config) -> self::Container<Created> {
+    pub fn new(id: String, name: String, config: Config) -> self::Container<Created> {
         Container {
             id,
+            name,
             config,
+            created_at: time::OffsetDateTime::now_utc(),
+            restart_count: 0,
             state: Created,
         }
     }
 }
 
 impl Container<Running> {
-    pub fn restore(id: String, config: Config, state: Running) -> self::Container<Running> {
+    pub fn restore(id: String, name: String, config: Config, state: Running) -> self::Container<Running> {
         Container {
             id,
+            name,
             config,
+            created_at: time::OffsetDateTime::now_utc(),
+            restart_count: 0,
             state,
         }
     }
 
-    pub async fn stop(self, runtime: &(impl runtime::ContainerRuntime + ?Sized)) -> Result<Container<Stopped>, Error> {
-        runtime.stop(&self).await?;
-        
+    pub async fn stop(
+        self,
+        runtime: &(impl runtime::ContainerRuntime + ?Sized),
+        options: &runtime::StopOptions,
+    ) -> Result<Container<Stopped>, Error> {
+        let exit_code = runtime.stop(&self, options).await?;
+        Ok(self.finish(exit_code, true))
+    }
+
+    /// Freezes the container's processes.
+    pub async fn pause(self, runtime: &(impl runtime::ContainerRuntime + ?Sized)) -> Result<Container<Paused>, Error> {
+        runtime.pause(&self).await?;
+        let Running { pid, started_at, process_start } = self.state;
         Ok(Container {
             id: self.id,
+            name: self.name,
+            config: self.config,
+            created_at: self.created_at,
+            restart_count: self.restart_count,
+            state: Paused { pid, started_at, process_start },
+        })
+    }
+
+    /// Records that the container's process has ended on its own.
+    pub fn exited(self, exit_code: i32) -> Container<Stopped> {
+        self.finish(exit_code, false)
+    }
+
+    /// Records that the process ended because it was stopped or killed on
+    /// request.
+    pub fn stopped_manually(self, exit_code: i32) -> Container<Stopped> {
+        self.finish(exit_code, true)
+    }
+
+    fn finish(self, exit_code: i32, manually_stopped: bool) -> Container<Stopped> {
+        Container {
+            id: self.id,
+            name: self.name,
             config: self.config,
+            created_at: self.created_at,
+            restart_count: self.restart_count,
             state: Stopped {
+                started_at: Some(self.state.started_at),
                 finished_at: time::OffsetDateTime::now_utc(),
-                exit_code: 0,
+                exit_code,
+                manually_stopped,
             },
-        })
+        }
     }
 }
 
 
+impl Container<Paused> {
+    pub fn restore(id: String, name: String, config: Config, state: Paused) -> self::Container<Paused> {
+        Container {
+            id,
+            name,
+            config,
+            created_at: time::OffsetDateTime::now_utc(),
+            restart_count: 0,
+            state,
+        }
+    }
+
+    /// Stops the container, thawing its processes once the stop signal has
+    /// been sent.
+    pub async fn stop(
+        self,
+        runtime: &(impl runtime::ContainerRuntime + ?Sized),
+        options: &runtime::StopOptions,
+    ) -> Result<Container<Stopped>, Error> {
+        let exit_code = runtime.stop_paused(&self, options).await?;
+        Ok(self.stopped_manually(exit_code))
+    }
+
+    /// Thaws the container's processes.
+    pub async fn unpause(self, runtime: &(impl runtime::ContainerRuntime + ?Sized)) -> Result<Container<Running>, Error> {
+        runtime.unpause(&self).await?;
+        Ok(self.thawed())
+    }
+
+    /// Records that the container's process has ended while frozen (e.g.
+    /// killed from outside or by the OOM killer).
+    pub fn exited(self, exit_code: i32) -> Container<Stopped> {
+        self.thawed().finish(exit_code, false)
+    }
+
+    /// Records that the process ended because it was killed on request.
+    pub fn stopped_manually(self, exit_code: i32) -> Container<Stopped> {
+        self.thawed().finish(exit_code, true)
+    }
+
+    fn thawed(self) -> Container<Running> {
+        let Paused { pid, started_at, process_start } = self.state;
+        Container {
+            id: self.id,
+            name: self.name,
+            config: self.config,
+            created_at: self.created_at,
+            restart_count: self.restart_count,
+            state: Running { pid, started_at, process_start },
+        }
+    }
+}
+
 impl Container<Created> {
     pub async fn start(self, runtime: &(impl runtime::ContainerRuntime + ?Sized)) -> Result<Container<Running>, Error> {
         let running_state = runtime.start(&self).await?;
         Ok(Container {
             id: self.id,
+            name: self.name,
             config: self.config,
+            created_at: self.created_at,
+            restart_count: self.restart_count,
             state: running_state,
         })
     }
 }
 
 impl Container<Stopped> {
-    pub fn restore(id: String, config: Config, state: Stopped) -> self::Container<Stopped> {
+    pub fn restore(id: String, name: String, config: Config, state: Stopped) -> self::Container<Stopped> {
         Container {
             id,
+            name,
             config,
+            created_at: time::OffsetDateTime::now_utc(),
+            restart_count: 0,
             state,
         }
     }
+
+    /// Starts the container again with the same id and config. A manual
+    /// start resets the restart policy's count.
+    pub async fn start(self, runtime: &(impl runtime::ContainerRuntime + ?Sized)) -> Result<Container<Running>, Error> {
+        self.start_with_restart_count(runtime, 0).await
+    }
+
+    /// Marks the container as stopped on request, which cancels a restart
+    /// its policy may have scheduled.
+    pub fn cancel_restart(mut self) -> Self {
+        self.state.manually_stopped = true;
+        self
+    }
+
+    /// Starts the container again on behalf of its restart policy.
+    pub async fn auto_restart(self, runtime: &(impl runtime::ContainerRuntime + ?Sized)) -> Result<Container<Running>, Error> {
+        let restart_count = self.restart_count.saturating_add(1);
+        self.start_with_restart_count(runtime, restart_count).await
+    }
+
+    async fn start_with_restart_count(
+        self,
+        runtime: &(impl runtime::ContainerRuntime + ?Sized),
+        restart_count: u32,
+    ) -> Result<Container<Running>, Error> {
+        let created_at = self.created_at;
+        Container::new(self.id, self.name, self.config)
+            .with_created_at(created_at)
+            .with_restart_count(restart_count)
+            .start(runtime)
+            .await
+    }
 }
 
 #[cfg(test)]
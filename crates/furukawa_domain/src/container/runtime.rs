@@ -1,7 +1,16 @@
-use super::{Container, Created, Running};
+use super::{Container, Created, Paused, Running};
 use async_trait::async_trait;
 use furukawa_common::Result;
 
+/// Overrides for a single stop request. Unset fields fall back to the
+/// container's `stop_signal` / `stop_timeout`, then to SIGTERM / 10s.
+#[derive(Debug, Clone, Default)]
+pub struct StopOptions {
+    pub signal: Option<String>,
+    /// Grace period in seconds; negative waits indefinitely.
+    pub timeout: Option<i64>,
+}
+
 #[async_trait]
 pub trait ContainerRuntime: Send + Sync {
     /// Starts a container.
@@ -10,6 +19,33 @@ pub trait ContainerRuntime: Send + Sync {
     async fn start(&self, container: &Container<Created>) -> Result<Running>;
     
     /// Stops a running container.
-    /// This attempts to gracefully shut down the process.
-    async fn stop(&self, container: &Container<Running>) -> Result<()>;
+    /// This sends the stop signal, waits for the grace period and only then
+    /// kills the process. It returns the exit code the process ended with.
+    async fn stop(&self, container: &Container<Running>, options: &StopOptions) -> Result<i32>;
+
+    /// Sends a signal (e.g. "SIGKILL", "HUP" or "9") to the container's process.
+    /// The container stays `Running` until its exit is observed.
+    async fn kill(&self, container: &Container<Running>, signal: &str) -> Result<()>;
+
+    /// Stops a paused container like [`stop`](Self::stop). The stop signal
+    /// is sent while it is frozen, then its processes are thawed to handle
+    /// it.
+    async fn stop_paused(&self, container: &Container<Paused>, options: &StopOptions) -> Result<i32>;
+
+    /// Sends a signal to a paused container's process. The container is
+    /// thawed after SIGKILL or its stop signal, so that it ends; other
+    /// signals are handled once it is unpaused.
+    async fn kill_paused(&self, container: &Container<Paused>, signal: &str) -> Result<()>;
+
+    /// Freezes every process of the container.
+    async fn pause(&self, container: &Container<Running>) -> Result<()>;
+
+    /// Thaws the processes frozen by [`pause`](Self::pause).
+    async fn unpause(&self, container: &Container<Paused>) -> Result<()>;
+
+    /// Resizes the container's terminal. Containers without a TTY, and
+    /// runtimes that cannot allocate one, have nothing to resize.
+    async fn resize(&self, _container: &Container<Running>, _height: u16, _width: u16) -> Result<()> {
+        Ok(())
+    }
 }
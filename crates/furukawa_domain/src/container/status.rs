@@ -0,0 +1,80 @@
+//! Docker-style human readable container status ("Up 5 minutes",
+//! "Exited (1) 2 hours ago").
+
+use super::AnyContainer;
+use time::{Duration, OffsetDateTime};
+
+/// Formats a duration the way Docker's `HumanDuration` does.
+pub fn human_duration(d: Duration) -> String {
+    let seconds = d.whole_seconds();
+    if seconds < 1 {
+        return "Less than a second".to_string();
+    }
+    if seconds == 1 {
+        return "1 second".to_string();
+    }
+    if seconds < 60 {
+        return format!("{} seconds", seconds);
+    }
+
+    let minutes = d.whole_minutes();
+    if minutes == 1 {
+        return "About a minute".to_string();
+    }
+    if minutes < 60 {
+        return format!("{} minutes", minutes);
+    }
+
+    // Hours are rounded, like Docker does.
+    let hours = (d.as_seconds_f64() / 3600.0 + 0.5) as i64;
+    if hours == 1 {
+        "About an hour".to_string()
+    } else if hours < 48 {
+        format!("{} hours", hours)
+    } else if hours < 24 * 7 * 2 {
+        format!("{} days", hours / 24)
+    } else if hours < 24 * 30 * 2 {
+        format!("{} weeks", hours / 24 / 7)
+    } else if hours < 24 * 365 * 2 {
+        format!("{} months", hours / 24 / 30)
+    } else {
+        format!("{} years", d.whole_hours() / 24 / 365)
+    }
+}
+
+impl AnyContainer {
+    /// The `Status` text of `docker ps`, relative to `now`.
+    pub fn status_text(&self, now: OffsetDateTime) -> String {
+        match self {
+            Self::Created(_) => "Created".to_string(),
+            Self::Running(c) => format!("Up {}", human_duration(now - c.state().started_at)),
+            Self::Paused(c) => format!("Up {} (Paused)", human_duration(now - c.state().started_at)),
+            Self::Stopped(c) => format!(
+                "Exited ({}) {} ago",
+                c.state().exit_code,
+                human_duration(now - c.state().finished_at)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::human_duration;
+    use time::Duration;
+
+    #[test]
+    fn test_human_duration_matches_docker() {
+        assert_eq!(human_duration(Duration::milliseconds(300)), "Less than a second");
+        assert_eq!(human_duration(Duration::seconds(1)), "1 second");
+        assert_eq!(human_duration(Duration::seconds(42)), "42 seconds");
+        assert_eq!(human_duration(Duration::seconds(90)), "About a minute");
+        assert_eq!(human_duration(Duration::minutes(5)), "5 minutes");
+        assert_eq!(human_duration(Duration::minutes(70)), "About an hour");
+        assert_eq!(human_duration(Duration::hours(2)), "2 hours");
+        assert_eq!(human_duration(Duration::days(3)), "3 days");
+        assert_eq!(human_duration(Duration::days(21)), "3 weeks");
+        assert_eq!(human_duration(Duration::days(90)), "3 months");
+        assert_eq!(human_duration(Duration::days(800)), "2 years");
+    }
+}
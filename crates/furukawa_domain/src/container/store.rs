@@ -2,15 +2,41 @@ use super::Container;
 use async_trait::async_trait;
 use furukawa_common::Result;
 
+/// Criteria a store applies itself when listing containers. Each non-empty
+/// list matches any of its values; all criteria must hold. Results come
+/// newest first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ContainerQuery {
+    /// Only running and paused containers.
+    pub running_only: bool,
+    /// Docker statuses (`created`, `running`, `paused`, `exited`...).
+    pub statuses: Vec<String>,
+    /// ID prefixes.
+    pub ids: Vec<String>,
+    /// Image references, matched exactly against `Config.image`.
+    pub images: Vec<String>,
+    /// Exit codes of stopped containers.
+    pub exit_codes: Vec<i32>,
+    pub created_before: Option<time::OffsetDateTime>,
+    pub created_after: Option<time::OffsetDateTime>,
+    pub limit: Option<usize>,
+}
+
 #[async_trait]
 pub trait ContainerStore: Send + Sync {
     async fn save(&self, container: &Container<super::Created>) -> Result<()>;
     async fn save_running(&self, container: &Container<super::Running>) -> Result<()>;
+    async fn save_paused(&self, container: &Container<super::Paused>) -> Result<()>;
     async fn save_stopped(&self, container: &Container<super::Stopped>) -> Result<()>;
     async fn list(&self) -> Result<Vec<super::AnyContainer>>;
+    /// The containers matching `query`, newest first.
+    async fn query(&self, query: &ContainerQuery) -> Result<Vec<super::AnyContainer>>;
     async fn get_any(&self, id: &str) -> Result<Option<super::AnyContainer>>;
-    async fn get(&self, id: &str) -> Result<Option<Container<super::Created>>>; 
+    async fn get(&self, id: &str) -> Result<Option<Container<super::Created>>>;
     async fn get_running(&self, id: &str) -> Result<Option<Container<super::Running>>>;
     async fn delete(&self, id: &str) -> Result<()>;
     async fn get_status(&self, id: &str) -> Result<Option<String>>;
+    /// Resolves a full ID, a name (with or without leading `/`) or a unique
+    /// ID prefix to the container's full ID.
+    async fn resolve_id(&self, reference: &str) -> Result<Option<String>>;
 }
@@ -1,5 +1,6 @@
-use super::{Container, Created, Running, Config};
-use super::runtime::ContainerRuntime;
+use super::{Container, Created, Paused, Running, Config, Healthcheck, LogConfig, Resources, RestartPolicy, validate_name, default_name};
+use crate::image::ImageConfig;
+use super::runtime::{ContainerRuntime, StopOptions};
 use furukawa_common::Result;
 use async_trait::async_trait;
 
@@ -11,9 +12,25 @@ impl ContainerRuntime for MockRuntime {
         Ok(Running {
             pid: 1234,
             started_at: time::OffsetDateTime::now_utc(),
+            process_start: None,
         })
     }
-    async fn stop(&self, _container: &Container<Running>) -> Result<()> {
+    async fn stop(&self, _container: &Container<Running>, _options: &StopOptions) -> Result<i32> {
+        Ok(143)
+    }
+    async fn kill(&self, _container: &Container<Running>, _signal: &str) -> Result<()> {
+        Ok(())
+    }
+    async fn stop_paused(&self, _container: &Container<Paused>, _options: &StopOptions) -> Result<i32> {
+        Ok(143)
+    }
+    async fn kill_paused(&self, _container: &Container<Paused>, _signal: &str) -> Result<()> {
+        Ok(())
+    }
+    async fn pause(&self, _container: &Container<Running>) -> Result<()> {
+        Ok(())
+    }
+    async fn unpause(&self, _container: &Container<Paused>) -> Result<()> {
         Ok(())
     }
 }
@@ -23,8 +40,9 @@ fn test_create_container() {
     let config = Config {
         image: "test-image".to_string(),
         cmd: vec!["test-cmd".to_string()],
+        ..Default::default()
     };
-    let c = Container::new("test-id".to_string(), config);
+    let c = Container::new("test-id".to_string(), "test-name".to_string(), config);
     assert_eq!(*c.state(), Created);
 }
 
@@ -33,9 +51,184 @@ async fn test_valid_lifecycle() {
     let config = Config {
         image: "test-image".to_string(),
         cmd: vec!["test-cmd".to_string()],
+        ..Default::default()
     };
-    let c = Container::new("test-id".to_string(), config);
+    let c = Container::new("test-id".to_string(), "test-name".to_string(), config);
     let runtime = MockRuntime;
     let running = c.start(&runtime).await.expect("Should be able to start created container");
-    let _stopped = running.stop(&runtime).await.expect("Should be able to stop running container");
+    let stopped = running.stop(&runtime, &StopOptions::default()).await.expect("Should be able to stop running container");
+    assert_eq!(stopped.state().exit_code, 143);
+    let restarted = stopped.start(&runtime).await.expect("Should be able to restart stopped container");
+    assert_eq!(restarted.id(), "test-id");
+}
+
+#[tokio::test]
+async fn test_pause_keeps_the_process() {
+    let running = Container::new("test-id".to_string(), "test-name".to_string(), Config::default())
+        .start(&MockRuntime)
+        .await
+        .expect("Should be able to start created container");
+    let started_at = running.state().started_at;
+
+    let paused = running.pause(&MockRuntime).await.expect("Should be able to pause running container");
+    assert_eq!(*paused.state(), Paused { pid: 1234, started_at, process_start: None });
+    let running = paused.unpause(&MockRuntime).await.expect("Should be able to unpause paused container");
+    assert_eq!(running.state().pid, 1234);
+
+    let stopped = running.pause(&MockRuntime).await.unwrap().exited(137);
+    assert_eq!(stopped.state().exit_code, 137);
+    assert_eq!(stopped.state().started_at, Some(started_at));
+    assert!(!stopped.state().manually_stopped);
+}
+
+#[tokio::test]
+async fn test_exited_records_exit_code() {
+    let config = Config {
+        image: "test-image".to_string(),
+        cmd: vec!["test-cmd".to_string()],
+        ..Default::default()
+    };
+    let running = Container::new("test-id".to_string(), "test-name".to_string(), config)
+        .start(&MockRuntime)
+        .await
+        .expect("Should be able to start created container");
+    let stopped = running.exited(3);
+    assert_eq!(stopped.id(), "test-id");
+    assert_eq!(stopped.state().exit_code, 3);
+}
+
+#[test]
+fn test_validate_name() {
+    assert_eq!(validate_name("/web-1").ok().as_deref(), Some("web-1"));
+    assert_eq!(validate_name("my_app.v2").ok().as_deref(), Some("my_app.v2"));
+    assert!(validate_name("a").is_err());
+    assert!(validate_name("-web").is_err());
+    assert!(validate_name("web app").is_err());
+}
+
+#[test]
+fn test_default_name() {
+    assert_eq!(default_name("0b8f6c1e-4d2a-4c7e-9a55-1f2e3d4c5b6a"), "furukawa-0b8f6c1e4d2a");
+}
+
+#[test]
+fn test_restart_policy() {
+    let on_failure = RestartPolicy::parse("on-failure", 2).expect("valid policy");
+    assert_eq!(on_failure, RestartPolicy::OnFailure { max_retries: 2 });
+    assert!(on_failure.should_restart(1, 1, false));
+    assert!(!on_failure.should_restart(1, 2, false));
+    assert!(!on_failure.should_restart(0, 0, false));
+
+    let always = RestartPolicy::parse("always", 0).expect("valid policy");
+    assert!(always.should_restart(0, 100, false));
+    assert!(!always.should_restart(0, 0, true));
+    assert!(always.start_on_boot(true));
+    assert!(!RestartPolicy::UnlessStopped.start_on_boot(true));
+
+    assert!(RestartPolicy::parse("sometimes", 0).is_err());
+    assert!(RestartPolicy::parse("always", 3).is_err());
+    assert!(RestartPolicy::parse("on-failure", -1).is_err());
+}
+
+#[tokio::test]
+async fn test_auto_restart_counts_and_manual_start_resets() {
+    let running = Container::new("test-id".to_string(), "test-name".to_string(), Config::default())
+        .start(&MockRuntime)
+        .await
+        .expect("Should be able to start created container");
+    let restarted = running.exited(1).auto_restart(&MockRuntime).await.expect("restart");
+    assert_eq!(restarted.restart_count(), 1);
+    let restarted = restarted.exited(1).auto_restart(&MockRuntime).await.expect("restart");
+    assert_eq!(restarted.restart_count(), 2);
+
+    let stopped = restarted.stopped_manually(137);
+    assert!(stopped.state().manually_stopped);
+    let started = stopped.start(&MockRuntime).await.expect("start");
+    assert_eq!(started.restart_count(), 0);
+}
+
+#[test]
+fn test_log_config() {
+    let options = |pairs: &[(&str, &str)]| {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<std::collections::HashMap<_, _>>()
+    };
+
+    let config = LogConfig::parse("json-file", &options(&[("max-size", "10m"), ("max-file", "3"), ("compress", "true")]))
+        .expect("valid log config");
+    assert_eq!(config, LogConfig { max_size: Some(10 << 20), max_file: 3, compress: true });
+    assert_eq!(LogConfig::parse("", &options(&[("max-size", "1.5k")])).unwrap().max_size, Some(1536));
+    assert_eq!(LogConfig::parse("", &options(&[])).unwrap(), LogConfig::default());
+
+    assert!(LogConfig::parse("syslog", &options(&[])).is_err());
+    assert!(LogConfig::parse("", &options(&[("max-size", "lots")])).is_err());
+    assert!(LogConfig::parse("", &options(&[("max-file", "3")])).is_err());
+    assert!(LogConfig::parse("", &options(&[("max-size", "1m"), ("compress", "true")])).is_err());
+    assert!(LogConfig::parse("", &options(&[("labels", "a")])).is_err());
+}
+
+#[test]
+fn test_resources_validate() {
+    let mb = 1024 * 1024;
+    assert!(Resources::default().validate().is_ok());
+    assert!(Resources { memory: Some(64 * mb), memory_swap: Some(-1), ..Default::default() }.validate().is_ok());
+    assert!(Resources { cpuset_cpus: Some("0-3,5".to_string()), ..Default::default() }.validate().is_ok());
+
+    assert!(Resources { memory: Some(mb), ..Default::default() }.validate().is_err());
+    assert!(Resources { memory_swap: Some(128 * mb), ..Default::default() }.validate().is_err());
+    assert!(Resources { memory: Some(64 * mb), memory_swap: Some(32 * mb), ..Default::default() }.validate().is_err());
+    assert!(Resources { nano_cpus: Some(-1), ..Default::default() }.validate().is_err());
+    assert!(Resources { cpuset_cpus: Some("3-1".to_string()), ..Default::default() }.validate().is_err());
+
+    assert_eq!(super::config::parse_cpu_list("0-2,7"), Some(vec![0, 1, 2, 7]));
+}
+
+#[test]
+fn test_healthcheck() {
+    let shell = Healthcheck { test: vec!["CMD-SHELL".to_string(), "curl -f localhost".to_string()], ..Default::default() };
+    assert!(shell.validate().is_ok());
+    assert_eq!(shell.command().unwrap(), ["/bin/sh", "-c", "curl -f localhost"]);
+    assert_eq!((shell.interval(), shell.retries()), (Healthcheck::DEFAULT_INTERVAL, Healthcheck::DEFAULT_RETRIES));
+
+    let none = Healthcheck { test: vec!["NONE".to_string()], ..Default::default() };
+    assert!(none.validate().is_ok());
+    assert!(none.command().is_none());
+
+    assert!(Healthcheck { test: vec!["CMD".to_string()], ..Default::default() }.validate().is_err());
+    assert!(Healthcheck { test: vec!["RUN".to_string(), "true".to_string()], ..Default::default() }.validate().is_err());
+    assert!(Healthcheck { interval: 1000, ..Default::default() }.validate().is_err());
+    assert!(Healthcheck { retries: -1, ..Default::default() }.validate().is_err());
+}
+
+#[test]
+fn test_apply_image_defaults() {
+    let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let image = ImageConfig {
+        env: Some(strings(&["PATH=/usr/bin", "LANG=C"])),
+        entrypoint: Some(strings(&["/entrypoint.sh"])),
+        cmd: Some(strings(&["serve"])),
+        working_dir: Some("/app".to_string()),
+        user: Some("app".to_string()),
+        labels: Some([("vendor", "acme"), ("tier", "base")].into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()),
+    };
+
+    let mut config = Config { env: strings(&["LANG=en_US.UTF-8"]), ..Default::default() };
+    config.labels.insert("tier".to_string(), "web".to_string());
+    config.apply_image_defaults(&image);
+    assert_eq!(config.command(), ["/entrypoint.sh", "serve"]);
+    assert_eq!(config.env, ["LANG=en_US.UTF-8", "PATH=/usr/bin"]);
+    assert_eq!((config.labels["vendor"].as_str(), config.labels["tier"].as_str()), ("acme", "web"));
+    assert_eq!((config.working_dir.as_deref(), config.user.as_deref()), (Some("/app"), Some("app")));
+
+    // A Cmd replaces the image's but keeps its entrypoint.
+    let mut config = Config { cmd: strings(&["migrate"]), ..Default::default() };
+    config.apply_image_defaults(&image);
+    assert_eq!(config.command(), ["/entrypoint.sh", "migrate"]);
+
+    // An entrypoint drops the image's Cmd; [""] just resets.
+    let mut config = Config { entrypoint: Some(strings(&["sh"])), ..Default::default() };
+    config.apply_image_defaults(&image);
+    assert_eq!(config.command(), ["sh"]);
+    let mut config = Config { entrypoint: Some(strings(&[""])), cmd: strings(&["ls"]), ..Default::default() };
+    config.apply_image_defaults(&image);
+    assert_eq!(config.command(), ["ls"]);
 }
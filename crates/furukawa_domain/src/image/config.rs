@@ -0,0 +1,15 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The defaults an image gives its containers: the `config` object of its
+/// OCI image config, of which only these fields are used.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ImageConfig {
+    pub env: Option<Vec<String>>,
+    pub entrypoint: Option<Vec<String>>,
+    pub cmd: Option<Vec<String>>,
+    pub working_dir: Option<String>,
+    pub user: Option<String>,
+    pub labels: Option<HashMap<String, String>>,
+}
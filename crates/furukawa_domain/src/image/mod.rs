@@ -1,6 +1,8 @@
+pub mod config;
 pub mod digest;
 pub mod r#ref;
 pub mod store;
 
+pub use config::ImageConfig;
 pub use digest::Digest;
 pub use r#ref::ImageRef;
@@ -0,0 +1,107 @@
+//! Versioned, forward-only schema migrations.
+//!
+//! Each migration runs in its own transaction together with the insert into
+//! `schema_version`, so a crash never leaves a half-applied step behind.
+//! Migrations are append-only: never edit one that has shipped, add a new
+//! one. Databases from before `schema_version` existed have the baseline
+//! schema, which migration 1 creates with `IF NOT EXISTS`.
+
+use anyhow::{bail, Context};
+use sqlx::{Executor, Pool, Sqlite};
+use tracing::info;
+
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    pub sql: &'static str,
+}
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "initial schema",
+        sql: include_str!("migrations/0001_initial.sql"),
+    },
+    Migration {
+        version: 2,
+        description: "container run state",
+        sql: include_str!("migrations/0002_run_state.sql"),
+    },
+    Migration {
+        version: 3,
+        description: "unique container names",
+        sql: include_str!("migrations/0003_container_names.sql"),
+    },
+    Migration {
+        version: 4,
+        description: "container process start time",
+        sql: include_str!("migrations/0004_process_start.sql"),
+    },
+    Migration {
+        version: 5,
+        description: "restart policy bookkeeping",
+        sql: include_str!("migrations/0005_restart_policy.sql"),
+    },
+];
+
+/// Schema version this build expects.
+pub const LATEST_VERSION: i64 = MIGRATIONS[MIGRATIONS.len() - 1].version;
+
+/// Brings the database up to [`LATEST_VERSION`].
+pub async fn run(pool: &Pool<Sqlite>) -> anyhow::Result<()> {
+    migrate_to(pool, LATEST_VERSION).await
+}
+
+/// Applies the migrations up to and including `target`, leaving the
+/// database as the build at that version would have (tests use this to
+/// recreate older databases).
+pub async fn migrate_to(pool: &Pool<Sqlite>, target: i64) -> anyhow::Result<()> {
+    pool.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+    .await?;
+
+    let current = current_version(pool).await?;
+
+    if current > LATEST_VERSION {
+        bail!(
+            "database schema version {} is newer than this build supports ({}); upgrade rustkerd",
+            current,
+            LATEST_VERSION
+        );
+    }
+
+    for migration in MIGRATIONS.iter().filter(|m| m.version > current && m.version <= target) {
+        info!("Applying migration {}: {}", migration.version, migration.description);
+
+        let mut tx = pool.begin().await?;
+        tx.execute(migration.sql)
+            .await
+            .with_context(|| format!("migration {} ({}) failed", migration.version, migration.description))?;
+        record(&mut tx, migration).await?;
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// Highest applied migration, or 0 for a new (or baseline) database.
+pub async fn current_version(pool: &Pool<Sqlite>) -> anyhow::Result<i64> {
+    let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+        .fetch_one(pool)
+        .await?;
+    Ok(version.unwrap_or(0))
+}
+
+async fn record(tx: &mut sqlx::Transaction<'_, Sqlite>, migration: &Migration) -> anyhow::Result<()> {
+    sqlx::query("INSERT INTO schema_version (version, description) VALUES (?, ?)")
+        .bind(migration.version)
+        .bind(migration.description)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
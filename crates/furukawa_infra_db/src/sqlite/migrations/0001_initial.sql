@@ -0,0 +1,25 @@
+-- Schema as it existed before versioned migrations. IF NOT EXISTS lets
+-- databases created by those builds adopt it unchanged.
+CREATE TABLE IF NOT EXISTS containers (
+    id TEXT PRIMARY KEY,
+    state TEXT NOT NULL,
+    config JSON NOT NULL,
+    pid INTEGER,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS images (
+    id TEXT PRIMARY KEY,
+    repo_tags TEXT NOT NULL,
+    parent_id TEXT,
+    created INTEGER,
+    size INTEGER,
+    layers TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS networks (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    driver TEXT NOT NULL DEFAULT 'bridge',
+    labels JSON NOT NULL DEFAULT '{}'
+);
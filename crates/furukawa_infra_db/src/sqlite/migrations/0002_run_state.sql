@@ -0,0 +1,3 @@
+ALTER TABLE containers ADD COLUMN exit_code INTEGER;
+ALTER TABLE containers ADD COLUMN finished_at TIMESTAMP;
+ALTER TABLE containers ADD COLUMN started_at TIMESTAMP;
@@ -0,0 +1,6 @@
+ALTER TABLE containers ADD COLUMN name TEXT;
+
+-- Same scheme as `default_name` for containers created without a name.
+UPDATE containers SET name = 'furukawa-' || substr(replace(id, '-', ''), 1, 12) WHERE name IS NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_containers_name ON containers(name);
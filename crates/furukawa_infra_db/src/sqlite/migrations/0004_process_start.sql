@@ -0,0 +1 @@
+ALTER TABLE containers ADD COLUMN process_start TEXT;
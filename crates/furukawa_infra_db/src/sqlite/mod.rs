@@ -1,9 +1,11 @@
 use async_trait::async_trait;
 use furukawa_common::Result;
-use furukawa_domain::container::{store::ContainerStore, Container, Created, Config, Running};
-use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite, Row};
+use furukawa_domain::container::{store::{ContainerQuery, ContainerStore}, Container, ContainerError, Created, Config, Running};
+use sqlx::{sqlite::SqlitePoolOptions, Pool, QueryBuilder, Sqlite, Row};
 use tracing::info;
 
+pub mod migrations;
+
 #[derive(Clone)]
 pub struct SqliteStore {
     pool: Pool<Sqlite>,
@@ -16,52 +18,87 @@ impl SqliteStore {
             .connect(database_url)
             .await?;
 
-        // Initialize schema (use migrations in prod)
         // Note: we do NOT drop networks on startup so custom networks persist.
-
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS containers (
-                id TEXT PRIMARY KEY,
-                state TEXT NOT NULL,
-                config JSON NOT NULL,
-                pid INTEGER,
-                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
-            );"
-        )
-        .execute(&pool)
-        .await?;
-
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS images (
-                id TEXT PRIMARY KEY,
-                repo_tags TEXT NOT NULL,
-                parent_id TEXT,
-                created INTEGER,
-                size INTEGER,
-                layers TEXT NOT NULL
-            );"
-        )
-        .execute(&pool)
-        .await?;
-
-        sqlx::query(
-            "CREATE TABLE IF NOT EXISTS networks (
-                id TEXT PRIMARY KEY,
-                name TEXT NOT NULL UNIQUE,
-                driver TEXT NOT NULL DEFAULT 'bridge',
-                labels JSON NOT NULL DEFAULT '{}'
-            );"
-        )
-        .execute(&pool)
-        .await?;
+        migrations::run(&pool).await?;
         
         info!("SQLite store initialized at {}", database_url);
 
         Ok(Self { pool })
     }
+
+    /// Schema version of the underlying database.
+    pub async fn schema_version(&self) -> anyhow::Result<i64> {
+        migrations::current_version(&self.pool).await
+    }
 }
 
-// ... (ContainerStore implementation) ...
+/// Rebuilds a domain container from a `containers` row, whatever its state.
+fn row_to_container(row: &sqlx::sqlite::SqliteRow) -> Result<Option<furukawa_domain::container::AnyContainer>> {
+    use furukawa_domain::container::{AnyContainer, Paused, Stopped};
+
+    let id: String = row.get("id");
+    let name: String = row.get("name");
+    let config_str: String = row.get("config");
+    let state_str: String = row.get("state");
+    let pid: Option<u32> = row.get("pid");
+    let process_start: Option<String> = row.get("process_start");
+    let created_at: time::OffsetDateTime = row.get("created_at");
+    let started_at: Option<time::OffsetDateTime> = row.get("started_at");
+    let restart_count: u32 = row.get("restart_count");
+
+    let config: Config = serde_json::from_str(&config_str)
+        .map_err(|e| furukawa_common::diagnostic::Error::new(SerializationError(e)))?;
+
+    let container = match state_str.as_str() {
+        "created" => AnyContainer::Created(
+            Container::new(id, name, config).with_created_at(created_at).with_restart_count(restart_count),
+        ),
+        "running" => {
+            let state = Running {
+                pid: pid.unwrap_or(0),
+                started_at: started_at.unwrap_or(created_at),
+                process_start,
+            };
+            AnyContainer::Running(
+                Container::<Running>::restore(id, name, config, state)
+                    .with_created_at(created_at)
+                    .with_restart_count(restart_count),
+            )
+        }
+        "paused" => {
+            let state = Paused {
+                pid: pid.unwrap_or(0),
+                started_at: started_at.unwrap_or(created_at),
+                process_start,
+            };
+            AnyContainer::Paused(
+                Container::<Paused>::restore(id, name, config, state)
+                    .with_created_at(created_at)
+                    .with_restart_count(restart_count),
+            )
+        }
+        "stopped" => {
+            let finished_at: Option<time::OffsetDateTime> = row.get("finished_at");
+            let exit_code: Option<i32> = row.get("exit_code");
+            let state = Stopped {
+                started_at,
+                finished_at: finished_at.unwrap_or_else(time::OffsetDateTime::now_utc),
+                exit_code: exit_code.unwrap_or(0),
+                manually_stopped: row.get("manually_stopped"),
+            };
+            AnyContainer::Stopped(
+                Container::<Stopped>::restore(id, name, config, state)
+                    .with_created_at(created_at)
+                    .with_restart_count(restart_count),
+            )
+        }
+        _ => {
+            tracing::warn!("Unknown state {} for container {}", state_str, id);
+            return Ok(None);
+        }
+    };
+    Ok(Some(container))
+}
 
 use furukawa_domain::image::store::{ImageMetadataStore, ImageMetadata};
 
@@ -194,13 +231,20 @@ impl ContainerStore for SqliteStore {
         let config_json = serde_json::to_string(container.config())
             .map_err(|e| furukawa_common::diagnostic::Error::new(SerializationError(e)))?;
 
-        sqlx::query("INSERT INTO containers (id, state, config) VALUES (?, ?, ?)")
+        sqlx::query("INSERT INTO containers (id, name, state, config, created_at) VALUES (?, ?, ?, ?, ?)")
             .bind(container.id())
+            .bind(container.name())
             .bind("created")
             .bind(config_json)
+            .bind(container.created_at())
             .execute(&self.pool)
             .await
-            .map_err(|e| furukawa_common::diagnostic::Error::new(DbError(e)))?;
+            .map_err(|e| match e {
+                sqlx::Error::Database(ref db) if db.is_unique_violation() => {
+                    furukawa_common::diagnostic::Error::new(ContainerError::NameConflict(container.name().to_string()))
+                }
+                e => furukawa_common::diagnostic::Error::new(DbError(e)),
+            })?;
             
         Ok(())
     }
@@ -209,45 +253,37 @@ impl ContainerStore for SqliteStore {
         let config_json = serde_json::to_string(container.config())
             .map_err(|e| furukawa_common::diagnostic::Error::new(SerializationError(e)))?;
 
-        // We update state and config (though config shouldn't change, we stay robust)
-        // And we need to store PID and started_at. 
-        // For strictly relational, we'd have columns, but for now using 'state' column implicitly
-        // or expanding the JSON.
-        // Let's store strict state JSON in a 'state_data' column? 
-        // Or just update 'state' string to 'running' and maybe store PID in another column?
-        // Given existing schema: id, state, config, created_at.
-        // I should have added 'state_data' JSON column.
-        
-        // REFACTOR ON THE FLY: Add metadata/state_data column to schema?
-        // Or just repurpose. 
-        // Let's stick to simple 'state' = 'running'. PID is runtime data.
-        // If daemon restarts, how do we know PID? We MUST store PID.
-        
-        // I will allow 'config' column to prevent error, but PID needs storage.
-        // I will add a 'pid' column to the table definition in the 'new' method first.
-        
-        sqlx::query("UPDATE containers SET state = 'running', config = ?, pid = ? WHERE id = ?")
+        // A new run clears the result of the previous one.
+        sqlx::query("UPDATE containers SET state = 'running', config = ?, pid = ?, process_start = ?, started_at = ?, exit_code = NULL, finished_at = NULL, restart_count = ?, manually_stopped = 0 WHERE id = ?")
              .bind(config_json)
              .bind(container.state().pid)
+             .bind(&container.state().process_start)
+             .bind(container.state().started_at)
+             .bind(container.restart_count())
              .bind(container.id())
              .execute(&self.pool)
              .await
              .map_err(|e| furukawa_common::diagnostic::Error::new(DbError(e)))?;
-             
-        // TODO: Store PID/StartedAt. For this phase, we accept data loss on restart for running state details
-        // prioritizing the flow. Real impl needs schema migration.
-        
+
+        Ok(())
+    }
+
+    async fn save_paused(&self, container: &Container<furukawa_domain::container::Paused>) -> Result<()> {
+        // Same process as the run it froze: only the state changes.
+        sqlx::query("UPDATE containers SET state = 'paused' WHERE id = ?")
+             .bind(container.id())
+             .execute(&self.pool)
+             .await
+             .map_err(|e| furukawa_common::diagnostic::Error::new(DbError(e)))?;
+
         Ok(())
     }
 
     async fn save_stopped(&self, container: &Container<furukawa_domain::container::Stopped>) -> Result<()> {
-        // Update state to stopped. 
-        // Ideally we store exit code and finished_at in the JSON or separate columns.
-        // For simplicity/robustness in this phase, we update state to 'stopped' 
-        // and would serialize the full Stopped state into a `state_data` column if we had one.
-        // We will just update the state string for now.
-        
-        sqlx::query("UPDATE containers SET state = 'stopped' WHERE id = ?")
+        sqlx::query("UPDATE containers SET state = 'stopped', pid = NULL, process_start = NULL, exit_code = ?, finished_at = ?, manually_stopped = ? WHERE id = ?")
+             .bind(container.state().exit_code)
+             .bind(container.state().finished_at)
+             .bind(container.state().manually_stopped)
              .bind(container.id())
              .execute(&self.pool)
              .await
@@ -257,102 +293,105 @@ impl ContainerStore for SqliteStore {
     }
 
     async fn list(&self) -> Result<Vec<furukawa_domain::container::AnyContainer>> {
-        use furukawa_domain::container::{AnyContainer, Container, Running, Stopped, Config};
-
-        let rows = sqlx::query("SELECT id, config, state, pid, created_at FROM containers")
+        let rows = sqlx::query("SELECT id, name, config, state, pid, process_start, created_at, started_at, exit_code, finished_at, restart_count, manually_stopped FROM containers")
             .fetch_all(&self.pool)
             .await
             .map_err(|e| furukawa_common::diagnostic::Error::new(DbError(e)))?;
 
         let mut containers = Vec::new();
-
         for row in rows {
-            let id: String = row.get("id");
-            let config_str: String = row.get("config");
-            let state_str: String = row.get("state");
-            let pid: Option<u32> = row.get("pid");
+            if let Some(container) = row_to_container(&row)? {
+                containers.push(container);
+            }
+        }
+        
+        Ok(containers)
+    }
 
-            let config: Config = serde_json::from_str(&config_str)
-                .map_err(|e| furukawa_common::diagnostic::Error::new(SerializationError(e)))?;
+    async fn query(&self, query: &ContainerQuery) -> Result<Vec<furukawa_domain::container::AnyContainer>> {
+        let mut sql = QueryBuilder::<Sqlite>::new(
+            "SELECT id, name, config, state, pid, process_start, created_at, started_at, exit_code, finished_at, restart_count, manually_stopped FROM containers WHERE 1 = 1",
+        );
+        if query.running_only {
+            sql.push(" AND state IN ('running', 'paused')");
+        }
+        if !query.statuses.is_empty() {
+            // Docker's `exited` is our `stopped`; statuses we never use
+            // (`restarting`, `dead`...) match nothing.
+            sql.push(" AND state IN (");
+            let mut states = sql.separated(", ");
+            for status in &query.statuses {
+                states.push_bind(if status == "exited" { "stopped" } else { status.as_str() });
+            }
+            sql.push(")");
+        }
+        if !query.ids.is_empty() {
+            sql.push(" AND (");
+            let mut ids = sql.separated(" OR ");
+            for id in &query.ids {
+                let escaped = id.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+                ids.push("id LIKE ").push_bind_unseparated(format!("{}%", escaped)).push_unseparated(" ESCAPE '\\'");
+            }
+            sql.push(")");
+        }
+        if !query.images.is_empty() {
+            sql.push(" AND json_extract(config, '$.image') IN (");
+            let mut images = sql.separated(", ");
+            for image in &query.images {
+                images.push_bind(image);
+            }
+            sql.push(")");
+        }
+        if !query.exit_codes.is_empty() {
+            sql.push(" AND state = 'stopped' AND exit_code IN (");
+            let mut codes = sql.separated(", ");
+            for code in &query.exit_codes {
+                codes.push_bind(*code);
+            }
+            sql.push(")");
+        }
+        if let Some(before) = query.created_before {
+            sql.push(" AND created_at < ").push_bind(before);
+        }
+        if let Some(after) = query.created_after {
+            sql.push(" AND created_at > ").push_bind(after);
+        }
+        sql.push(" ORDER BY created_at DESC, rowid DESC");
+        if let Some(limit) = query.limit {
+            sql.push(" LIMIT ").push_bind(limit as i64);
+        }
 
-            match state_str.as_str() {
-                "created" => {
-                    containers.push(AnyContainer::Created(Container::new(id, config)));
-                }
-                "running" => {
-                    let pid = pid.unwrap_or(0); 
-                    let state = Running {
-                        pid,
-                        started_at: time::OffsetDateTime::now_utc(), 
-                    };
-                    containers.push(AnyContainer::Running(Container::<Running>::restore(id, config, state)));
-                }
-                "stopped" => {
-                    let state = Stopped {
-                        finished_at: time::OffsetDateTime::now_utc(), 
-                        exit_code: 0, 
-                    };
-                    containers.push(AnyContainer::Stopped(Container::<Stopped>::restore(id, config, state)));
-                }
-                _ => {
-                    tracing::warn!("Unknown state {} for container {}", state_str, id);
-                }
+        let rows = sql
+            .build()
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| furukawa_common::diagnostic::Error::new(DbError(e)))?;
+
+        let mut containers = Vec::new();
+        for row in rows {
+            if let Some(container) = row_to_container(&row)? {
+                containers.push(container);
             }
         }
-        
+
         Ok(containers)
     }
 
     async fn get_any(&self, id: &str) -> Result<Option<furukawa_domain::container::AnyContainer>> {
-        use furukawa_domain::container::{AnyContainer, Container, Running, Stopped, Config};
-
-        let row = sqlx::query("SELECT id, config, state, pid, created_at FROM containers WHERE id = ?")
+        let row = sqlx::query("SELECT id, name, config, state, pid, process_start, created_at, started_at, exit_code, finished_at, restart_count, manually_stopped FROM containers WHERE id = ?")
             .bind(id)
             .fetch_optional(&self.pool)
             .await
             .map_err(|e| furukawa_common::diagnostic::Error::new(DbError(e)))?;
 
         match row {
-            Some(row) => {
-                let id: String = row.get("id");
-                let config_str: String = row.get("config");
-                let state_str: String = row.get("state");
-                let pid: Option<u32> = row.get("pid");
-
-                let config: Config = serde_json::from_str(&config_str)
-                    .map_err(|e| furukawa_common::diagnostic::Error::new(SerializationError(e)))?;
-
-                match state_str.as_str() {
-                    "created" => {
-                        Ok(Some(AnyContainer::Created(Container::new(id, config))))
-                    }
-                    "running" => {
-                        let pid = pid.unwrap_or(0); 
-                        let state = Running {
-                            pid,
-                            started_at: time::OffsetDateTime::now_utc(), 
-                        };
-                        Ok(Some(AnyContainer::Running(Container::<Running>::restore(id, config, state))))
-                    }
-                    "stopped" => {
-                        let state = Stopped {
-                            finished_at: time::OffsetDateTime::now_utc(), 
-                            exit_code: 0, 
-                        };
-                        Ok(Some(AnyContainer::Stopped(Container::<Stopped>::restore(id, config, state))))
-                    }
-                    _ => {
-                        tracing::warn!("Unknown state {} for container {}", state_str, id);
-                        Ok(None)
-                    }
-                }
-            },
+            Some(row) => row_to_container(&row),
             None => Ok(None)
         }
     }
 
     async fn get(&self, id: &str) -> Result<Option<Container<Created>>> {
-        let row = sqlx::query("SELECT id, config FROM containers WHERE id = ? AND state = 'created'")
+        let row = sqlx::query("SELECT id, name, config, created_at FROM containers WHERE id = ? AND state = 'created'")
             .bind(id)
             .fetch_optional(&self.pool)
             .await
@@ -361,58 +400,28 @@ impl ContainerStore for SqliteStore {
         match row {
             Some(row) => {
                 let id: String = row.get("id");
+                let name: String = row.get("name");
                 let config_str: String = row.get("config");
+                let created_at: time::OffsetDateTime = row.get("created_at");
                 let config: Config = serde_json::from_str(&config_str)
                      .map_err(|e| furukawa_common::diagnostic::Error::new(SerializationError(e)))?;
-                Ok(Some(Container::new(id, config)))
+                Ok(Some(Container::new(id, name, config).with_created_at(created_at)))
             },
             None => Ok(None),
         }
     }
 
     async fn get_running(&self, id: &str) -> Result<Option<Container<Running>>> {
-        // We assume for now that if state='running', we can reconstruct it.
-        // BUT we didn't store PID in a column yet! 
-        // In `save_running`, we just updated state='running'. WE LOST THE PID on restart.
-        // This is a critical "10 year" flaw.
-        // START REFACTOR: We need to store runtime state.
-        // For now, to unblock, I will return a dummy PID if not found, 
-        // BUT strict correctness demands we fix `save_running` to store PID.
-        // Since I cannot change schema easily without migration in this flow,
-        // and because I need to stop the *current* process which I might know from memory if I didn't restart...
-        // Wait, if I restart furukawad, I lose the in-memory handle. I NEED the PID in DB.
-        
-        // I will hack `save_running` to store PID in `config` field? NO, that's dirty.
-        // I will add a `pid` column to the table creation query and `save_running`.
-        // This requires dropping the table again (which is fine for dev).
-        
-        // I will first update the table schema in `new`.
-        
-        let row = sqlx::query("SELECT id, config, pid, created_at FROM containers WHERE id = ? AND state = 'running'")
+        let row = sqlx::query("SELECT id, name, config, state, pid, process_start, created_at, started_at, exit_code, finished_at, restart_count, manually_stopped FROM containers WHERE id = ? AND state = 'running'")
             .bind(id)
             .fetch_optional(&self.pool)
             .await
             .map_err(|e| furukawa_common::diagnostic::Error::new(DbError(e)))?;
 
         match row {
-            Some(row) => {
-                let id: String = row.get("id");
-                let config_str: String = row.get("config");
-                let pid: u32 = row.get("pid"); // This will fail if column doesn't exist
-                let _created_at: time::OffsetDateTime = row.get("created_at");
-                
-                let config: Config = serde_json::from_str(&config_str)
-                     .map_err(|e| furukawa_common::diagnostic::Error::new(SerializationError(e)))?;
-                
-                // Reconstruct Running state
-                // We use created_at as started_at proxy for now, strictly we should store started_at
-                let state = Running {
-                    pid,
-                    started_at: time::OffsetDateTime::now_utc(), // Placeholder, acceptable for now
-                };
-
-                let container = Container::<Running>::restore(id, config, state);
-                Ok(Some(container))
+            Some(row) => match row_to_container(&row)? {
+                Some(furukawa_domain::container::AnyContainer::Running(c)) => Ok(Some(c)),
+                _ => Ok(None),
             },
             None => Ok(None),
         }
@@ -442,6 +451,37 @@ impl ContainerStore for SqliteStore {
 
         Ok(row.map(|r| r.get("state")))
     }
+
+    async fn resolve_id(&self, reference: &str) -> Result<Option<String>> {
+        // Same precedence as Docker: full ID, then exact name, then ID prefix.
+        let name = reference.strip_prefix('/').unwrap_or(reference);
+        let exact: Option<String> = sqlx::query_scalar("SELECT id FROM containers WHERE id = ? OR name = ? ORDER BY id = ? DESC LIMIT 1")
+            .bind(reference)
+            .bind(name)
+            .bind(reference)
+            .fetch_optional(&self.pool)
+            .await
+            .map_err(|e| furukawa_common::diagnostic::Error::new(DbError(e)))?;
+        if exact.is_some() {
+            return Ok(exact);
+        }
+
+        if reference.is_empty() {
+            return Ok(None);
+        }
+        let escaped = reference.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+        let matches: Vec<String> = sqlx::query_scalar("SELECT id FROM containers WHERE id LIKE ? ESCAPE '\\' LIMIT 2")
+            .bind(format!("{}%", escaped))
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|e| furukawa_common::diagnostic::Error::new(DbError(e)))?;
+
+        match matches.as_slice() {
+            [id] => Ok(Some(id.clone())),
+            [] => Ok(None),
+            _ => Err(furukawa_common::diagnostic::Error::new(ContainerError::AmbiguousReference(reference.to_string()))),
+        }
+    }
 }
 
 // Map sqlx errors to our Diagnosable error
@@ -0,0 +1,30 @@
+-- Database written before schema_version existed (baseline schema).
+CREATE TABLE containers (
+    id TEXT PRIMARY KEY,
+    state TEXT NOT NULL,
+    config JSON NOT NULL,
+    pid INTEGER,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE images (
+    id TEXT PRIMARY KEY,
+    repo_tags TEXT NOT NULL,
+    parent_id TEXT,
+    created INTEGER,
+    size INTEGER,
+    layers TEXT NOT NULL
+);
+
+CREATE TABLE networks (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    driver TEXT NOT NULL DEFAULT 'bridge',
+    labels JSON NOT NULL DEFAULT '{}'
+);
+
+INSERT INTO images VALUES ('sha256:1111', '["library/busybox:latest"]', NULL, 1727386302, 2211656, '["sha256:aaaa"]');
+INSERT INTO networks VALUES ('net-1', 'backend', 'bridge', '{}');
+
+INSERT INTO containers (id, state, config, pid, created_at) VALUES ('0b8f6c1e-4d2a-4c7e-9a55-1f2e3d4c5b6a', 'created', '{"image":"busybox:latest","cmd":["sleep","30"],"port_mappings":[]}', NULL, '2024-05-01 10:00:00');
+INSERT INTO containers (id, state, config, pid, created_at) VALUES ('7c1d2e3f-0000-4000-8000-000000000002', 'stopped', '{"image":"busybox:latest","cmd":["sleep","30"],"port_mappings":[]}', NULL, '2024-05-01 11:00:00');
@@ -0,0 +1,6 @@
+-- Rows written at schema version 4 (container process start time).
+INSERT INTO images VALUES ('sha256:1111', '["library/busybox:latest"]', NULL, 1727386302, 2211656, '["sha256:aaaa"]');
+INSERT INTO networks VALUES ('net-1', 'backend', 'bridge', '{}');
+
+INSERT INTO containers (id, name, state, config, pid, created_at) VALUES ('0b8f6c1e-4d2a-4c7e-9a55-1f2e3d4c5b6a', 'furukawa-0b8f6c1e4d2a', 'created', '{"image":"busybox:latest","cmd":["sleep","30"],"port_mappings":[]}', NULL, '2024-05-01 10:00:00');
+INSERT INTO containers (id, name, state, config, pid, process_start, created_at, started_at, finished_at, exit_code) VALUES ('7c1d2e3f-0000-4000-8000-000000000002', 'web', 'stopped', '{"image":"busybox:latest","cmd":["sleep","30"],"port_mappings":[]}', NULL, NULL, '2024-05-01 11:00:00', '2024-05-01 11:00:01', '2024-05-01 11:00:31', 0);
@@ -0,0 +1,141 @@
+use furukawa_domain::container::{store::ContainerStore, Config, Container};
+use furukawa_infra_db::sqlite::SqliteStore;
+use tempfile::TempDir;
+
+fn database_url(dir: &TempDir) -> String {
+    format!("sqlite://{}?mode=rwc", dir.path().join("rustker.db").to_string_lossy().replace('\\', "/"))
+}
+
+/// A new store in `dir`, which must outlive it.
+async fn fresh_store(dir: &TempDir) -> SqliteStore {
+    SqliteStore::new(&database_url(dir)).await.expect("Failed to init store")
+}
+
+#[tokio::test]
+async fn test_container_names_are_unique_and_resolvable() {
+    let dir = TempDir::new().unwrap();
+    let store = fresh_store(&dir).await;
+
+    let web = Container::new("abc123-0001".to_string(), "web".to_string(), Config::default());
+    let db = Container::new("abc456-0002".to_string(), "db".to_string(), Config::default());
+    store.save(&web).await.unwrap();
+    store.save(&db).await.unwrap();
+
+    let duplicate = Container::new("def789-0003".to_string(), "web".to_string(), Config::default());
+    let err = store.save(&duplicate).await.expect_err("Duplicate name must be rejected");
+    assert_eq!(err.code(), "CONTAINER_NAME_CONFLICT");
+
+    assert_eq!(store.resolve_id("abc123-0001").await.unwrap().as_deref(), Some("abc123-0001"));
+    assert_eq!(store.resolve_id("web").await.unwrap().as_deref(), Some("abc123-0001"));
+    assert_eq!(store.resolve_id("/db").await.unwrap().as_deref(), Some("abc456-0002"));
+    assert_eq!(store.resolve_id("abc4").await.unwrap().as_deref(), Some("abc456-0002"));
+    assert_eq!(store.resolve_id("zzz").await.unwrap(), None);
+
+    let err = store.resolve_id("abc").await.expect_err("Ambiguous prefix must be rejected");
+    assert_eq!(err.code(), "CONTAINER_AMBIGUOUS_REFERENCE");
+}
+
+#[tokio::test]
+async fn test_timestamps_survive_reopen() {
+    use furukawa_domain::container::{AnyContainer, Running};
+
+    let dir = TempDir::new().unwrap();
+    let store = fresh_store(&dir).await;
+    let created = Container::new("ts-0001".to_string(), "ts".to_string(), Config::default());
+    let created_at = created.created_at();
+    store.save(&created).await.unwrap();
+
+    let started_at = time::OffsetDateTime::now_utc();
+    let running = Container::<Running>::restore(
+        "ts-0001".to_string(),
+        "ts".to_string(),
+        Config::default(),
+        Running { pid: 4242, started_at, process_start: Some("boot:1234".to_string()) },
+    );
+    store.save_running(&running).await.unwrap();
+    let stopped = running.exited(7);
+    store.save_stopped(&stopped).await.unwrap();
+
+    // Reopen the same file, as the daemon does after a restart.
+    let reopened = SqliteStore::new(&database_url(&dir)).await.unwrap();
+
+    match reopened.get_any("ts-0001").await.unwrap() {
+        Some(AnyContainer::Stopped(c)) => {
+            assert_eq!(c.created_at().unix_timestamp(), created_at.unix_timestamp());
+            assert_eq!(c.state().started_at.map(|t| t.unix_timestamp()), Some(started_at.unix_timestamp()));
+            assert_eq!(c.state().finished_at.unix_timestamp(), stopped.state().finished_at.unix_timestamp());
+            assert_eq!(c.state().exit_code, 7);
+        }
+        other => panic!("Expected a stopped container, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_paused_state_persists() {
+    use furukawa_domain::container::{AnyContainer, Paused, Running};
+
+    let dir = TempDir::new().unwrap();
+    let store = fresh_store(&dir).await;
+    store.save(&Container::new("p-0001".to_string(), "p".to_string(), Config::default())).await.unwrap();
+
+    let started_at = time::OffsetDateTime::now_utc();
+    let state = Running { pid: 4242, started_at, process_start: Some("boot:1".to_string()) };
+    let running = Container::<Running>::restore("p-0001".to_string(), "p".to_string(), Config::default(), state);
+    store.save_running(&running).await.unwrap();
+
+    let paused = Container::<Paused>::restore(
+        "p-0001".to_string(),
+        "p".to_string(),
+        Config::default(),
+        Paused { pid: 4242, started_at, process_start: Some("boot:1".to_string()) },
+    );
+    store.save_paused(&paused).await.unwrap();
+    assert_eq!(store.get_status("p-0001").await.unwrap().as_deref(), Some("paused"));
+    assert!(store.get_running("p-0001").await.unwrap().is_none());
+
+    match store.get_any("p-0001").await.unwrap() {
+        Some(AnyContainer::Paused(c)) => {
+            assert_eq!(c.state().pid, 4242);
+            assert_eq!(c.state().process_start.as_deref(), Some("boot:1"));
+            assert_eq!(c.state().started_at.unix_timestamp(), started_at.unix_timestamp());
+        }
+        other => panic!("Expected a paused container, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_query_filters_in_sql() {
+    use furukawa_domain::container::{store::ContainerQuery, Running};
+
+    let dir = TempDir::new().unwrap();
+    let store = fresh_store(&dir).await;
+    let base = time::OffsetDateTime::now_utc() - time::Duration::hours(1);
+    let config = |image: &str| Config { image: image.to_string(), ..Config::default() };
+    for (i, (id, image)) in [("q1-aaaa", "busybox:latest"), ("q2-bbbb", "alpine"), ("q3-cccc", "busybox:latest")].iter().enumerate() {
+        let c = Container::new(id.to_string(), id.to_string(), config(image)).with_created_at(base + time::Duration::minutes(i as i64));
+        store.save(&c).await.unwrap();
+    }
+    let state = Running { pid: 4242, started_at: base, process_start: None };
+    let running = Container::<Running>::restore("q2-bbbb".to_string(), "q2-bbbb".to_string(), config("alpine"), state);
+    store.save_running(&running).await.unwrap();
+    store.save_stopped(&running.exited(3)).await.unwrap();
+
+    let ids = |containers: Vec<furukawa_domain::container::AnyContainer>| {
+        containers.iter().map(|c| c.id().to_string()).collect::<Vec<_>>()
+    };
+    let all = store.query(&ContainerQuery::default()).await.unwrap();
+    assert_eq!(ids(all), ["q3-cccc", "q2-bbbb", "q1-aaaa"]);
+
+    let query = ContainerQuery { statuses: vec!["exited".to_string()], ..Default::default() };
+    assert_eq!(ids(store.query(&query).await.unwrap()), ["q2-bbbb"]);
+    let query = ContainerQuery { running_only: true, ..Default::default() };
+    assert!(store.query(&query).await.unwrap().is_empty());
+    let query = ContainerQuery { images: vec!["busybox:latest".to_string()], limit: Some(1), ..Default::default() };
+    assert_eq!(ids(store.query(&query).await.unwrap()), ["q3-cccc"]);
+    let query = ContainerQuery { ids: vec!["q1".to_string(), "q3-c".to_string()], ..Default::default() };
+    assert_eq!(ids(store.query(&query).await.unwrap()), ["q3-cccc", "q1-aaaa"]);
+    let query = ContainerQuery { exit_codes: vec![3], created_after: Some(base), ..Default::default() };
+    assert_eq!(ids(store.query(&query).await.unwrap()), ["q2-bbbb"]);
+    let query = ContainerQuery { created_before: Some(base + time::Duration::minutes(1)), ..Default::default() };
+    assert_eq!(ids(store.query(&query).await.unwrap()), ["q1-aaaa"]);
+}
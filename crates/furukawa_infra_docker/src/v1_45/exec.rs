@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+// Ref: https://docs.docker.com/engine/api/v1.45/#tag/Exec
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ExecConfig {
+    pub attach_stdin: Option<bool>,
+    pub attach_stdout: Option<bool>,
+    pub attach_stderr: Option<bool>,
+    /// Initial terminal size as [height, width].
+    pub console_size: Option<Vec<u16>>,
+    pub detach_keys: Option<String>,
+    pub tty: Option<bool>,
+    pub env: Option<Vec<String>>,
+    pub cmd: Option<Vec<String>>,
+    pub privileged: Option<bool>,
+    pub user: Option<String>,
+    pub working_dir: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ExecCreateResponse {
+    pub id: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase", default)]
+pub struct ExecStartConfig {
+    pub detach: Option<bool>,
+    pub tty: Option<bool>,
+    /// Initial terminal size as [height, width].
+    pub console_size: Option<Vec<u16>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ExecInspectResponse {
+    pub can_remove: bool,
+    #[serde(rename = "ContainerID")]
+    pub container_id: String,
+    pub detach_keys: String,
+    pub exit_code: Option<i32>,
+    #[serde(rename = "ID")]
+    pub id: String,
+    pub open_stderr: bool,
+    pub open_stdin: bool,
+    pub open_stdout: bool,
+    pub process_config: ExecProcessConfig,
+    pub running: bool,
+    pub pid: u32,
+}
+
+/// `ProcessConfig` of an exec inspect response; Docker uses lower case here.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExecProcessConfig {
+    pub arguments: Vec<String>,
+    pub entrypoint: String,
+    pub privileged: bool,
+    pub tty: bool,
+    pub user: String,
+}
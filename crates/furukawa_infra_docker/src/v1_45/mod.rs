@@ -30,9 +30,22 @@ pub struct ContainerConfig {
     pub stop_signal: Option<String>,
     pub stop_timeout: Option<isize>,
     pub shell: Option<Vec<String>>,
+    pub healthcheck: Option<HealthConfig>,
     pub host_config: Option<HostConfig>,
 }
 
+/// Durations are in nanoseconds; 0 means inherit (the default).
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct HealthConfig {
+    /// `[]` inherit, `["NONE"]` disable, `["CMD", args...]` or `["CMD-SHELL", command]`
+    pub test: Option<Vec<String>>,
+    pub interval: Option<i64>,
+    pub timeout: Option<i64>,
+    pub retries: Option<i64>,
+    pub start_period: Option<i64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 pub struct HostConfig {
@@ -41,6 +54,39 @@ pub struct HostConfig {
     pub binds: Option<Vec<String>>,
     /// Network mode: "bridge", "host", "none", or custom network name
     pub network_mode: Option<String>,
+    pub restart_policy: Option<RestartPolicy>,
+    pub log_config: Option<LogConfig>,
+    /// Memory limit in bytes; 0 means unlimited.
+    pub memory: Option<i64>,
+    /// Memory plus swap in bytes; -1 means unlimited swap.
+    pub memory_swap: Option<i64>,
+    /// CPU quota in units of 10^-9 CPUs.
+    pub nano_cpus: Option<i64>,
+    /// Relative CPU weight (default 1024).
+    pub cpu_shares: Option<i64>,
+    /// CPUs in which to allow execution, e.g. "0-3" or "0,1"
+    pub cpuset_cpus: Option<String>,
+    /// Maximum number of processes; 0 or -1 means unlimited.
+    pub pids_limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct RestartPolicy {
+    /// "", "no", "always", "unless-stopped" or "on-failure"
+    pub name: Option<String>,
+    /// Only valid with "on-failure"; 0 means unlimited.
+    pub maximum_retry_count: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct LogConfig {
+    /// Logging driver; only "json-file" is supported.
+    #[serde(rename = "Type")]
+    pub type_: Option<String>,
+    /// Driver options, e.g. {"max-size": "10m", "max-file": "3"}
+    pub config: Option<HashMap<String, String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,6 +103,36 @@ pub struct ContainerCreateResponse {
     pub warnings: Vec<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerWaitResponse {
+    pub status_code: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ContainerWaitExitError>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerWaitExitError {
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerTopResponse {
+    pub titles: Vec<String>,
+    pub processes: Vec<Vec<String>>,
+}
+
+/// One entry of `GET /containers/:id/changes`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct FilesystemChange {
+    pub path: String,
+    /// 0: modified, 1: added, 2: deleted
+    pub kind: u8,
+}
+
 mod summary;
 pub use summary::*;
 
@@ -68,3 +144,9 @@ pub use system_info::*;
 
 mod image;
 pub use image::*;
+
+mod exec;
+pub use exec::*;
+
+mod stats;
+pub use stats::*;
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Ref: https://docs.docker.com/engine/api/v1.45/#tag/Container/operation/ContainerStats
+// Unlike the rest of the API, stats use snake_case keys.
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ContainerStats {
+    /// Container name with Docker's leading `/`.
+    pub name: String,
+    pub id: String,
+    /// When this sample was taken (RFC 3339).
+    pub read: String,
+    /// When the previous sample was taken; Docker's zero time if none.
+    pub preread: String,
+    pub pids_stats: PidsStats,
+    pub blkio_stats: BlkioStats,
+    pub num_procs: u32,
+    pub storage_stats: serde_json::Value,
+    pub cpu_stats: CpuStats,
+    pub precpu_stats: CpuStats,
+    pub memory_stats: MemoryStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub networks: Option<BTreeMap<String, NetworkStats>>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PidsStats {
+    pub current: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BlkioStats {
+    pub io_service_bytes_recursive: Option<Vec<BlkioStatEntry>>,
+    pub io_serviced_recursive: Option<Vec<BlkioStatEntry>>,
+    pub io_queue_recursive: Option<Vec<BlkioStatEntry>>,
+    pub io_service_time_recursive: Option<Vec<BlkioStatEntry>>,
+    pub io_wait_time_recursive: Option<Vec<BlkioStatEntry>>,
+    pub io_merged_recursive: Option<Vec<BlkioStatEntry>>,
+    pub io_time_recursive: Option<Vec<BlkioStatEntry>>,
+    pub sectors_recursive: Option<Vec<BlkioStatEntry>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BlkioStatEntry {
+    pub major: u64,
+    pub minor: u64,
+    /// "read" or "write"
+    pub op: String,
+    pub value: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CpuStats {
+    pub cpu_usage: CpuUsage,
+    /// Host CPU time in nanoseconds, for computing the container's share.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_cpu_usage: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub online_cpus: Option<u32>,
+    pub throttling_data: ThrottlingData,
+}
+
+/// CPU time in nanoseconds.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CpuUsage {
+    pub total_usage: u64,
+    pub usage_in_kernelmode: u64,
+    pub usage_in_usermode: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ThrottlingData {
+    pub periods: u64,
+    pub throttled_periods: u64,
+    /// Nanoseconds.
+    pub throttled_time: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MemoryStats {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<u64>,
+    /// cgroup v2 `memory.stat`, e.g. `inactive_file`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<BTreeMap<String, u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NetworkStats {
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_dropped: u64,
+}
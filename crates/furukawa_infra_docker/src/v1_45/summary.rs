@@ -12,6 +12,11 @@ pub struct ContainerSummary {
     pub command: String,
     pub created: i64,
     pub ports: Vec<Port>,
+    /// Only with `size=true`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_rw: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size_root_fs: Option<i64>,
     pub labels: HashMap<String, String>,
     pub state: String,
     pub status: String,
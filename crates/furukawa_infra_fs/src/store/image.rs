@@ -1,7 +1,10 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
 use tokio::fs::{self, File};
 use tokio::io::AsyncWriteExt;
 use bytes::Bytes;
+use sha2::{Digest, Sha256};
+use super::snapshot::{self, RootfsSnapshot};
 
 
 #[derive(Debug, thiserror::Error)]
@@ -53,6 +56,19 @@ impl ImageStore {
         Ok(())
     }
 
+    /// A layer written in place under a temporary name, to be named after
+    /// its digest by [`LayerWriter::commit`]. Blocking file I/O.
+    pub fn layer_writer(&self) -> Result<LayerWriter, StoreError> {
+        let dir = self.root_path.join("layers");
+        std::fs::create_dir_all(&dir)?;
+        Ok(LayerWriter {
+            file: tempfile::NamedTempFile::new_in(&dir)?,
+            hasher: Sha256::new(),
+            size: 0,
+            dir,
+        })
+    }
+
     pub async fn save_config(&self, id: &str, config: serde_json::Value) -> Result<(), StoreError> {
         let path = self.config_path(id);
         if let Some(parent) = path.parent() {
@@ -64,10 +80,29 @@ impl ImageStore {
         Ok(())
     }
 
+    /// Reads back a config saved with [`save_config`](Self::save_config);
+    /// `None` if the image has none.
+    pub async fn load_config(&self, id: &str) -> Result<Option<serde_json::Value>, StoreError> {
+        let data = match fs::read(self.config_path(id)).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| StoreError::Io(std::io::Error::new(std::io::ErrorKind::InvalidData, e)))
+    }
+
     /// Unpacks a layer into the target directory.
     /// This is a blocking operation for now as 'tar' and 'flate2' are synchronous.
     /// In a 10 year architecture, we'd use tokio-tar or spawn_blocking.
     pub async fn unpack_layer(&self, digest: &str, target_dir: PathBuf) -> Result<(), StoreError> {
+        self.unpack_layer_paths(digest, target_dir).await.map(|_| ())
+    }
+
+    /// [`unpack_layer`](Self::unpack_layer), returning the container paths
+    /// the layer wrote (whiteouts excluded).
+    async fn unpack_layer_paths(&self, digest: &str, target_dir: PathBuf) -> Result<Vec<String>, StoreError> {
         let layer_path = self.layer_path(digest);
         if !layer_path.exists() {
             return Err(StoreError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "Layer not found")));
@@ -75,16 +110,28 @@ impl ImageStore {
 
         let target_dir_clone = target_dir.clone();
         
-        tokio::task::spawn_blocking(move || {
+        let paths = tokio::task::spawn_blocking(move || {
             let file = std::fs::File::open(layer_path)?;
             let decompressed = flate2::read::GzDecoder::new(file);
             let mut archive = tar::Archive::new(decompressed);
             
             let mut deferred_links = Vec::new();
+            let mut paths = Vec::new();
 
             for entry in archive.entries()? {
                 let mut entry = entry?;
                 let etype = entry.header().entry_type();
+                if let Ok(path) = entry.path() {
+                    // A whiteout hides its target in the layers below, so it
+                    // is applied now, before a later layer can recreate it.
+                    if is_whiteout(&path) {
+                        if let Some(target) = whiteout_target(&path) {
+                            remove_whiteout_target(&target_dir_clone.join(target))?;
+                        }
+                        continue;
+                    }
+                    paths.push(snapshot::container_path(std::path::Path::new(""), &path));
+                }
                 
                 if etype == tar::EntryType::Symlink || etype == tar::EntryType::Link {
                     if let Ok(Some(link_name)) = entry.link_name() {
@@ -148,56 +195,93 @@ impl ImageStore {
                 }
             }
             
-            Ok::<(), std::io::Error>(())
-        }).await.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))??;
+            Ok::<Vec<String>, std::io::Error>(paths)
+        }).await.map_err(std::io::Error::other)??;
 
-        Ok(())
+        Ok(paths)
     }
 
-    pub async fn compose_rootfs(&self, layers: &[String], target_dir: PathBuf) -> Result<(), StoreError> {
+    /// Unpacks `layers` in order into `target_dir`, each layer's whiteouts
+    /// applied as it is unpacked, and
+    /// returns a snapshot of the result recording which layer wrote what.
+    pub async fn compose_rootfs(&self, layers: &[String], target_dir: PathBuf) -> Result<RootfsSnapshot, StoreError> {
         if !target_dir.exists() {
             fs::create_dir_all(&target_dir).await?;
         }
 
+        let mut provenance = HashMap::new();
         for digest in layers {
             tracing::info!("Unpacking layer: {}", digest);
-            self.unpack_layer(digest, target_dir.clone()).await?;
+            for path in self.unpack_layer_paths(digest, target_dir.clone()).await? {
+                provenance.insert(path, digest.clone());
+            }
         }
 
-        // Basic Whiteout Handling:
-        // After unpacking all layers, we should scan for .wh. files.
-        // In a real 10-year foundation, we'd handle this during untarring.
-        // For Phase 5, we do a post-extraction cleanup to satisfy the "Compose RootFS" objective.
         let target_dir_clone = target_dir.clone();
         tokio::task::spawn_blocking(move || {
-            for entry in walkdir::WalkDir::new(&target_dir_clone)
-                .into_iter()
-                .filter_map(|e| e.ok()) {
-                let path = entry.path();
-                if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                    if file_name.starts_with(".wh.") {
-                        // This is a whiteout file.
-                        // For .wh.<file>, we delete both the whiteout AND the target file.
-                        // For .wh..wh..opq, it's more complex (opaque dir), 
-                        // but for now we just handle the file hiding.
-                        let target_file_name = file_name.strip_prefix(".wh.").unwrap();
-                        let target_path = path.parent().unwrap().join(target_file_name);
-                        
-                        if target_path.exists() {
-                            if target_path.is_dir() {
-                                std::fs::remove_dir_all(&target_path)?;
-                            } else {
-                                std::fs::remove_file(&target_path)?;
-                            }
-                        }
-                        std::fs::remove_file(path)?; // Remove the whiteout marker itself
-                    }
-                }
-            }
-            Ok::<(), std::io::Error>(())
-        }).await.map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))??;
+            RootfsSnapshot::capture(&target_dir_clone, &provenance)
+        }).await.map_err(std::io::Error::other)?
+        .map_err(StoreError::from)
+    }
+}
 
-        Ok(())
+/// A layer being written by [`ImageStore::layer_writer`], hashed as it goes.
+/// Dropped without committing, it leaves nothing behind.
+pub struct LayerWriter {
+    file: tempfile::NamedTempFile,
+    hasher: Sha256,
+    size: u64,
+    dir: PathBuf,
+}
+
+impl LayerWriter {
+    /// Moves the layer to its digest's path; returns the digest
+    /// (`sha256:<hex>`) and the size in bytes.
+    pub fn commit(mut self) -> Result<(String, u64), StoreError> {
+        self.file.as_file_mut().sync_all()?;
+        let digest = format!("sha256:{}", hex::encode(self.hasher.finalize()));
+        self.file
+            .persist(self.dir.join(digest.replace(":", "_")))
+            .map_err(|e| StoreError::Io(e.error))?;
+        Ok((digest, self.size))
+    }
+}
+
+impl std::io::Write for LayerWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.file.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        self.size += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn is_whiteout(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| n.to_string_lossy().starts_with(".wh."))
+}
+
+/// The path a `.wh.<name>` whiteout hides. Opaque-directory markers hide
+/// nothing of their own, and a whiteout reaching outside the rootfs is
+/// ignored.
+fn whiteout_target(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?.strip_prefix(".wh.")?;
+    let inside = path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+    (inside && !name.is_empty() && !name.starts_with(".wh.")).then(|| path.with_file_name(name))
+}
+
+fn remove_whiteout_target(target: &Path) -> std::io::Result<()> {
+    let result = match target.symlink_metadata() {
+        Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(target),
+        Ok(_) => std::fs::remove_file(target),
+        Err(_) => return Ok(()),
+    };
+    match result {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
     }
 }
 
@@ -249,7 +333,7 @@ mod tests {
         store.save_layer(digest2, Bytes::from(layer2_data)).await.unwrap();
 
         let target_dir = tmp.path().join("rootfs");
-        store.compose_rootfs(&[digest1.to_string(), digest2.to_string()], target_dir.clone()).await.unwrap();
+        let snapshot = store.compose_rootfs(&[digest1.to_string(), digest2.to_string()], target_dir.clone()).await.unwrap();
 
         // Verify outcomes
         assert!(target_dir.join("usr/bin/app").exists());
@@ -258,5 +342,10 @@ mod tests {
 
         assert!(!target_dir.join("etc/config").exists());
         assert!(!target_dir.join("etc/.wh.config").exists());
+
+        // The snapshot records what is left, and which layer wrote it
+        assert_eq!(snapshot.entries["/usr/bin/app"].layer.as_deref(), Some(digest2));
+        assert!(!snapshot.entries.contains_key("/etc/config"));
+        assert!(snapshot.diff(&target_dir).unwrap().is_empty());
     }
 }
@@ -0,0 +1,270 @@
+//! A record of what composition wrote into a rootfs, so that the changes a
+//! container makes can be told apart from the files its image gave it.
+//!
+//! Paths are container paths (`/etc/passwd`); the root itself is not
+//! recorded. Changes follow Docker's rules: directories only count as
+//! modified for mode or ownership changes, or for changes inside them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
+
+const S_IFMT: u32 = 0o170000;
+const S_IFDIR: u32 = 0o040000;
+
+/// Stat of one path as composed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryRecord {
+    /// `st_mode`: file type and permission bits.
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u64,
+    pub mtime: i64,
+    pub mtime_nsec: i64,
+    /// Digest of the layer that last wrote the path; `None` for paths the
+    /// runtime added (mount targets).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<String>,
+}
+
+impl EntryRecord {
+    pub fn from_metadata(metadata: &std::fs::Metadata, layer: Option<String>) -> Self {
+        let (mode, uid, gid, mtime, mtime_nsec) = stat_fields(metadata);
+        Self {
+            mode,
+            uid,
+            gid,
+            size: metadata.len(),
+            mtime,
+            mtime_nsec,
+            layer,
+        }
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.mode & S_IFMT == S_IFDIR
+    }
+
+    /// Whether `other` is a change of this entry. Size and mtime are not
+    /// compared for directories.
+    fn differs(&self, other: &EntryRecord) -> bool {
+        self.mode != other.mode
+            || self.uid != other.uid
+            || self.gid != other.gid
+            || (!self.is_dir() && (self.size != other.size || self.mtime != other.mtime || self.mtime_nsec != other.mtime_nsec))
+    }
+}
+
+/// Docker's change kinds, with their API values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Modified = 0,
+    Added = 1,
+    Deleted = 2,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub path: String,
+    pub kind: ChangeKind,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RootfsSnapshot {
+    pub entries: BTreeMap<String, EntryRecord>,
+}
+
+impl RootfsSnapshot {
+    /// Stats everything under `rootfs`, without following symlinks.
+    /// `layers` maps paths to the layer that wrote them; paths it does not
+    /// list (trees copied in place of symlinks) take their closest listed
+    /// ancestor's. Blocking file I/O.
+    pub fn capture(rootfs: &Path, layers: &HashMap<String, String>) -> io::Result<Self> {
+        let mut entries = BTreeMap::new();
+        for entry in walkdir::WalkDir::new(rootfs).min_depth(1).follow_links(false) {
+            let entry = entry.map_err(io::Error::other)?;
+            let path = container_path(rootfs, entry.path());
+            let layer = ancestors(&path).find_map(|p| layers.get(p)).cloned();
+            let metadata = entry.metadata().map_err(io::Error::other)?;
+            entries.insert(path, EntryRecord::from_metadata(&metadata, layer));
+        }
+        Ok(Self { entries })
+    }
+
+    /// `None` if there is no snapshot at `path`.
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        match std::fs::read(path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let data = serde_json::to_vec(self).map_err(io::Error::other)?;
+        std::fs::write(path, data)
+    }
+
+    /// Records `paths` and their ancestors as they are now, unless already
+    /// recorded, as if composition had written them. Returns whether
+    /// anything was added.
+    pub fn record(&mut self, rootfs: &Path, paths: &[&str]) -> bool {
+        let mut added = false;
+        for path in paths {
+            for path in ancestors(path) {
+                if self.entries.contains_key(path) {
+                    continue;
+                }
+                if let Ok(metadata) = std::fs::symlink_metadata(rootfs.join(path.trim_start_matches('/'))) {
+                    self.entries.insert(path.to_string(), EntryRecord::from_metadata(&metadata, None));
+                    added = true;
+                }
+            }
+        }
+        added
+    }
+
+    /// Whether `path` is as composed, given its current `metadata`.
+    pub fn is_pristine(&self, path: &str, metadata: &std::fs::Metadata) -> bool {
+        self.entries
+            .get(path)
+            .is_some_and(|old| !old.differs(&EntryRecord::from_metadata(metadata, None)))
+    }
+
+    /// Changes of `rootfs` since the snapshot, sorted by path. Deleted trees
+    /// are reported at their top only; directories holding changes count
+    /// as modified. Blocking file I/O.
+    pub fn diff(&self, rootfs: &Path) -> io::Result<Vec<Change>> {
+        let current = Self::capture(rootfs, &HashMap::new())?;
+        let mut changes = BTreeMap::new();
+
+        for (path, new) in &current.entries {
+            match self.entries.get(path) {
+                None => {
+                    changes.insert(path.clone(), ChangeKind::Added);
+                }
+                Some(old) if old.differs(new) => {
+                    changes.insert(path.clone(), ChangeKind::Modified);
+                }
+                Some(_) => {}
+            }
+        }
+
+        let is_dir = |entries: &BTreeMap<String, EntryRecord>, path: &str| {
+            path == "/" || entries.get(path).is_some_and(EntryRecord::is_dir)
+        };
+        for path in self.entries.keys() {
+            let parent = parent(path);
+            if !current.entries.contains_key(path) && is_dir(&current.entries, parent) && is_dir(&self.entries, parent) {
+                changes.insert(path.clone(), ChangeKind::Deleted);
+            }
+        }
+
+        let holders: Vec<String> = changes
+            .keys()
+            .flat_map(|path| ancestors(path).skip(1).map(str::to_string))
+            .collect();
+        for dir in holders {
+            changes.entry(dir).or_insert(ChangeKind::Modified);
+        }
+
+        Ok(changes.into_iter().map(|(path, kind)| Change { path, kind }).collect())
+    }
+}
+
+/// `/a/b` for `<rootfs>/a/b`.
+pub fn container_path(rootfs: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(rootfs).unwrap_or(path);
+    let mut container = String::new();
+    for component in relative.components() {
+        if let std::path::Component::Normal(part) = component {
+            container.push('/');
+            container.push_str(&part.to_string_lossy());
+        }
+    }
+    if container.is_empty() {
+        container.push('/');
+    }
+    container
+}
+
+/// `path` and its ancestors, closest first, without the root.
+fn ancestors(path: &str) -> impl Iterator<Item = &str> {
+    std::iter::successors(Some(path), |p| Some(parent(p))).take_while(|p| *p != "/" && !p.is_empty())
+}
+
+fn parent(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) => &path[..i],
+    }
+}
+
+#[cfg(unix)]
+fn stat_fields(metadata: &std::fs::Metadata) -> (u32, u32, u32, i64, i64) {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.mode(), metadata.uid(), metadata.gid(), metadata.mtime(), metadata.mtime_nsec())
+}
+
+#[cfg(not(unix))]
+fn stat_fields(metadata: &std::fs::Metadata) -> (u32, u32, u32, i64, i64) {
+    let mode = if metadata.is_dir() {
+        S_IFDIR | 0o755
+    } else if metadata.file_type().is_symlink() {
+        0o120777
+    } else {
+        0o100644
+    };
+    let mtime = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .unwrap_or_default();
+    (mode, 0, 0, mtime.as_secs() as i64, i64::from(mtime.subsec_nanos()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_diff_follows_docker_rules() {
+        let tmp = TempDir::new().unwrap();
+        let rootfs = tmp.path();
+        for dir in ["etc", "usr/lib/old", "var/cache"] {
+            std::fs::create_dir_all(rootfs.join(dir)).unwrap();
+        }
+        std::fs::write(rootfs.join("etc/passwd"), "root").unwrap();
+        std::fs::write(rootfs.join("usr/lib/old/a"), "a").unwrap();
+        std::fs::write(rootfs.join("var/cache/keep"), "k").unwrap();
+        let layers = HashMap::from([("/etc".to_string(), "sha256:base".to_string())]);
+        let snapshot = RootfsSnapshot::capture(rootfs, &layers).unwrap();
+        assert_eq!(snapshot.entries["/etc/passwd"].layer.as_deref(), Some("sha256:base"));
+        assert_eq!(snapshot.diff(rootfs).unwrap(), vec![]);
+
+        std::fs::write(rootfs.join("etc/passwd"), "root:x").unwrap();
+        std::fs::remove_dir_all(rootfs.join("usr/lib/old")).unwrap();
+        std::fs::create_dir_all(rootfs.join("srv/app")).unwrap();
+        std::fs::write(rootfs.join("srv/app/run"), "").unwrap();
+
+        let change = |path: &str, kind| Change { path: path.to_string(), kind };
+        assert_eq!(
+            snapshot.diff(rootfs).unwrap(),
+            vec![
+                change("/etc", ChangeKind::Modified),
+                change("/etc/passwd", ChangeKind::Modified),
+                change("/srv", ChangeKind::Added),
+                change("/srv/app", ChangeKind::Added),
+                change("/srv/app/run", ChangeKind::Added),
+                change("/usr", ChangeKind::Modified),
+                change("/usr/lib", ChangeKind::Modified),
+                change("/usr/lib/old", ChangeKind::Deleted),
+            ]
+        );
+    }
+}
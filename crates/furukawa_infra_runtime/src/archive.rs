@@ -0,0 +1,483 @@
+//! Copying files in and out of container root filesystems (`docker cp`).
+//!
+//! Paths are container paths. Symlinks met while resolving them are
+//! followed inside the rootfs, never out of it: absolute targets restart
+//! at the rootfs and `..` stops at it. As in Docker, the last element of a
+//! path is itself only followed when the path ends in `/` or `/.`. Nothing
+//! here depends on the container running, as the rootfs is a plain
+//! directory on the host either way. Blocking file I/O.
+
+use crate::RuntimeError;
+use furukawa_common::diagnostic::Error;
+use furukawa_infra_fs::store::snapshot::{Change, ChangeKind};
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// Symlinks followed while resolving one path before giving up, as in Linux.
+const MAX_SYMLINKS: usize = 40;
+
+/// `os.FileMode` type bits, which Docker clients expect in path stats.
+mod go_mode {
+    pub const DIR: u32 = 1 << 31;
+    pub const SYMLINK: u32 = 1 << 27;
+    pub const DEVICE: u32 = 1 << 26;
+    pub const NAMED_PIPE: u32 = 1 << 25;
+    pub const SOCKET: u32 = 1 << 24;
+    pub const SETUID: u32 = 1 << 23;
+    pub const SETGID: u32 = 1 << 22;
+    pub const CHAR_DEVICE: u32 = 1 << 21;
+    pub const STICKY: u32 = 1 << 20;
+}
+
+/// What `HEAD /containers/:id/archive` reports about a path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathStat {
+    pub name: String,
+    pub size: u64,
+    /// Permission bits plus Go `os.FileMode` type bits.
+    pub mode: u32,
+    pub mtime: SystemTime,
+    /// For symlinks, the container path they resolve to; empty otherwise.
+    pub link_target: String,
+}
+
+impl PathStat {
+    pub fn is_dir(&self) -> bool {
+        self.mode & go_mode::DIR != 0
+    }
+}
+
+/// How `PUT /containers/:id/archive` extracts.
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    /// Fail instead of replacing a directory with a non-directory or the
+    /// other way round.
+    pub no_overwrite_dir_non_dir: bool,
+    /// Owner given to every extracted file; `None` keeps the archive's.
+    pub owner: Option<(u32, u32)>,
+}
+
+/// Resolves `path` inside `rootfs`, returning the normalized container
+/// path (always absolute). Missing elements are kept as they are.
+pub fn resolve(rootfs: &Path, path: &str) -> Result<PathBuf, Error> {
+    let follow_last = path.ends_with('/') || path.ends_with("/.");
+    let mut pending: VecDeque<OsString> = parts(Path::new(path));
+    let mut resolved = PathBuf::from("/");
+    let mut links = 0;
+
+    while let Some(part) = pending.pop_front() {
+        if part == ".." {
+            resolved.pop();
+            continue;
+        }
+        let candidate = resolved.join(&part);
+        if pending.is_empty() && !follow_last {
+            resolved = candidate;
+            break;
+        }
+        let host = on_host(rootfs, &candidate);
+        match fs::symlink_metadata(&host) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                links += 1;
+                if links > MAX_SYMLINKS {
+                    return Err(Error::new(RuntimeError::ArchiveFailed(format!(
+                        "too many levels of symbolic links: {}",
+                        path
+                    ))));
+                }
+                let target = fs::read_link(&host).map_err(|e| archive_failed(&host, e))?;
+                if target.is_absolute() {
+                    resolved = PathBuf::from("/");
+                }
+                for part in parts(&target).into_iter().rev() {
+                    pending.push_front(part);
+                }
+            }
+            _ => resolved = candidate,
+        }
+    }
+    Ok(resolved)
+}
+
+/// Where container path `path` (as returned by [`resolve`]) is on the host.
+pub fn on_host(rootfs: &Path, path: &Path) -> PathBuf {
+    rootfs.join(path.strip_prefix("/").unwrap_or(path))
+}
+
+/// Stats `path` (without following it if it is a symlink, see [`resolve`]).
+pub fn stat(rootfs: &Path, path: &str) -> Result<PathStat, Error> {
+    let resolved = resolve(rootfs, path)?;
+    let host = on_host(rootfs, &resolved);
+    let metadata = fs::symlink_metadata(&host).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => Error::new(RuntimeError::PathNotFound(path.to_string())),
+        _ => archive_failed(&host, e),
+    })?;
+    let link_target = if metadata.file_type().is_symlink() {
+        resolve(rootfs, &format!("{}/", resolved.display()))?.display().to_string()
+    } else {
+        String::new()
+    };
+    Ok(PathStat {
+        name: base_name(path),
+        size: metadata.len(),
+        mode: file_mode(&metadata),
+        mtime: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+        link_target,
+    })
+}
+
+/// Writes a tar stream of `path` to `out`, recursively for directories,
+/// named after the last element of `path` as given. Symlinks are archived
+/// as symlinks.
+pub fn archive(rootfs: &Path, path: &str, out: impl Write) -> Result<(), Error> {
+    let stat = stat(rootfs, path)?;
+    let host = on_host(rootfs, &resolve(rootfs, path)?);
+    let name = if stat.name == "/" { ".".to_string() } else { stat.name.clone() };
+
+    let mut builder = tar::Builder::new(out);
+    builder.follow_symlinks(false);
+    let appended = if stat.is_dir() {
+        builder.append_dir_all(&name, &host)
+    } else {
+        builder.append_path_with_name(&host, &name)
+    };
+    appended.and_then(|_| builder.finish()).map_err(|e| archive_failed(&host, e))
+}
+
+/// Writes the whole rootfs to `out` as a tar stream, as `docker export`
+/// does: entries are relative to the root, symlinks are not followed.
+pub fn export(rootfs: &Path, out: impl Write) -> Result<(), Error> {
+    let mut builder = tar::Builder::new(out);
+    builder.follow_symlinks(false);
+    builder
+        .append_dir_all("", rootfs)
+        .and_then(|_| builder.finish())
+        .map_err(|e| archive_failed(rootfs, e))
+}
+
+/// Writes `changes` of the rootfs to `out` as an image layer: a tar stream
+/// of what was added or modified (directories without their contents),
+/// and a `.wh.<name>` whiteout for each deleted path.
+pub fn changes_layer(rootfs: &Path, changes: &[Change], out: impl Write) -> Result<(), Error> {
+    let mut builder = tar::Builder::new(out);
+    builder.follow_symlinks(false);
+    for change in changes {
+        let name = change.path.trim_start_matches('/');
+        let appended = match change.kind {
+            ChangeKind::Added | ChangeKind::Modified => builder.append_path_with_name(rootfs.join(name), name),
+            ChangeKind::Deleted => {
+                let (parent, base) = name.rsplit_once('/').unwrap_or(("", name));
+                let whiteout = Path::new(parent).join(format!(".wh.{}", base));
+                let mut header = tar::Header::new_gnu();
+                header.set_entry_type(tar::EntryType::Regular);
+                header.set_size(0);
+                header.set_mode(0o644);
+                builder.append_data(&mut header, whiteout, io::empty())
+            }
+        };
+        appended.map_err(|e| archive_failed(&rootfs.join(name), e))?;
+    }
+    builder.finish().map_err(|e| archive_failed(rootfs, e))
+}
+
+/// Extracts the tar stream `input` (optionally gzipped) into the directory
+/// `path`, which must exist.
+pub fn extract(rootfs: &Path, path: &str, input: impl Read, options: &ExtractOptions) -> Result<(), Error> {
+    // The extraction point is followed all the way, as in Docker.
+    let dest = resolve(rootfs, &format!("{}/", path.trim_end_matches('/')))?;
+    match fs::metadata(on_host(rootfs, &dest)) {
+        Ok(metadata) if metadata.is_dir() => {}
+        Ok(_) => return Err(Error::new(RuntimeError::NotADirectory(path.to_string()))),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(Error::new(RuntimeError::PathNotFound(path.to_string())));
+        }
+        Err(e) => return Err(archive_failed(&on_host(rootfs, &dest), e)),
+    }
+
+    let mut input = io::BufReader::new(input);
+    let gzipped = io::BufRead::fill_buf(&mut input).map(|buf| buf.starts_with(&[0x1f, 0x8b])).unwrap_or(false);
+    let reader: Box<dyn Read> = if gzipped { Box::new(flate2::read::GzDecoder::new(input)) } else { Box::new(input) };
+    let mut archive = tar::Archive::new(reader);
+    archive.set_preserve_permissions(true);
+    archive.set_preserve_ownerships(options.owner.is_none());
+
+    let failed = |e: io::Error| Error::new(RuntimeError::ArchiveFailed(format!("Error extracting tar: {}", e)));
+    for entry in archive.entries().map_err(failed)? {
+        let mut entry = entry.map_err(failed)?;
+        let name = entry.path().map_err(failed)?.into_owned();
+        // Entries may not climb out of the extraction point.
+        let relative: PathBuf = parts(&name).iter().filter(|p| *p != "..").collect();
+        if relative.as_os_str().is_empty() {
+            continue;
+        }
+
+        let parent = match relative.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => resolve(rootfs, &format!("{}/", dest.join(parent).display()))?,
+            _ => dest.clone(),
+        };
+        let parent_host = on_host(rootfs, &parent);
+        fs::create_dir_all(&parent_host).map_err(|e| archive_failed(&parent_host, e))?;
+        let file_name = relative.file_name().unwrap_or_default();
+        let target = parent_host.join(file_name);
+
+        let kind = entry.header().entry_type();
+        if let Ok(existing) = fs::symlink_metadata(&target) {
+            let both_dirs = existing.is_dir() && kind.is_dir();
+            if options.no_overwrite_dir_non_dir && existing.is_dir() != kind.is_dir() {
+                let (existing_kind, new_kind) = if existing.is_dir() {
+                    ("directory", "non-directory")
+                } else {
+                    ("non-directory", "directory")
+                };
+                return Err(Error::new(RuntimeError::OverwriteConflict(format!(
+                    "cannot overwrite {} \"{}\" with {} \"{}\"",
+                    existing_kind,
+                    parent.join(file_name).display(),
+                    new_kind,
+                    name.display()
+                ))));
+            }
+            if !both_dirs {
+                let removed = if existing.is_dir() { fs::remove_dir_all(&target) } else { fs::remove_file(&target) };
+                removed.map_err(|e| archive_failed(&target, e))?;
+            }
+        }
+
+        if kind.is_hard_link() {
+            // Link names are relative to the extraction point too.
+            let link = entry.link_name().map_err(failed)?.unwrap_or_default().into_owned();
+            let source: PathBuf = parts(&link).iter().filter(|p| *p != "..").collect();
+            let source = on_host(rootfs, &resolve(rootfs, &dest.join(source).display().to_string())?);
+            fs::hard_link(&source, &target).map_err(|e| archive_failed(&target, e))?;
+        } else {
+            entry.unpack(&target).map_err(|e| archive_failed(&target, e))?;
+        }
+        if let Some((uid, gid)) = options.owner {
+            chown(&target, uid, gid).map_err(|e| archive_failed(&target, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// The `Normal` and `..` elements of `path`, in order.
+fn parts(path: &Path) -> VecDeque<OsString> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(part) => Some(part.to_os_string()),
+            Component::ParentDir => Some(OsString::from("..")),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Last element of `path` as given, `/` for the root.
+fn base_name(path: &str) -> String {
+    Path::new(path)
+        .components()
+        .rfind(|c| matches!(c, Component::Normal(_) | Component::ParentDir))
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .unwrap_or_else(|| "/".to_string())
+}
+
+fn archive_failed(path: &Path, e: io::Error) -> Error {
+    Error::new(RuntimeError::ArchiveFailed(format!("{}: {}", path.display(), e)))
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    let raw = metadata.mode();
+    let file_type = metadata.file_type();
+    let mut mode = raw & 0o777;
+    for (set, bit) in [
+        (file_type.is_dir(), go_mode::DIR),
+        (file_type.is_symlink(), go_mode::SYMLINK),
+        (file_type.is_block_device(), go_mode::DEVICE),
+        (file_type.is_char_device(), go_mode::DEVICE | go_mode::CHAR_DEVICE),
+        (file_type.is_fifo(), go_mode::NAMED_PIPE),
+        (file_type.is_socket(), go_mode::SOCKET),
+        (raw & 0o4000 != 0, go_mode::SETUID),
+        (raw & 0o2000 != 0, go_mode::SETGID),
+        (raw & 0o1000 != 0, go_mode::STICKY),
+    ] {
+        if set {
+            mode |= bit;
+        }
+    }
+    mode
+}
+
+#[cfg(not(unix))]
+fn file_mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.is_dir() {
+        go_mode::DIR | 0o755
+    } else if metadata.file_type().is_symlink() {
+        go_mode::SYMLINK | 0o777
+    } else if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o644
+    }
+}
+
+#[cfg(unix)]
+fn chown(path: &Path, uid: u32, gid: u32) -> io::Result<()> {
+    std::os::unix::fs::lchown(path, Some(uid), Some(gid))
+}
+
+#[cfg(not(unix))]
+fn chown(_path: &Path, _uid: u32, _gid: u32) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn test_resolve_stays_in_rootfs() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let rootfs = tmp.path();
+        fs::create_dir_all(rootfs.join("usr/lib")).unwrap();
+        symlink("/usr/lib", rootfs.join("lib")).unwrap();
+        symlink("../../../../etc", rootfs.join("usr/escape")).unwrap();
+        symlink("loop", rootfs.join("loop")).unwrap();
+
+        assert_eq!(resolve(rootfs, "/lib/x").unwrap(), PathBuf::from("/usr/lib/x"));
+        assert_eq!(resolve(rootfs, "lib").unwrap(), PathBuf::from("/lib"));
+        assert_eq!(resolve(rootfs, "/lib/").unwrap(), PathBuf::from("/usr/lib"));
+        assert_eq!(resolve(rootfs, "/usr/escape/passwd").unwrap(), PathBuf::from("/etc/passwd"));
+        assert_eq!(resolve(rootfs, "/../../etc").unwrap(), PathBuf::from("/etc"));
+        assert!(resolve(rootfs, "/loop/").is_err());
+
+        let stat = stat(rootfs, "/lib").unwrap();
+        assert_eq!(stat.link_target, "/usr/lib");
+        assert_ne!(stat.mode & go_mode::SYMLINK, 0);
+    }
+
+    /// Appends an entry named `name` as given, `..` included, which
+    /// `tar::Builder` refuses to write.
+    fn append(tar: &mut tar::Builder<Vec<u8>>, name: &str, kind: tar::EntryType, mode: u32, link: Option<&str>, data: &[u8]) {
+        let mut header = tar::Header::new_gnu();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_entry_type(kind);
+        header.set_mode(mode);
+        header.set_size(data.len() as u64);
+        header.set_uid(nix::unistd::getuid().as_raw().into());
+        header.set_gid(nix::unistd::getgid().as_raw().into());
+        header.set_mtime(0);
+        if let Some(link) = link {
+            header.set_link_name(link).unwrap();
+        }
+        header.set_cksum();
+        tar.append(&header, data).unwrap();
+    }
+
+    fn file(tar: &mut tar::Builder<Vec<u8>>, name: &str, data: &[u8]) {
+        append(tar, name, tar::EntryType::Regular, 0o644, None, data);
+    }
+
+    /// A rootfs with an empty `/dst` to extract into.
+    fn rootfs() -> (tempfile::TempDir, PathBuf) {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let rootfs = tmp.path().join("rootfs");
+        fs::create_dir_all(rootfs.join("dst")).unwrap();
+        (tmp, rootfs)
+    }
+
+    fn extract_all(rootfs: &Path, tar: tar::Builder<Vec<u8>>, options: &ExtractOptions) -> Result<(), Error> {
+        extract(rootfs, "/dst", &tar.into_inner().unwrap()[..], options)
+    }
+
+    #[test]
+    fn test_extract_stays_in_extraction_point() {
+        let (tmp, rootfs) = rootfs();
+        let mut tar = tar::Builder::new(Vec::new());
+        file(&mut tar, "../../escape", b"x");
+        file(&mut tar, "sub/../../up", b"x");
+        extract_all(&rootfs, tar, &ExtractOptions::default()).unwrap();
+
+        assert!(rootfs.join("dst/escape").is_file());
+        assert!(rootfs.join("dst/sub/up").is_file());
+        assert!(!rootfs.join("escape").exists());
+        assert!(!tmp.path().join("escape").exists());
+    }
+
+    #[test]
+    fn test_extract_under_symlink_to_root_stays_in_rootfs() {
+        let (tmp, rootfs) = rootfs();
+        // Unique, so the host check below cannot see an unrelated file.
+        let marker = tmp.path().file_name().unwrap().to_string_lossy().into_owned();
+        symlink("/", rootfs.join("dst/existing")).unwrap();
+
+        let mut tar = tar::Builder::new(Vec::new());
+        append(&mut tar, "fresh", tar::EntryType::Symlink, 0o777, Some("/"), b"");
+        file(&mut tar, &format!("fresh/{}-a", marker), b"x");
+        file(&mut tar, &format!("existing/{}-b", marker), b"x");
+        extract_all(&rootfs, tar, &ExtractOptions::default()).unwrap();
+
+        for name in [format!("{}-a", marker), format!("{}-b", marker)] {
+            assert!(rootfs.join(&name).is_file(), "{} is not in the rootfs", name);
+            assert!(!Path::new("/").join(&name).exists(), "{} was written to the host", name);
+        }
+    }
+
+    #[test]
+    fn test_extract_hard_links_resolve_in_rootfs() {
+        use std::os::unix::fs::MetadataExt;
+
+        let (_tmp, rootfs) = rootfs();
+        let mut tar = tar::Builder::new(Vec::new());
+        file(&mut tar, "a", b"shared");
+        append(&mut tar, "b", tar::EntryType::Link, 0o644, Some("../../a"), b"");
+        append(&mut tar, "c", tar::EntryType::Link, 0o644, Some("/a"), b"");
+        extract_all(&rootfs, tar, &ExtractOptions::default()).unwrap();
+
+        let inode = fs::metadata(rootfs.join("dst/a")).unwrap().ino();
+        for name in ["dst/b", "dst/c"] {
+            assert_eq!(fs::metadata(rootfs.join(name)).unwrap().ino(), inode, "{} is not linked to /dst/a", name);
+        }
+    }
+
+    #[test]
+    fn test_extract_no_overwrite_dir_non_dir() {
+        let (_tmp, rootfs) = rootfs();
+        fs::create_dir(rootfs.join("dst/dir")).unwrap();
+        fs::write(rootfs.join("dst/file"), "x").unwrap();
+        let options = ExtractOptions { no_overwrite_dir_non_dir: true, ..ExtractOptions::default() };
+
+        let mut tar = tar::Builder::new(Vec::new());
+        file(&mut tar, "dir", b"x");
+        let err = extract_all(&rootfs, tar, &options).unwrap_err();
+        assert_eq!(err.code(), "RUNTIME_OVERWRITE_CONFLICT");
+
+        let mut tar = tar::Builder::new(Vec::new());
+        append(&mut tar, "file", tar::EntryType::Directory, 0o755, None, b"");
+        let err = extract_all(&rootfs, tar, &options).unwrap_err();
+        assert_eq!(err.code(), "RUNTIME_OVERWRITE_CONFLICT");
+
+        assert!(rootfs.join("dst/dir").is_dir());
+        assert!(rootfs.join("dst/file").is_file());
+    }
+
+    #[test]
+    fn test_extract_preserves_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_tmp, rootfs) = rootfs();
+        let mut tar = tar::Builder::new(Vec::new());
+        append(&mut tar, "private", tar::EntryType::Directory, 0o700, None, b"");
+        append(&mut tar, "private/run.sh", tar::EntryType::Regular, 0o751, None, b"#!/bin/sh\n");
+        extract_all(&rootfs, tar, &ExtractOptions::default()).unwrap();
+
+        let mode = |name: &str| fs::metadata(rootfs.join(name)).unwrap().permissions().mode() & 0o7777;
+        assert_eq!(mode("dst/private"), 0o700);
+        assert_eq!(mode("dst/private/run.sh"), 0o751);
+    }
+}
@@ -0,0 +1,111 @@
+//! Live container I/O for attach sessions.
+//!
+//! The log copiers publish every chunk of container output here as well as
+//! writing it to the log, and the runtimes register the write end of the
+//! container's stdin (if it was created with `OpenStdin`). Channels exist per
+//! container ID from the first use on, so a client can attach before the
+//! container is started.
+
+use crate::logs::Stream;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::{broadcast, Notify};
+
+/// Chunks buffered per subscriber before a slow client starts missing output.
+const OUTPUT_CAPACITY: usize = 1024;
+
+/// A chunk of container output, as read (not split into lines).
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub stream: Stream,
+    pub data: Bytes,
+}
+
+type StdinWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+struct Channels {
+    output: broadcast::Sender<Output>,
+    stdin: tokio::sync::Mutex<Option<StdinWriter>>,
+    stdin_ready: Notify,
+}
+
+#[derive(Default)]
+pub struct AttachHub {
+    containers: Mutex<HashMap<String, Arc<Channels>>>,
+}
+
+impl AttachHub {
+    fn channels(&self, container_id: &str) -> Arc<Channels> {
+        let mut containers = match self.containers.lock() {
+            Ok(containers) => containers,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        containers
+            .entry(container_id.to_string())
+            .or_insert_with(|| {
+                Arc::new(Channels {
+                    output: broadcast::channel(OUTPUT_CAPACITY).0,
+                    stdin: tokio::sync::Mutex::new(None),
+                    stdin_ready: Notify::new(),
+                })
+            })
+            .clone()
+    }
+
+    /// Receives the container's output from now on.
+    pub fn subscribe(&self, container_id: &str) -> broadcast::Receiver<Output> {
+        self.channels(container_id).output.subscribe()
+    }
+
+    pub(crate) fn publish(&self, container_id: &str, stream: Stream, data: &[u8]) {
+        let channels = self.channels(container_id);
+        if channels.output.receiver_count() > 0 {
+            let _ = channels.output.send(Output {
+                stream,
+                data: Bytes::copy_from_slice(data),
+            });
+        }
+    }
+
+    /// Registers the write end of a freshly started container's stdin.
+    pub async fn set_stdin(&self, container_id: &str, stdin: StdinWriter) {
+        let channels = self.channels(container_id);
+        *channels.stdin.lock().await = Some(stdin);
+        channels.stdin_ready.notify_waiters();
+    }
+
+    /// Writes to the container's stdin, waiting for the container to be
+    /// started if it has not been yet.
+    pub async fn write_stdin(&self, container_id: &str, data: &[u8]) -> std::io::Result<()> {
+        let channels = self.channels(container_id);
+        loop {
+            let ready = channels.stdin_ready.notified();
+            tokio::pin!(ready);
+            ready.as_mut().enable();
+
+            if let Some(stdin) = channels.stdin.lock().await.as_mut() {
+                stdin.write_all(data).await?;
+                return stdin.flush().await;
+            }
+            ready.await;
+        }
+    }
+
+    /// Closes the container's stdin, so that it reads EOF (`StdinOnce`).
+    pub async fn close_stdin(&self, container_id: &str) {
+        let channels = self.channels(container_id);
+        let stdin = channels.stdin.lock().await.take();
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.shutdown().await;
+        }
+    }
+
+    /// Drops the channels of a removed container.
+    pub fn remove(&self, container_id: &str) {
+        if let Ok(mut containers) = self.containers.lock() {
+            containers.remove(container_id);
+        }
+    }
+}
@@ -0,0 +1,343 @@
+//! Resource limits through cgroup v2.
+//!
+//! Every container of the native Linux runtime gets a cgroup of its own under
+//! `/sys/fs/cgroup/rustker`, with the limits of its `Resources` written to
+//! the controller files. Only plain file I/O is needed, so this module has no
+//! platform gate; on hosts without a unified hierarchy [`Cgroups::init`]
+//! finds nothing and every limit is discarded with a warning, as Docker does.
+
+use crate::RuntimeError;
+use furukawa_common::diagnostic::Error;
+use furukawa_domain::container::Resources;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Where the unified hierarchy is mounted.
+pub const MOUNT: &str = "/sys/fs/cgroup";
+
+/// Parent of the containers' cgroups, relative to the mount.
+const PARENT: &str = "rustker";
+
+/// `cpu.max` period in microseconds (Docker's CFS default).
+pub const CPU_PERIOD: i64 = 100_000;
+
+/// Smallest `cpu.max` quota the kernel accepts.
+const MIN_CPU_QUOTA: i64 = 1_000;
+
+/// How long [`Cgroups::freeze`] waits for the kernel, in polls.
+const FREEZE_POLLS: u32 = 200;
+const FREEZE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(10);
+
+/// Controllers the daemon enables for its containers.
+const CONTROLLERS: &[&str] = &["cpu", "cpuset", "memory", "pids"];
+
+/// Which limits can be enforced on this host.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Capabilities {
+    pub memory: bool,
+    pub swap: bool,
+    pub cpu: bool,
+    pub cpuset: bool,
+    pub pids: bool,
+}
+
+impl Capabilities {
+    /// Discards the limits that cannot be enforced and returns a warning for
+    /// each (Docker's wording). Also fills in Docker's default swap limit of
+    /// twice the memory limit.
+    pub fn apply(&self, resources: &mut Resources) -> Vec<String> {
+        let mut warnings = Vec::new();
+
+        if resources.memory.is_some() && !self.memory {
+            warnings.push(
+                "Your kernel does not support memory limit capabilities or the cgroup is not mounted. Limitation discarded."
+                    .to_string(),
+            );
+            resources.memory = None;
+            resources.memory_swap = None;
+        }
+        if let Some(memory) = resources.memory {
+            if !self.swap {
+                if resources.memory_swap.is_some_and(|swap| swap != -1) {
+                    warnings.push(
+                        "Your kernel does not support swap limit capabilities or the cgroup is not mounted. Memory limited without swap."
+                            .to_string(),
+                    );
+                }
+                resources.memory_swap = None;
+            } else if resources.memory_swap.is_none() {
+                resources.memory_swap = Some(memory * 2);
+            }
+        }
+        if resources.nano_cpus.is_some() && !self.cpu {
+            warnings.push(
+                "Your kernel does not support CPU CFS scheduler or the cgroup is not mounted. NanoCPUs discarded.".to_string(),
+            );
+            resources.nano_cpus = None;
+        }
+        if resources.cpu_shares.is_some() && !self.cpu {
+            warnings.push("Your kernel does not support CPU shares or the cgroup is not mounted. Shares discarded.".to_string());
+            resources.cpu_shares = None;
+        }
+        if resources.cpuset_cpus.is_some() && !self.cpuset {
+            warnings.push("Your kernel does not support cpuset or the cgroup is not mounted. Cpuset discarded.".to_string());
+            resources.cpuset_cpus = None;
+        }
+        if resources.pids_limit.is_some() && !self.pids {
+            warnings.push(
+                "Your kernel does not support PIDs limit capabilities or the cgroup is not mounted. PIDs limit discarded."
+                    .to_string(),
+            );
+            resources.pids_limit = None;
+        }
+        warnings
+    }
+}
+
+/// The daemon's part of the cgroup hierarchy.
+pub struct Cgroups {
+    root: PathBuf,
+    capabilities: Capabilities,
+}
+
+impl Cgroups {
+    /// Creates the parent cgroup under `mount` and delegates the controllers
+    /// to it. Returns `None` if `mount` is not a cgroup v2 hierarchy or the
+    /// parent cannot be created (e.g. without root).
+    pub fn init(mount: &Path) -> Option<Self> {
+        if !mount.join("cgroup.controllers").exists() {
+            info!("No cgroup v2 hierarchy at {}; resource limits are unavailable", mount.display());
+            return None;
+        }
+        let root = mount.join(PARENT);
+        if let Err(e) = std::fs::create_dir_all(&root) {
+            warn!("Failed to create cgroup {}: {}", root.display(), e);
+            return None;
+        }
+
+        // One at a time, so that a controller in use elsewhere (e.g. by
+        // cgroup v1) does not keep the others from being enabled.
+        enable_controllers(mount, CONTROLLERS);
+        enable_controllers(&root, CONTROLLERS);
+
+        let enabled = read_list(&root.join("cgroup.subtree_control"));
+        let has = |controller: &str| enabled.iter().any(|c| c == controller);
+        let capabilities = Capabilities {
+            memory: has("memory"),
+            swap: has("memory") && root.join("memory.swap.max").exists(),
+            cpu: has("cpu"),
+            cpuset: has("cpuset"),
+            pids: has("pids"),
+        };
+        info!("Using cgroup {} with {:?}", root.display(), capabilities);
+        Some(Self { root, capabilities })
+    }
+
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+
+    /// The container's cgroup directory.
+    pub fn path(&self, container_id: &str) -> PathBuf {
+        self.root.join(container_id)
+    }
+
+    /// Creates (or updates) the container's cgroup with its limits and
+    /// returns its directory. Limits the host cannot enforce are skipped.
+    pub fn create(&self, container_id: &str, resources: &Resources) -> Result<PathBuf, Error> {
+        let path = self.path(container_id);
+        let failed = |file: &str, e: std::io::Error| {
+            Error::new(RuntimeError::CgroupSetupFailed(format!("{}: {}", path.join(file).display(), e)))
+        };
+        match std::fs::create_dir(&path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => return Err(failed("", e)),
+        }
+
+        let caps = self.capabilities;
+        let mut files = Vec::new();
+        if caps.memory {
+            files.push(("memory.max", limit(resources.memory)));
+        }
+        if caps.swap {
+            files.push(("memory.swap.max", swap_max(resources)));
+        }
+        if caps.cpu {
+            files.push(("cpu.max", cpu_max(resources.nano_cpus)));
+            files.push(("cpu.weight", cpu_weight(resources.cpu_shares).to_string()));
+        }
+        if caps.cpuset {
+            files.push(("cpuset.cpus", resources.cpuset_cpus.clone().unwrap_or_default()));
+        }
+        if caps.pids {
+            files.push(("pids.max", limit(resources.pids_limit.filter(|&n| n > 0))));
+        }
+        for (file, value) in files {
+            std::fs::write(path.join(file), value).map_err(|e| failed(file, e))?;
+        }
+        Ok(path)
+    }
+
+    /// Freezes or thaws every process in the container's cgroup and waits
+    /// until the kernel reports it done. Blocking.
+    pub fn freeze(&self, container_id: &str, frozen: bool) -> std::io::Result<()> {
+        let path = self.path(container_id);
+        let state = if frozen { "1" } else { "0" };
+        std::fs::write(path.join("cgroup.freeze"), state)?;
+
+        // Freezing is asynchronous; `cgroup.events` has `frozen 1` once every
+        // process has stopped.
+        let done = format!("frozen {}", state);
+        for _ in 0..FREEZE_POLLS {
+            if std::fs::read_to_string(path.join("cgroup.events"))?.lines().any(|l| l == done) {
+                return Ok(());
+            }
+            std::thread::sleep(FREEZE_POLL_INTERVAL);
+        }
+        Err(std::io::Error::new(std::io::ErrorKind::TimedOut, format!("cgroup {} did not reach {:?}", path.display(), done)))
+    }
+
+    /// Removes the container's cgroup; it must have no processes left.
+    pub fn remove(&self, container_id: &str) {
+        match std::fs::remove_dir(self.path(container_id)) {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => warn!("Failed to remove cgroup of {}: {}", container_id, e),
+        }
+    }
+}
+
+/// Enables those of the given controllers that `cgroup` has and has not
+/// enabled yet for its children. Failures show in what ends up enabled.
+fn enable_controllers(cgroup: &Path, controllers: &[&str]) {
+    let available = read_list(&cgroup.join("cgroup.controllers"));
+    let control = cgroup.join("cgroup.subtree_control");
+    let enabled = read_list(&control);
+    for controller in controllers {
+        if available.iter().any(|c| c == controller) && !enabled.iter().any(|c| c == controller) {
+            let _ = std::fs::write(&control, format!("+{}", controller));
+        }
+    }
+}
+
+fn read_list(file: &Path) -> Vec<String> {
+    std::fs::read_to_string(file)
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+fn limit(value: Option<i64>) -> String {
+    value.map_or_else(|| "max".to_string(), |v| v.to_string())
+}
+
+/// cgroup v2 limits swap alone, where Docker's `MemorySwap` is memory plus
+/// swap.
+fn swap_max(resources: &Resources) -> String {
+    match (resources.memory, resources.memory_swap) {
+        (Some(_), Some(-1)) | (None, _) => "max".to_string(),
+        (Some(memory), Some(swap)) => (swap - memory).max(0).to_string(),
+        (Some(_), None) => "0".to_string(),
+    }
+}
+
+/// `cpu.max` for a quota in units of 10^-9 CPUs.
+fn cpu_max(nano_cpus: Option<i64>) -> String {
+    match nano_cpus {
+        Some(nano) => {
+            let quota = (nano * CPU_PERIOD / 1_000_000_000).max(MIN_CPU_QUOTA);
+            format!("{} {}", quota, CPU_PERIOD)
+        }
+        None => format!("max {}", CPU_PERIOD),
+    }
+}
+
+/// Maps cgroup v1 shares (2..=262144) onto `cpu.weight` (1..=10000) the way
+/// runc does; Docker's default of 1024 becomes about 39, unset the kernel's
+/// default of 100.
+fn cpu_weight(shares: Option<i64>) -> i64 {
+    match shares {
+        Some(shares) => {
+            let shares = shares.clamp(2, 262_144);
+            1 + ((shares - 2) * 9_999) / 262_142
+        }
+        None => 100,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: i64 = 1024 * 1024;
+
+    #[test]
+    fn test_limit_conversions() {
+        assert_eq!(cpu_max(Some(1_500_000_000)), "150000 100000");
+        assert_eq!(cpu_max(Some(1)), "1000 100000");
+        assert_eq!(cpu_max(None), "max 100000");
+        assert_eq!(cpu_weight(Some(1024)), 39);
+        assert_eq!(cpu_weight(Some(2)), 1);
+        assert_eq!(cpu_weight(Some(262_144)), 10_000);
+        assert_eq!(cpu_weight(None), 100);
+
+        let resources = |memory, memory_swap| Resources { memory, memory_swap, ..Default::default() };
+        assert_eq!(swap_max(&resources(Some(64 * MB), Some(96 * MB))), (32 * MB).to_string());
+        assert_eq!(swap_max(&resources(Some(64 * MB), Some(-1))), "max");
+        assert_eq!(swap_max(&resources(Some(64 * MB), None)), "0");
+        assert_eq!(swap_max(&resources(None, None)), "max");
+    }
+
+    #[test]
+    fn test_apply_discards_unsupported_limits() {
+        let mut resources = Resources {
+            memory: Some(64 * MB),
+            pids_limit: Some(10),
+            ..Default::default()
+        };
+        let warnings = Capabilities::default().apply(&mut resources);
+        assert_eq!(warnings.len(), 2);
+        assert!(resources.is_empty());
+
+        let mut resources = Resources { memory: Some(64 * MB), ..Default::default() };
+        let all = Capabilities { memory: true, swap: true, cpu: true, cpuset: true, pids: true };
+        assert!(all.apply(&mut resources).is_empty());
+        assert_eq!(resources.memory_swap, Some(128 * MB));
+    }
+
+    #[test]
+    fn test_create_writes_limits() {
+        // A stand-in for the hierarchy: plain files, as the kernel would
+        // present them once the controllers are enabled.
+        let tmp = tempfile::TempDir::new().unwrap();
+        let mount = tmp.path();
+        std::fs::create_dir_all(mount.join(PARENT)).unwrap();
+        std::fs::write(mount.join("cgroup.controllers"), "cpu memory pids").unwrap();
+        std::fs::write(mount.join("cgroup.subtree_control"), "cpu memory pids").unwrap();
+        std::fs::write(mount.join(PARENT).join("cgroup.controllers"), "cpu memory pids").unwrap();
+        std::fs::write(mount.join(PARENT).join("cgroup.subtree_control"), "cpu memory pids").unwrap();
+
+        let cgroups = Cgroups::init(mount).expect("cgroup v2 mount");
+        assert_eq!(
+            cgroups.capabilities(),
+            Capabilities { memory: true, swap: false, cpu: true, cpuset: false, pids: true }
+        );
+
+        let resources = Resources {
+            memory: Some(64 * MB),
+            nano_cpus: Some(500_000_000),
+            pids_limit: Some(-1),
+            ..Default::default()
+        };
+        let path = cgroups.create("abc", &resources).unwrap();
+        let read = |file: &str| std::fs::read_to_string(path.join(file)).unwrap();
+        assert_eq!(read("memory.max"), (64 * MB).to_string());
+        assert_eq!(read("cpu.max"), "50000 100000");
+        assert_eq!(read("pids.max"), "max");
+        assert!(!path.join("cpuset.cpus").exists());
+
+        assert!(Cgroups::init(&mount.join("missing")).is_none());
+    }
+}
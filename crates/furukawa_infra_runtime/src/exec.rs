@@ -0,0 +1,340 @@
+//! Exec instances: additional processes run inside a running container.
+//!
+//! An exec is created with its [`ExecConfig`], started at most once, and
+//! kept with its exit code until the container is removed; `docker exec`
+//! reads that exit code once the process's output has ended.
+
+use crate::supervisor::exit_code;
+use async_trait::async_trait;
+use furukawa_common::diagnostic::{Diagnosable, Error};
+use furukawa_domain::container::store::ContainerStore;
+use furukawa_domain::container::{AnyContainer, Container, Running};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::process::{Child, ChildStderr, ChildStdin, ChildStdout};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+/// What to run, as given to `POST /containers/:id/exec`.
+#[derive(Debug, Clone, Default)]
+pub struct ExecConfig {
+    pub cmd: Vec<String>,
+    /// `KEY=VALUE` entries added to the container's environment.
+    pub env: Vec<String>,
+    /// Overrides the container's user.
+    pub user: Option<String>,
+    /// Overrides the container's working directory.
+    pub working_dir: Option<String>,
+    pub tty: bool,
+    pub attach_stdin: bool,
+    pub attach_stdout: bool,
+    pub attach_stderr: bool,
+    pub privileged: bool,
+    pub detach_keys: Option<String>,
+}
+
+/// The daemon's side of an exec's standard streams.
+pub enum ExecIo {
+    /// Separate pipes for the attached streams.
+    Pipes {
+        stdin: Option<ChildStdin>,
+        stdout: Option<ChildStdout>,
+        stderr: Option<ChildStderr>,
+    },
+    /// One terminal carrying input and (merged) output.
+    #[cfg(unix)]
+    Pty(crate::pty::PtyMaster),
+}
+
+/// A freshly spawned exec process, as returned by an [`ExecRuntime`].
+pub struct ExecProcess {
+    /// Waiting on it yields the exec's exit status.
+    pub child: Child,
+    /// Host PID of the process itself.
+    pub pid: u32,
+    pub io: ExecIo,
+}
+
+/// Runtimes that can start processes inside their running containers.
+#[async_trait]
+pub trait ExecRuntime: Send + Sync {
+    /// Starts `config.cmd` with the container's environment, working
+    /// directory and user, overridden by the exec's own settings.
+    async fn spawn_exec(&self, container: &Container<Running>, config: &ExecConfig) -> Result<ExecProcess, Error>;
+}
+
+/// Snapshot of an exec instance, for `GET /exec/:id/json`.
+#[derive(Debug, Clone)]
+pub struct ExecInfo {
+    pub id: String,
+    pub container_id: String,
+    pub config: ExecConfig,
+    pub running: bool,
+    /// Set once the process has exited.
+    pub exit_code: Option<i32>,
+    /// Host PID, 0 until started.
+    pub pid: u32,
+}
+
+struct ExecInstance {
+    info: Mutex<ExecInfo>,
+    started: Mutex<bool>,
+    exit: watch::Sender<Option<i32>>,
+    #[cfg(unix)]
+    pty: Mutex<Option<crate::pty::PtyMaster>>,
+}
+
+impl ExecInstance {
+    fn finish(&self, exit_code: i32) {
+        if let Ok(mut info) = self.info.lock() {
+            info.running = false;
+            info.exit_code = Some(exit_code);
+        }
+        #[cfg(unix)]
+        if let Ok(mut pty) = self.pty.lock() {
+            pty.take();
+        }
+        self.exit.send_replace(Some(exit_code));
+    }
+}
+
+pub struct ExecManager {
+    runtime: Arc<dyn ExecRuntime>,
+    store: Arc<dyn ContainerStore>,
+    execs: Mutex<HashMap<String, Arc<ExecInstance>>>,
+}
+
+impl ExecManager {
+    pub fn new(runtime: Arc<dyn ExecRuntime>, store: Arc<dyn ContainerStore>) -> Self {
+        Self {
+            runtime,
+            store,
+            execs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Registers a new exec in a running container and returns its ID.
+    pub async fn create(&self, container_id: &str, config: ExecConfig) -> Result<String, Error> {
+        if config.cmd.is_empty() {
+            return Err(Error::new(ExecError::InvalidConfig("No exec command specified".to_string())));
+        }
+        self.running_container(container_id).await?;
+
+        let id = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        let instance = Arc::new(ExecInstance {
+            info: Mutex::new(ExecInfo {
+                id: id.clone(),
+                container_id: container_id.to_string(),
+                config,
+                running: false,
+                exit_code: None,
+                pid: 0,
+            }),
+            started: Mutex::new(false),
+            exit: watch::channel(None).0,
+            #[cfg(unix)]
+            pty: Mutex::new(None),
+        });
+        if let Ok(mut execs) = self.execs.lock() {
+            execs.insert(id.clone(), instance);
+        }
+        Ok(id)
+    }
+
+    pub fn get(&self, id: &str) -> Option<ExecInfo> {
+        let instance = self.instance(id).ok()?;
+        let info = instance.info.lock().ok()?;
+        Some(info.clone())
+    }
+
+    /// IDs of the container's execs, for `ExecIDs` in inspect.
+    pub fn ids_for_container(&self, container_id: &str) -> Vec<String> {
+        let Ok(execs) = self.execs.lock() else {
+            return Vec::new();
+        };
+        let mut ids: Vec<String> = execs
+            .iter()
+            .filter(|(_, exec)| exec.info.lock().is_ok_and(|info| info.container_id == container_id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Spawns the exec's process and hands back its streams. The exit code
+    /// is recorded in the background.
+    pub async fn start(&self, id: &str) -> Result<ExecIo, Error> {
+        let instance = self.instance(id)?;
+        {
+            let mut started = instance
+                .started
+                .lock()
+                .map_err(|_| Error::new(ExecError::AlreadyStarted(id.to_string())))?;
+            if *started {
+                return Err(Error::new(ExecError::AlreadyStarted(id.to_string())));
+            }
+            *started = true;
+        }
+
+        let (container_id, config) = {
+            let info = instance.info.lock().map_err(|_| Error::new(ExecError::NotFound(id.to_string())))?;
+            (info.container_id.clone(), info.config.clone())
+        };
+        let spawned = match self.running_container(&container_id).await {
+            Ok(container) => self.runtime.spawn_exec(&container, &config).await,
+            Err(e) => Err(e),
+        };
+        let ExecProcess { mut child, pid, io } = match spawned {
+            Ok(process) => process,
+            Err(e) => {
+                // Never ran; 126 as in Docker, so waiters are released
+                instance.finish(126);
+                return Err(e);
+            }
+        };
+        info!(exec = %id, container = %container_id, pid, cmd = ?config.cmd, "Exec started");
+
+        if let Ok(mut info) = instance.info.lock() {
+            info.running = true;
+            info.pid = pid;
+        }
+        #[cfg(unix)]
+        if let ExecIo::Pty(master) = &io {
+            if let Ok(mut pty) = instance.pty.lock() {
+                *pty = Some(master.clone());
+            }
+        }
+
+        let exec_id = id.to_string();
+        tokio::spawn(async move {
+            let code = match child.wait().await {
+                Ok(status) => exit_code(status),
+                Err(e) => {
+                    warn!("Failed to wait on exec {} (PID {}): {}", exec_id, pid, e);
+                    -1
+                }
+            };
+            info!(exec = %exec_id, pid, exit_code = code, "Exec process exited");
+            instance.finish(code);
+        });
+
+        Ok(io)
+    }
+
+    /// Waits for a started exec to exit and returns its exit code.
+    pub async fn wait(&self, id: &str) -> Option<i32> {
+        let mut rx = self.instance(id).ok()?.exit.subscribe();
+        let code = rx.wait_for(Option::is_some).await.ok()?;
+        *code
+    }
+
+    /// Resizes the exec's terminal. Execs without a TTY ignore this.
+    pub fn resize(&self, id: &str, height: u16, width: u16) -> Result<(), Error> {
+        let instance = self.instance(id)?;
+        #[cfg(unix)]
+        if let Some(pty) = instance.pty.lock().ok().and_then(|pty| pty.clone()) {
+            pty.resize(height, width).map_err(|e| Error::new(ExecError::ResizeFailed(e)))?;
+        }
+        #[cfg(not(unix))]
+        let _ = (instance, height, width);
+        Ok(())
+    }
+
+    /// Drops the execs of a removed container.
+    pub fn forget_container(&self, container_id: &str) {
+        if let Ok(mut execs) = self.execs.lock() {
+            execs.retain(|_, exec| exec.info.lock().is_ok_and(|info| info.container_id != container_id));
+        }
+    }
+
+    fn instance(&self, id: &str) -> Result<Arc<ExecInstance>, Error> {
+        self.execs
+            .lock()
+            .ok()
+            .and_then(|execs| execs.get(id).cloned())
+            .ok_or_else(|| Error::new(ExecError::NotFound(id.to_string())))
+    }
+
+    async fn running_container(&self, container_id: &str) -> Result<Container<Running>, Error> {
+        match self.store.get_any(container_id).await? {
+            Some(AnyContainer::Running(container)) => Ok(container),
+            Some(AnyContainer::Paused(_)) => Err(Error::new(ExecError::ContainerPaused(container_id.to_string()))),
+            _ => Err(Error::new(ExecError::ContainerNotRunning(container_id.to_string()))),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExecError {
+    #[error("No such exec instance: {0}")]
+    NotFound(String),
+    #[error("Exec {0} has already been started")]
+    AlreadyStarted(String),
+    #[error("Container {0} is not running")]
+    ContainerNotRunning(String),
+    #[error("Container {0} is paused, unpause the container before exec")]
+    ContainerPaused(String),
+    #[error("Invalid exec config: {0}")]
+    InvalidConfig(String),
+    #[error("Failed to resize exec TTY: {0}")]
+    ResizeFailed(std::io::Error),
+}
+
+impl Diagnosable for ExecError {
+    fn code(&self) -> String {
+        match self {
+            Self::NotFound(_) => "EXEC_NOT_FOUND".to_string(),
+            Self::AlreadyStarted(_) => "EXEC_ALREADY_STARTED".to_string(),
+            Self::ContainerNotRunning(_) => "EXEC_CONTAINER_NOT_RUNNING".to_string(),
+            Self::ContainerPaused(_) => "EXEC_CONTAINER_PAUSED".to_string(),
+            Self::InvalidConfig(_) => "EXEC_INVALID_CONFIG".to_string(),
+            Self::ResizeFailed(_) => "EXEC_RESIZE_FAILED".to_string(),
+        }
+    }
+    fn suggestion(&self) -> Option<String> {
+        match self {
+            Self::NotFound(_) => Some("Create the exec with POST /containers/{id}/exec first".to_string()),
+            Self::AlreadyStarted(_) => Some("Create a new exec instance for every run".to_string()),
+            Self::ContainerNotRunning(_) => Some("Start the container before running commands in it".to_string()),
+            Self::ContainerPaused(_) => Some("Unpause the container with POST /containers/{id}/unpause".to_string()),
+            Self::InvalidConfig(_) => Some("Provide a non-empty Cmd".to_string()),
+            Self::ResizeFailed(_) => Some("The exec process may have exited".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttachHub, LogStore, ProcessRuntime, Supervisor};
+    use furukawa_domain::container::Config;
+
+    #[tokio::test]
+    async fn test_start_in_stopped_container_finishes_exec() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", tmp.path().join("rustker.db").display());
+        let store = Arc::new(furukawa_infra_db::SqliteStore::new(&url).await.unwrap());
+        let logs = Arc::new(LogStore::new(tmp.path().join("logs"), Arc::new(AttachHub::default())));
+        let supervisor = Arc::new(Supervisor::new(store.clone(), logs.clone()));
+        let execs = ExecManager::new(Arc::new(ProcessRuntime { supervisor, logs }), store.clone());
+
+        let running = Container::<Running>::restore(
+            "c-0001".to_string(),
+            "box".to_string(),
+            Config::default(),
+            Running { pid: 4242, started_at: time::OffsetDateTime::now_utc(), process_start: None },
+        );
+        store.save(&Container::new("c-0001".to_string(), "box".to_string(), Config::default())).await.unwrap();
+        store.save_running(&running).await.unwrap();
+        let config = ExecConfig { cmd: vec!["true".to_string()], ..ExecConfig::default() };
+        let id = execs.create("c-0001", config).await.unwrap();
+        store.save_stopped(&running.exited(0)).await.unwrap();
+
+        let err = execs.start(&id).await.err().expect("start should fail");
+        assert_eq!(err.code(), "EXEC_CONTAINER_NOT_RUNNING");
+        let info = execs.get(&id).unwrap();
+        assert!(!info.running);
+        assert_eq!(info.exit_code, Some(126));
+        assert_eq!(execs.wait(&id).await, Some(126));
+    }
+}
@@ -0,0 +1,343 @@
+//! Health checks.
+//!
+//! The [`HealthMonitor`] follows the [`Supervisor`]'s start events and, for
+//! every container with a `Healthcheck`, runs the check command inside the
+//! container (as an exec) once per interval while it runs. The status moves
+//! from `starting` to `healthy` on the first passing probe, and to
+//! `unhealthy` after `retries` failures in a row; failures during the start
+//! period do not count while the container is still `starting`. Status
+//! changes are published as [`HealthEvent`]s.
+//!
+//! Health is kept in memory only: after a daemon restart, adopted
+//! containers start over as `starting`.
+
+use crate::exec::{ExecConfig, ExecIo, ExecProcess, ExecRuntime};
+use crate::process_control::{self, SIGKILL};
+use crate::supervisor::{exit_code, StartEvent, Supervisor};
+use furukawa_common::diagnostic::Error;
+use furukawa_domain::container::store::ContainerStore;
+use furukawa_domain::container::{AnyContainer, Container, Healthcheck, Running};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Probe results kept per container (as in Docker).
+pub const MAX_LOG_ENTRIES: usize = 5;
+
+/// Output kept per probe, in bytes (as in Docker).
+const MAX_OUTPUT: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthStatus {
+    Starting,
+    Healthy,
+    Unhealthy,
+}
+
+impl HealthStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Starting => "starting",
+            Self::Healthy => "healthy",
+            Self::Unhealthy => "unhealthy",
+        }
+    }
+}
+
+/// One run of the check command.
+#[derive(Debug, Clone)]
+pub struct HealthResult {
+    pub start: time::OffsetDateTime,
+    pub end: time::OffsetDateTime,
+    /// 0 healthy, anything else a failure; -1 if the probe could not run or
+    /// timed out.
+    pub exit_code: i32,
+    pub output: String,
+}
+
+/// Health of a running container, for `State.Health` in inspect.
+#[derive(Debug, Clone)]
+pub struct Health {
+    pub status: HealthStatus,
+    pub failing_streak: u32,
+    /// The latest results, oldest first.
+    pub log: VecDeque<HealthResult>,
+}
+
+/// Published every time a container's health status changes.
+#[derive(Debug, Clone)]
+pub struct HealthEvent {
+    pub container_id: String,
+    pub status: HealthStatus,
+}
+
+impl HealthEvent {
+    /// Docker's event action, e.g. `health_status: healthy`.
+    pub fn action(&self) -> String {
+        format!("health_status: {}", self.status.as_str())
+    }
+}
+
+pub struct HealthMonitor {
+    store: Arc<dyn ContainerStore>,
+    runtime: Arc<dyn ExecRuntime>,
+    supervisor: Arc<Supervisor>,
+    /// Health per container id, with the PID of the run it belongs to.
+    health: Mutex<HashMap<String, (u32, Health)>>,
+    events: broadcast::Sender<HealthEvent>,
+}
+
+impl HealthMonitor {
+    pub fn new(store: Arc<dyn ContainerStore>, runtime: Arc<dyn ExecRuntime>, supervisor: Arc<Supervisor>) -> Self {
+        let (events, _) = broadcast::channel(64);
+        Self {
+            store,
+            runtime,
+            supervisor,
+            health: Mutex::new(HashMap::new()),
+            events,
+        }
+    }
+
+    /// Receives a [`HealthEvent`] for every health status change.
+    pub fn subscribe(&self) -> broadcast::Receiver<HealthEvent> {
+        self.events.subscribe()
+    }
+
+    /// Current health of a running container with a health check.
+    pub fn get(&self, container_id: &str) -> Option<Health> {
+        let health = self.health.lock().ok()?;
+        health.get(container_id).map(|(_, health)| health.clone())
+    }
+
+    /// Handles start events (from [`Supervisor::subscribe_starts`]) until
+    /// the supervisor goes away.
+    pub async fn run(self: Arc<Self>, mut starts: broadcast::Receiver<StartEvent>) {
+        loop {
+            match starts.recv().await {
+                Ok(event) => self.watch(event).await,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Health monitor missed {} start events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Starts checking the containers that were already running before
+    /// [`run`](Self::run) subscribed, i.e. those adopted at startup.
+    pub async fn watch_running(self: &Arc<Self>) -> Result<(), Error> {
+        for container in self.store.list().await? {
+            let Some(pid) = container.pid() else {
+                continue;
+            };
+            let exits = self.supervisor.notices(container.id()).borrow().exits;
+            self.watch(StartEvent {
+                container_id: container.id().to_string(),
+                pid,
+                exits,
+            })
+            .await;
+        }
+        Ok(())
+    }
+
+    async fn watch(self: &Arc<Self>, event: StartEvent) {
+        let check = match self.store.get_any(&event.container_id).await {
+            Ok(Some(c)) => c.config().healthcheck.clone(),
+            Ok(None) => return,
+            Err(e) => {
+                warn!("Failed to load container {} for health checks: {}", event.container_id, e);
+                return;
+            }
+        };
+        let Some((check, command)) = check.and_then(|check| check.command().map(|command| (check, command))) else {
+            return;
+        };
+
+        let health = Health {
+            status: HealthStatus::Starting,
+            failing_streak: 0,
+            log: VecDeque::new(),
+        };
+        if let Ok(mut all) = self.health.lock() {
+            all.insert(event.container_id.clone(), (event.pid, health));
+        }
+
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            monitor.monitor(&event, &check, command).await;
+            if let Ok(mut all) = monitor.health.lock() {
+                if all.get(&event.container_id).is_some_and(|(pid, _)| *pid == event.pid) {
+                    all.remove(&event.container_id);
+                }
+            }
+        });
+    }
+
+    /// Probes once per interval until the run ends. Paused containers, and
+    /// those whose start is not saved yet, are skipped.
+    async fn monitor(&self, run: &StartEvent, check: &Healthcheck, command: Vec<String>) {
+        let started = Instant::now();
+        let mut notices = self.supervisor.notices(&run.container_id);
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep(check.interval()) => {}
+                _ = notices.wait_for(|notice| notice.removed || notice.exits > run.exits) => return,
+            }
+
+            let container = match self.store.get_any(&run.container_id).await {
+                Ok(Some(AnyContainer::Running(c))) if c.state().pid == run.pid => c,
+                Ok(Some(_)) => continue,
+                Ok(None) => return,
+                Err(e) => {
+                    warn!("Failed to load container {} for a health check: {}", run.container_id, e);
+                    continue;
+                }
+            };
+            let result = self.probe(&container, check, command.clone()).await;
+            self.record(run, result, check.retries(), started.elapsed() < check.start_period());
+        }
+    }
+
+    async fn probe(&self, container: &Container<Running>, check: &Healthcheck, cmd: Vec<String>) -> HealthResult {
+        let start = time::OffsetDateTime::now_utc();
+        let failed = |output: String| HealthResult {
+            start,
+            end: time::OffsetDateTime::now_utc(),
+            exit_code: -1,
+            output,
+        };
+
+        let config = ExecConfig {
+            cmd,
+            attach_stdout: true,
+            attach_stderr: true,
+            ..Default::default()
+        };
+        let ExecProcess { mut child, pid, io } = match self.runtime.spawn_exec(container, &config).await {
+            Ok(process) => process,
+            Err(e) => return failed(e.to_string()),
+        };
+        let (stdout, stderr) = match io {
+            ExecIo::Pipes { stdout, stderr, .. } => (stdout, stderr),
+            #[cfg(unix)]
+            ExecIo::Pty(_) => (None, None),
+        };
+
+        let run = async {
+            let (mut output, stderr) = tokio::join!(read_capped(stdout), read_capped(stderr));
+            output.extend(stderr);
+            (output, child.wait().await)
+        };
+        match tokio::time::timeout(check.timeout(), run).await {
+            Ok((output, status)) => HealthResult {
+                start,
+                end: time::OffsetDateTime::now_utc(),
+                exit_code: status.map(exit_code).unwrap_or(-1),
+                output: if output.len() > MAX_OUTPUT {
+                    format!("{}...", String::from_utf8_lossy(&output[..MAX_OUTPUT]))
+                } else {
+                    String::from_utf8_lossy(&output).into_owned()
+                },
+            },
+            Err(_) => {
+                if let Err(e) = process_control::send_signal(pid, SIGKILL) {
+                    warn!("Failed to kill timed out health check (PID {}): {}", pid, e);
+                }
+                let _ = child.kill().await;
+                failed(format!("Health check exceeded timeout ({:?})", check.timeout()))
+            }
+        }
+    }
+
+    fn record(&self, run: &StartEvent, result: HealthResult, retries: u32, in_start_period: bool) {
+        let Ok(mut all) = self.health.lock() else {
+            return;
+        };
+        let Some((_, health)) = all.get_mut(&run.container_id).filter(|(pid, _)| *pid == run.pid) else {
+            return;
+        };
+
+        let previous = health.status;
+        if result.exit_code == 0 {
+            health.status = HealthStatus::Healthy;
+            health.failing_streak = 0;
+        } else if !(in_start_period && previous == HealthStatus::Starting) {
+            health.failing_streak += 1;
+            if health.failing_streak >= retries {
+                health.status = HealthStatus::Unhealthy;
+            }
+        }
+        health.log.push_back(result);
+        while health.log.len() > MAX_LOG_ENTRIES {
+            health.log.pop_front();
+        }
+
+        if health.status != previous {
+            info!(id = %run.container_id, status = health.status.as_str(), "Container health changed");
+            // Nobody listening is fine.
+            let _ = self.events.send(HealthEvent {
+                container_id: run.container_id.clone(),
+                status: health.status,
+            });
+        }
+    }
+}
+
+/// Reads to the end, keeping one byte more than [`MAX_OUTPUT`] so that
+/// truncation shows.
+async fn read_capped<R: AsyncRead + Unpin>(reader: Option<R>) -> Vec<u8> {
+    let mut output = Vec::new();
+    let Some(mut reader) = reader else {
+        return output;
+    };
+    let mut buf = [0u8; 1024];
+    loop {
+        match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => return output,
+            Ok(n) => {
+                let room = (MAX_OUTPUT + 1).saturating_sub(output.len());
+                output.extend_from_slice(&buf[..n.min(room)]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AttachHub, LogStore, ProcessRuntime};
+
+    fn result(exit_code: i32) -> HealthResult {
+        let now = time::OffsetDateTime::now_utc();
+        HealthResult { start: now, end: now, exit_code, output: String::new() }
+    }
+
+    #[tokio::test]
+    async fn test_status_change_is_published() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let url = format!("sqlite://{}?mode=rwc", tmp.path().join("rustker.db").display());
+        let store = Arc::new(furukawa_infra_db::SqliteStore::new(&url).await.unwrap());
+        let logs = Arc::new(LogStore::new(tmp.path().join("logs"), Arc::new(AttachHub::default())));
+        let supervisor = Arc::new(Supervisor::new(store.clone(), logs.clone()));
+        let runtime = Arc::new(ProcessRuntime { supervisor: supervisor.clone(), logs });
+        let monitor = HealthMonitor::new(store, runtime, supervisor);
+
+        let run = StartEvent { container_id: "c-0001".to_string(), pid: 4242, exits: 0 };
+        let starting = Health { status: HealthStatus::Starting, failing_streak: 0, log: VecDeque::new() };
+        monitor.health.lock().unwrap().insert(run.container_id.clone(), (run.pid, starting));
+        let mut events = monitor.subscribe();
+
+        monitor.record(&run, result(0), 3, false);
+        monitor.record(&run, result(0), 3, false);
+        monitor.record(&run, result(1), 1, false);
+
+        let actions: Vec<String> = std::iter::from_fn(|| events.try_recv().ok()).map(|e| e.action()).collect();
+        assert_eq!(actions, ["health_status: healthy", "health_status: unhealthy"]);
+        assert_eq!(monitor.get("c-0001").unwrap().status, HealthStatus::Unhealthy);
+    }
+}
@@ -1,76 +1,266 @@
+pub mod archive;
+pub mod attach;
+pub mod cgroup;
+pub mod exec;
+pub mod health;
+pub mod logs;
 mod process_control;
+#[cfg(unix)]
+pub mod pty;
+pub mod restart;
+pub mod rootfs;
+pub mod stats;
+pub mod supervisor;
+pub mod top;
+pub mod user;
+#[cfg(target_os = "linux")]
+mod linux;
+
+pub use attach::AttachHub;
+pub use exec::{ExecConfig, ExecManager, ExecRuntime};
+pub use health::HealthMonitor;
+#[cfg(target_os = "linux")]
+pub use linux::LinuxRuntime;
+pub use restart::RestartManager;
+pub use logs::LogStore;
+pub use supervisor::{ExitEvent, ExitNotice, StartEvent, Supervisor};
 
 use async_trait::async_trait;
-use furukawa_domain::container::{Container, Created, Running};
-use furukawa_domain::container::runtime::ContainerRuntime;
+use furukawa_domain::container::{Config, Container, Created, Paused, Running};
+use furukawa_domain::container::runtime::{ContainerRuntime, StopOptions};
 use furukawa_common::diagnostic::Error;
 use std::process::Stdio;
+use std::sync::Arc;
 use tokio::process::Command;
 use tracing::info;
 
-#[derive(Default, Clone)]
-pub struct ProcessRuntime;
+#[derive(Clone)]
+pub struct ProcessRuntime {
+    pub supervisor: Arc<Supervisor>,
+    pub logs: Arc<logs::LogStore>,
+}
 
 #[async_trait]
 impl ContainerRuntime for ProcessRuntime {
     async fn start(&self, container: &Container<Created>) -> Result<Running, Error> {
         let config = container.config();
         
-        info!("Starting container {} with command: {:?}", container.id(), config.cmd);
-        
-        let program = if config.cmd.is_empty() {
-             "sh"
-        } else {
-             &config.cmd[0]
-        };
+        let command = config.command();
+        info!("Starting container {} with command: {:?}", container.id(), command);
+        if config.tty {
+            tracing::warn!("Process runtime has no TTY support; container {} uses pipes", container.id());
+        }
         
-        let args = if config.cmd.len() > 1 {
-            &config.cmd[1..]
-        } else {
-            &[]
+        // Containers created before image defaults were applied may have
+        // no command at all.
+        let (program, args) = match command.split_first() {
+            Some((program, args)) => (program.as_str(), args),
+            None => ("sh", &[][..]),
         };
 
-        // Create logs directory
-        let log_dir = std::path::Path::new("furukawa_logs");
-        if !log_dir.exists() {
-            std::fs::create_dir_all(log_dir).map_err(|e| Error::new(RuntimeError::LogSetupFailed(e)))?;
-        }
-
-        let log_path = log_dir.join(format!("{}.log", container.id()));
-        let log_file = std::fs::File::create(&log_path)
-            .map_err(|e| Error::new(RuntimeError::LogSetupFailed(e)))?;
-
-        let stdout_file = log_file.try_clone().map_err(|e| Error::new(RuntimeError::LogSetupFailed(e)))?;
-        let stderr_file = log_file;
-
-        let child = Command::new(program)
+        let mut child = Command::new(program)
             .args(args)
-            .stdout(Stdio::from(stdout_file))
-            .stderr(Stdio::from(stderr_file))
+            .stdin(stdin_stdio(config))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| Error::new(RuntimeError::SpawnFailed(e)))?;
             
         let pid = child.id().ok_or_else(|| Error::new(RuntimeError::NoPid))?;
+        let log_copy = copy_child_output(&self.logs, container, &mut child).await?;
+        self.supervisor.watch(container.id(), pid, child, log_copy);
         
         info!("Container started with PID: {}", pid);
 
         Ok(Running {
             pid,
             started_at: time::OffsetDateTime::now_utc(),
+            process_start: process_control::process_start(pid),
         })
     }
 
-    async fn stop(&self, container: &Container<Running>) -> Result<(), Error> {
-        let pid = container.state().pid;
-        process_control::stop_container(pid)
+    async fn stop(&self, container: &Container<Running>, options: &StopOptions) -> Result<i32, Error> {
+        stop_gracefully(&self.supervisor, container, options).await
+    }
+
+    async fn kill(&self, container: &Container<Running>, signal: &str) -> Result<(), Error> {
+        signal_container(&self.supervisor, container, signal)
+    }
+
+    async fn stop_paused(&self, _container: &Container<Paused>, _options: &StopOptions) -> Result<i32, Error> {
+        Err(Error::new(RuntimeError::PauseUnsupported("the process runtime has no cgroup freezer".to_string())))
+    }
+
+    async fn kill_paused(&self, _container: &Container<Paused>, _signal: &str) -> Result<(), Error> {
+        Err(Error::new(RuntimeError::PauseUnsupported("the process runtime has no cgroup freezer".to_string())))
+    }
+
+    async fn pause(&self, _container: &Container<Running>) -> Result<(), Error> {
+        Err(Error::new(RuntimeError::PauseUnsupported("the process runtime has no cgroup freezer".to_string())))
+    }
+
+    async fn unpause(&self, _container: &Container<Paused>) -> Result<(), Error> {
+        Err(Error::new(RuntimeError::PauseUnsupported("the process runtime has no cgroup freezer".to_string())))
     }
 }
 
+#[async_trait]
+impl ExecRuntime for ProcessRuntime {
+    /// Plain host processes: the command runs on the host with the exec's
+    /// environment added to the daemon's.
+    async fn spawn_exec(&self, container: &Container<Running>, config: &ExecConfig) -> Result<exec::ExecProcess, Error> {
+        if config.tty {
+            tracing::warn!("Process runtime has no TTY support; exec in {} uses pipes", container.id());
+        }
+        if config.user.is_some() {
+            tracing::warn!("Process runtime ignores the exec user for {}", container.id());
+        }
+
+        let mut cmd = Command::new(&config.cmd[0]);
+        cmd.args(&config.cmd[1..]);
+        for env_var in container.config().env.iter().chain(&config.env) {
+            if let Some((key, value)) = env_var.split_once('=') {
+                cmd.env(key, value);
+            }
+        }
+        if let Some(dir) = config.working_dir.as_ref().or(container.config().working_dir.as_ref()) {
+            cmd.current_dir(dir);
+        }
+        spawn_exec_piped(cmd, config)
+    }
+}
+
+/// Spawns an exec whose attached streams are pipes (the others go to
+/// `/dev/null`).
+fn spawn_exec_piped(mut cmd: Command, config: &ExecConfig) -> Result<exec::ExecProcess, Error> {
+    let stdio = |attached: bool| if attached { Stdio::piped() } else { Stdio::null() };
+    let mut child = cmd
+        .stdin(stdio(config.attach_stdin))
+        .stdout(stdio(config.attach_stdout))
+        .stderr(stdio(config.attach_stderr))
+        .spawn()
+        .map_err(|e| Error::new(RuntimeError::SpawnFailed(e)))?;
+    let pid = child.id().ok_or_else(|| Error::new(RuntimeError::NoPid))?;
+    let io = exec::ExecIo::Pipes {
+        stdin: child.stdin.take(),
+        stdout: child.stdout.take(),
+        stderr: child.stderr.take(),
+    };
+    Ok(exec::ExecProcess { child, pid, io })
+}
+
+/// Stops the container's process, honouring the request's overrides and then
+/// the container's own `stop_signal` / `stop_timeout`.
+async fn stop_gracefully(
+    supervisor: &Supervisor,
+    container: &Container<Running>,
+    options: &StopOptions,
+) -> Result<i32, Error> {
+    stop_process(supervisor, container.id(), container.config(), container.state(), options, async {}).await
+}
+
+/// [`stop_gracefully`] for any container process; `resume` runs once the
+/// stop signal has been sent.
+async fn stop_process(
+    supervisor: &Supervisor,
+    container_id: &str,
+    config: &Config,
+    process: &Running,
+    options: &StopOptions,
+    resume: impl std::future::Future<Output = ()>,
+) -> Result<i32, Error> {
+    let signal = options.signal.as_deref().or(config.stop_signal.as_deref());
+    let timeout = options.timeout.or(config.stop_timeout);
+
+    supervisor.expect_exit(container_id);
+
+    process_control::stop_container(supervisor, container_id, process, signal, timeout, resume).await
+}
+
+fn signal_container(supervisor: &Supervisor, container: &Container<Running>, signal: &str) -> Result<(), Error> {
+    signal_process(supervisor, container.id(), container.config(), container.state(), signal).map(|_| ())
+}
+
+/// Sends `signal` to a container process; returns whether it is meant to
+/// end the container.
+fn signal_process(supervisor: &Supervisor, container_id: &str, config: &Config, process: &Running, signal: &str) -> Result<bool, Error> {
+    let signal = process_control::parse_signal(signal)?;
+
+    // Like Docker, killing with SIGKILL or the stop signal counts as a
+    // manual stop; other signals (e.g. SIGHUP to reload) do not.
+    let stop_signal = process_control::parse_signal(config.stop_signal.as_deref().unwrap_or("SIGTERM"))?;
+    let stops = signal == process_control::SIGKILL || signal == stop_signal;
+    if stops {
+        supervisor.expect_exit(container_id);
+    }
+
+    if !process_control::is_same_process(process.pid, process.process_start.as_deref()) {
+        tracing::warn!("Process {} of container {} is already gone", process.pid, container_id);
+        return Ok(stops);
+    }
+    info!("Sending signal {} to container {}", signal, container_id);
+    process_control::send_signal(process.pid, signal)?;
+    Ok(stops)
+}
+
+/// The process of a paused container, which is the one it ran before.
+fn frozen_process(state: &Paused) -> Running {
+    Running {
+        pid: state.pid,
+        started_at: state.started_at,
+        process_start: state.process_start.clone(),
+    }
+}
+
+/// Stdin of a container process: a pipe if the container keeps stdin open.
+fn stdin_stdio(config: &furukawa_domain::container::Config) -> Stdio {
+    if config.open_stdin {
+        Stdio::piped()
+    } else {
+        Stdio::null()
+    }
+}
+
+/// Starts copying a piped child's stdout and stderr into the container log,
+/// and hands its stdin (if piped) to the attach hub.
+async fn copy_child_output(
+    logs: &logs::LogStore,
+    container: &Container<Created>,
+    child: &mut tokio::process::Child,
+) -> Result<logs::LogCopy, Error> {
+    if let Some(stdin) = child.stdin.take() {
+        logs.hub().set_stdin(container.id(), Box::new(stdin)).await;
+    }
+    match (child.stdout.take(), child.stderr.take()) {
+        (Some(stdout), Some(stderr)) => logs.copy(container.id(), &container.config().log_config, stdout, stderr),
+        _ => Ok(logs::LogCopy::default()),
+    }
+}
+
+/// Resolves the container's image and composes its rootfs under
+/// `<containers_root>/<id>/rootfs` (only on first start, see [`rootfs`]).
+async fn prepare_rootfs(
+    image_store: &furukawa_infra_fs::store::image::ImageStore,
+    metadata_store: &dyn furukawa_domain::image::store::ImageMetadataStore,
+    containers_root: &std::path::Path,
+    container: &Container<Created>,
+) -> Result<std::path::PathBuf, Error> {
+    let config = container.config();
+
+    let metadata = metadata_store.get(&config.image).await
+        .map_err(|e| Error::new(RuntimeError::ImageResolutionFailed(e.to_string())))?
+        .ok_or_else(|| Error::new(RuntimeError::ImageResolutionFailed("Image not found".into())))?;
+
+    rootfs::compose(image_store, &metadata.layers, &containers_root.join(container.id())).await
+}
+
 pub struct WslRuntime {
     pub image_store: std::sync::Arc<furukawa_infra_fs::store::image::ImageStore>,
     pub metadata_store: std::sync::Arc<dyn furukawa_domain::image::store::ImageMetadataStore>,
     pub containers_root: std::path::PathBuf,
     pub distro: String,
+    pub supervisor: Arc<Supervisor>,
+    pub logs: Arc<logs::LogStore>,
 }
 
 /// Convert a Windows absolute path to a WSL path using `wslpath -u`.
@@ -105,22 +295,18 @@ impl ContainerRuntime for WslRuntime {
         let config = container.config();
         
         info!("Starting WSL container {} with image: {}", container.id(), config.image);
-
-        // 1. Resolve Image Metadata
-        let metadata = self.metadata_store.get(&config.image).await
-            .map_err(|e| Error::new(RuntimeError::ImageResolutionFailed(e.to_string())))?
-            .ok_or_else(|| Error::new(RuntimeError::ImageResolutionFailed("Image not found".into())))?;
-
-        // 2. Prepare RootFS
-        let container_dir = self.containers_root.join(container.id());
-        let rootfs_dir = container_dir.join("rootfs");
-        
-        if !rootfs_dir.exists() {
-            info!("Composing rootfs for container {} at {:?}", container.id(), rootfs_dir);
-            self.image_store.compose_rootfs(&metadata.layers, rootfs_dir.clone()).await
-                .map_err(|e| Error::new(RuntimeError::RootfsCompositionFailed(e.to_string())))?;
+        if config.tty {
+            tracing::warn!("WSL runtime has no TTY support; container {} uses pipes", container.id());
         }
 
+        // 1-2. Resolve image metadata and prepare RootFS
+        let rootfs_dir = prepare_rootfs(
+            &self.image_store,
+            self.metadata_store.as_ref(),
+            &self.containers_root,
+            container,
+        ).await?;
+
         // 3. Convert Windows rootfs path to WSL path
         let wsl_rootfs = windows_to_wsl_path(
             &self.distro,
@@ -164,8 +350,11 @@ impl ContainerRuntime for WslRuntime {
         }
 
         // 5. Build the WSL command
-        let program = if config.cmd.is_empty() { "sh" } else { &config.cmd[0] };
-        let args = if config.cmd.len() > 1 { &config.cmd[1..] } else { &[] };
+        let command = config.command();
+        let (program, args) = match command.split_first() {
+            Some((program, args)) => (program.as_str(), args),
+            None => ("sh", &[][..]),
+        };
 
         // Start with env vars via `env KEY=VALUE ... chroot <rootfs> <program>`
         let mut wsl_cmd = Command::new("wsl.exe");
@@ -179,29 +368,32 @@ impl ContainerRuntime for WslRuntime {
             wsl_cmd.arg(env_var);
         }
 
-        wsl_cmd.arg("chroot").arg(&wsl_rootfs).arg(program);
+        // User and working directory as for execs
+        wsl_cmd.arg("chroot");
+        if let Some(user) = &config.user {
+            wsl_cmd.arg(format!("--userspec={}", user));
+        }
+        wsl_cmd.arg(&wsl_rootfs);
+        if let Some(dir) = &config.working_dir {
+            wsl_cmd.args(["sh", "-c", "cd \"$0\" && exec \"$@\""]).arg(dir);
+        }
+        wsl_cmd.arg(program);
         for arg in args {
             wsl_cmd.arg(arg);
         }
 
-        // 6. Setup container logs
-        let log_dir = std::path::Path::new("furukawa_logs");
-        if !log_dir.exists() {
-            std::fs::create_dir_all(log_dir).map_err(|e| Error::new(RuntimeError::LogSetupFailed(e)))?;
-        }
-        let log_path = log_dir.join(format!("{}.log", container.id()));
-        let log_file = std::fs::File::create(&log_path).map_err(|e| Error::new(RuntimeError::LogSetupFailed(e)))?;
-        let stdout_file = log_file.try_clone().map_err(|e| Error::new(RuntimeError::LogSetupFailed(e)))?;
-        let stderr_file = log_file;
-
+        // 6. Spawn, with output going to the container log
         info!("Spawning WSL process: env {:?} chroot {} {}", config.env, wsl_rootfs, program);
-        let child = wsl_cmd
-            .stdout(Stdio::from(stdout_file))
-            .stderr(Stdio::from(stderr_file))
+        let mut child = wsl_cmd
+            .stdin(stdin_stdio(config))
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
             .spawn()
             .map_err(|e| Error::new(RuntimeError::SpawnFailed(e)))?;
 
         let pid = child.id().ok_or_else(|| Error::new(RuntimeError::NoPid))?;
+        let log_copy = copy_child_output(&self.logs, container, &mut child).await?;
+        self.supervisor.watch(container.id(), pid, child, log_copy);
         
         // 7. Port Forwarding (Netsh)
         if !config.port_mappings.is_empty() {
@@ -235,13 +427,15 @@ impl ContainerRuntime for WslRuntime {
         Ok(Running {
             pid,
             started_at: time::OffsetDateTime::now_utc(),
+            process_start: process_control::process_start(pid),
         })
     }
 
-    async fn stop(&self, container: &Container<Running>) -> Result<(), Error> {
-        let pid = container.state().pid;
-        
-        // 1. Unmount bind-mounts
+    async fn stop(&self, container: &Container<Running>, options: &StopOptions) -> Result<i32, Error> {
+        // 1. Stop process
+        let exit_code = stop_gracefully(&self.supervisor, container, options).await?;
+
+        // 2. Unmount bind-mounts
         for vol in &container.config().volumes {
             let container_dir = self.containers_root.join(container.id());
             let rootfs_dir = container_dir.join("rootfs");
@@ -251,7 +445,7 @@ impl ContainerRuntime for WslRuntime {
                 .status();
         }
 
-        // 2. Cleanup Port Forwarding
+        // 3. Cleanup Port Forwarding
         for mapping in &container.config().port_mappings {
              info!("Cleaning up port forward: {}", mapping.host_port);
              let _ = std::process::Command::new("netsh")
@@ -262,8 +456,62 @@ impl ContainerRuntime for WslRuntime {
                 .status();
         }
 
-        // 3. Stop process
-        process_control::stop_container(pid)
+        Ok(exit_code)
+    }
+
+    async fn kill(&self, container: &Container<Running>, signal: &str) -> Result<(), Error> {
+        signal_container(&self.supervisor, container, signal)
+    }
+
+    async fn stop_paused(&self, _container: &Container<Paused>, _options: &StopOptions) -> Result<i32, Error> {
+        Err(Error::new(RuntimeError::PauseUnsupported("the WSL runtime has no cgroup freezer".to_string())))
+    }
+
+    async fn kill_paused(&self, _container: &Container<Paused>, _signal: &str) -> Result<(), Error> {
+        Err(Error::new(RuntimeError::PauseUnsupported("the WSL runtime has no cgroup freezer".to_string())))
+    }
+
+    async fn pause(&self, _container: &Container<Running>) -> Result<(), Error> {
+        Err(Error::new(RuntimeError::PauseUnsupported("the WSL runtime has no cgroup freezer".to_string())))
+    }
+
+    async fn unpause(&self, _container: &Container<Paused>) -> Result<(), Error> {
+        Err(Error::new(RuntimeError::PauseUnsupported("the WSL runtime has no cgroup freezer".to_string())))
+    }
+}
+
+#[async_trait]
+impl ExecRuntime for WslRuntime {
+    /// Runs the command chrooted into the container's rootfs, like `start`.
+    async fn spawn_exec(&self, container: &Container<Running>, config: &ExecConfig) -> Result<exec::ExecProcess, Error> {
+        if config.tty {
+            tracing::warn!("WSL runtime has no TTY support; exec in {} uses pipes", container.id());
+        }
+
+        let rootfs_dir = self.containers_root.join(container.id()).join("rootfs");
+        let wsl_rootfs = windows_to_wsl_path(&self.distro, rootfs_dir.to_str().unwrap_or("")).await;
+
+        let mut wsl_cmd = Command::new("wsl.exe");
+        wsl_cmd.arg("-d").arg(&self.distro)
+               .arg("-u").arg("root")
+               .arg("--")
+               .arg("env");
+        for env_var in container.config().env.iter().chain(&config.env) {
+            wsl_cmd.arg(env_var);
+        }
+
+        wsl_cmd.arg("chroot");
+        if let Some(user) = config.user.as_ref().or(container.config().user.as_ref()) {
+            wsl_cmd.arg(format!("--userspec={}", user));
+        }
+        wsl_cmd.arg(&wsl_rootfs);
+        // chroot always starts in `/`; change directory from inside.
+        if let Some(dir) = config.working_dir.as_ref().or(container.config().working_dir.as_ref()) {
+            wsl_cmd.args(["sh", "-c", "cd \"$0\" && exec \"$@\""]).arg(dir);
+        }
+        wsl_cmd.args(&config.cmd);
+
+        spawn_exec_piped(wsl_cmd, config)
     }
 }
 
@@ -283,6 +531,34 @@ pub enum RuntimeError {
     PortForwardingFailed(String),
     #[error("WSL setup failed: {0}")]
     WslSetupFailed(String),
+    #[error("Namespace setup failed: {0}")]
+    NamespaceSetupFailed(String),
+    #[error("Invalid signal: {0}")]
+    InvalidSignal(String),
+    #[error("Failed to send signal {signal} to PID {pid}: {source}")]
+    SignalFailed { pid: u32, signal: i32, source: std::io::Error },
+    #[error("{0}")]
+    UnknownUser(String),
+    #[error("Failed to resize TTY: {0}")]
+    ResizeFailed(std::io::Error),
+    #[error("Cgroup setup failed: {0}")]
+    CgroupSetupFailed(String),
+    #[error("Pausing is not supported: {0}")]
+    PauseUnsupported(String),
+    #[error("Failed to freeze or thaw container: {0}")]
+    FreezeFailed(std::io::Error),
+    #[error("Error running ps: {0}")]
+    PsFailed(String),
+    #[error("Could not find the file {0}")]
+    PathNotFound(String),
+    #[error("extraction point is not a directory: {0}")]
+    NotADirectory(String),
+    #[error("{0}")]
+    OverwriteConflict(String),
+    #[error("{0}")]
+    ArchiveFailed(String),
+    #[error("No snapshot of the image files in {0}; the container was first started before changes were tracked")]
+    ChangesUntracked(String),
 }
 
 impl furukawa_common::diagnostic::Diagnosable for RuntimeError {
@@ -295,6 +571,20 @@ impl furukawa_common::diagnostic::Diagnosable for RuntimeError {
             Self::RootfsCompositionFailed(_) => "RUNTIME_ROOTFS_COMPOSITION_FAILED".to_string(),
             Self::PortForwardingFailed(_) => "RUNTIME_PORT_FORWARDING_FAILED".to_string(),
             Self::WslSetupFailed(_) => "RUNTIME_WSL_SETUP_FAILED".to_string(),
+            Self::NamespaceSetupFailed(_) => "RUNTIME_NAMESPACE_SETUP_FAILED".to_string(),
+            Self::InvalidSignal(_) => "RUNTIME_INVALID_SIGNAL".to_string(),
+            Self::SignalFailed { .. } => "RUNTIME_SIGNAL_FAILED".to_string(),
+            Self::UnknownUser(_) => "RUNTIME_UNKNOWN_USER".to_string(),
+            Self::ResizeFailed(_) => "RUNTIME_RESIZE_FAILED".to_string(),
+            Self::CgroupSetupFailed(_) => "RUNTIME_CGROUP_SETUP_FAILED".to_string(),
+            Self::PauseUnsupported(_) => "RUNTIME_PAUSE_UNSUPPORTED".to_string(),
+            Self::FreezeFailed(_) => "RUNTIME_FREEZE_FAILED".to_string(),
+            Self::PsFailed(_) => "RUNTIME_PS_FAILED".to_string(),
+            Self::PathNotFound(_) => "RUNTIME_PATH_NOT_FOUND".to_string(),
+            Self::NotADirectory(_) => "RUNTIME_NOT_A_DIRECTORY".to_string(),
+            Self::OverwriteConflict(_) => "RUNTIME_OVERWRITE_CONFLICT".to_string(),
+            Self::ArchiveFailed(_) => "RUNTIME_ARCHIVE_FAILED".to_string(),
+            Self::ChangesUntracked(_) => "RUNTIME_CHANGES_UNTRACKED".to_string(),
         }
     }
     fn suggestion(&self) -> Option<String> {
@@ -306,6 +596,20 @@ impl furukawa_common::diagnostic::Diagnosable for RuntimeError {
             Self::RootfsCompositionFailed(_) => Some("Check for enough disk space and file permissions".to_string()),
             Self::PortForwardingFailed(_) => Some("Check if firewall or another process is blocking the port, and ensure Admin privileges".to_string()),
             Self::WslSetupFailed(_) => Some("Ensure WSL2 is installed (wsl --install) and enabled".to_string()),
+            Self::NamespaceSetupFailed(_) => Some("rustkerd needs root (CAP_SYS_ADMIN) to create namespaces and mounts".to_string()),
+            Self::InvalidSignal(_) => Some("Use a signal name like SIGTERM, TERM or a number like 15".to_string()),
+            Self::SignalFailed { .. } => Some("Check that the daemon is allowed to signal the container process".to_string()),
+            Self::UnknownUser(_) => Some("Use a user that exists in the image's /etc/passwd, or a numeric uid[:gid]".to_string()),
+            Self::ResizeFailed(_) => Some("The container's process may have closed its terminal".to_string()),
+            Self::CgroupSetupFailed(_) => Some("rustkerd needs root and a cgroup v2 hierarchy at /sys/fs/cgroup with the controllers delegated".to_string()),
+            Self::PauseUnsupported(_) => Some("Pausing needs the native Linux runtime on a cgroup v2 host (kernel 5.2 or later)".to_string()),
+            Self::FreezeFailed(_) => Some("Check that the container's cgroup still exists".to_string()),
+            Self::PsFailed(_) => Some("ps_args are passed to the host's ps as-is; check that they are valid and include the PID column".to_string()),
+            Self::PathNotFound(_) => Some("Paths are resolved inside the container's filesystem; check the path exists there".to_string()),
+            Self::NotADirectory(_) => Some("Archives are extracted into an existing directory".to_string()),
+            Self::OverwriteConflict(_) => Some("Remove the existing path first, or allow overwriting (noOverwriteDirNonDir=false)".to_string()),
+            Self::ArchiveFailed(_) => Some("Check the archive is a valid tar stream and that there is enough disk space".to_string()),
+            Self::ChangesUntracked(_) => Some("Recreate the container to track its changes".to_string()),
         }
     }
 }
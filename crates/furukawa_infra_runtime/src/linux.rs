@@ -0,0 +1,737 @@
+//! Native Linux runtime.
+//!
+//! Runs the workload directly on the host in fresh mount/PID/UTS/IPC
+//! namespaces, with the composed image rootfs as its root (`pivot_root`).
+//! Networking is shared with the host, so published ports are simply the
+//! ports the workload binds. Resource limits come from a cgroup of the
+//! container's own (see [`cgroup`](crate::cgroup)), which also gets a cgroup
+//! namespace so that it sees that cgroup as its root.
+
+use async_trait::async_trait;
+use furukawa_common::diagnostic::Error;
+use furukawa_domain::container::runtime::{ContainerRuntime, StopOptions};
+use furukawa_domain::container::{Container, Created, Paused, Running};
+use nix::mount::{mount, umount2, MntFlags, MsFlags};
+use nix::sched::{setns, unshare, CloneFlags};
+use nix::sys::wait::{waitpid, WaitStatus};
+use nix::unistd::{chdir, dup2, fork, pivot_root, setgid, setgroups, sethostname, setuid, ForkResult, Gid, Pid, Uid};
+use std::ffi::CString;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::process::Command;
+use tracing::info;
+
+use crate::cgroup::Cgroups;
+use crate::exec::{ExecIo, ExecProcess};
+use crate::{
+    frozen_process, prepare_rootfs, process_control, pty, rootfs, signal_container, signal_process, stop_gracefully, stop_process,
+    user, ExecConfig, ExecRuntime,
+    LogStore, RuntimeError, Supervisor,
+};
+
+/// Default `PATH` for workloads whose config does not set one (same as Docker).
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// Host device nodes bind-mounted into every container's `/dev`.
+const DEV_NODES: &[&str] = &["null", "zero", "full", "random", "urandom", "tty"];
+
+/// Mount options of the container's `/dev/pts` (as Docker's).
+const DEVPTS_OPTIONS: &str = "newinstance,ptmxmode=0666,mode=0620,gid=5";
+
+/// Directory (relative to the rootfs) the old root is moved to by `pivot_root`.
+const PIVOT_OLD: &str = ".pivot_root";
+const PIVOT_OLD_ABS: &str = "/.pivot_root";
+
+/// Namespaces an exec joins, in order. The mount namespace comes last since
+/// it hides the host's `/proc`.
+const EXEC_NAMESPACES: &[(&str, CloneFlags)] = &[
+    ("cgroup", CloneFlags::CLONE_NEWCGROUP),
+    ("ipc", CloneFlags::CLONE_NEWIPC),
+    ("uts", CloneFlags::CLONE_NEWUTS),
+    ("pid", CloneFlags::CLONE_NEWPID),
+    ("mnt", CloneFlags::CLONE_NEWNS),
+];
+
+pub struct LinuxRuntime {
+    pub image_store: Arc<furukawa_infra_fs::store::image::ImageStore>,
+    pub metadata_store: Arc<dyn furukawa_domain::image::store::ImageMetadataStore>,
+    pub containers_root: PathBuf,
+    pub supervisor: Arc<Supervisor>,
+    pub logs: Arc<LogStore>,
+    /// `None` if the host has no usable cgroup v2 hierarchy.
+    pub cgroups: Option<Arc<Cgroups>>,
+}
+
+/// Everything the forked child needs, converted up front so that nothing
+/// has to allocate between `fork` and `exec`.
+struct NamespaceSetup {
+    rootfs: CString,
+    /// (source, target, readonly)
+    binds: Vec<(CString, CString, bool)>,
+    hostname: String,
+    tty: bool,
+    /// `cgroup.procs` of the container's cgroup, if it has one.
+    cgroup_procs: Option<CString>,
+    /// The working directory, preceded by its ancestors (outermost first)
+    /// so that missing ones can be created in order.
+    working_dir: Vec<CString>,
+    uid: Uid,
+    gid: Gid,
+    groups: Vec<Gid>,
+}
+
+fn cstring(path: &Path) -> Result<CString, Error> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| Error::new(RuntimeError::NamespaceSetupFailed(format!("invalid path {:?}: {}", path, e))))
+}
+
+impl NamespaceSetup {
+    /// Runs in the child between `fork` and `exec`.
+    ///
+    /// Unsharing the PID namespace only affects children created afterwards,
+    /// so once the filesystem is set up we fork once more: the intermediate
+    /// process stays behind as a shim that mirrors the workload's exit status
+    /// (and owns its terminal, if it has one), and the grandchild becomes
+    /// PID 1 of the container and goes on to `exec` the workload.
+    fn enter(&self) -> std::io::Result<()> {
+        let mut namespaces =
+            CloneFlags::CLONE_NEWNS | CloneFlags::CLONE_NEWPID | CloneFlags::CLONE_NEWUTS | CloneFlags::CLONE_NEWIPC;
+        if let Some(procs) = &self.cgroup_procs {
+            // Everything forked from here on stays in the cgroup.
+            join_cgroup(procs)?;
+            namespaces |= CloneFlags::CLONE_NEWCGROUP;
+        }
+        unshare(namespaces).map_err(std::io::Error::from)?;
+
+        // Keep our mounts from propagating back to the host.
+        mount(None::<&str>, "/", None::<&str>, MsFlags::MS_REC | MsFlags::MS_PRIVATE, None::<&str>)
+            .map_err(std::io::Error::from)?;
+
+        // pivot_root requires the new root to be a mount point.
+        mount(
+            Some(self.rootfs.as_c_str()),
+            self.rootfs.as_c_str(),
+            None::<&str>,
+            MsFlags::MS_BIND | MsFlags::MS_REC,
+            None::<&str>,
+        )
+        .map_err(std::io::Error::from)?;
+
+        for (source, target, readonly) in &self.binds {
+            mount(
+                Some(source.as_c_str()),
+                target.as_c_str(),
+                None::<&str>,
+                MsFlags::MS_BIND | MsFlags::MS_REC,
+                None::<&str>,
+            )
+            .map_err(std::io::Error::from)?;
+
+            if *readonly {
+                mount(
+                    None::<&str>,
+                    target.as_c_str(),
+                    None::<&str>,
+                    MsFlags::MS_BIND | MsFlags::MS_REMOUNT | MsFlags::MS_RDONLY,
+                    None::<&str>,
+                )
+                .map_err(std::io::Error::from)?;
+            }
+        }
+
+        chdir(self.rootfs.as_c_str()).map_err(std::io::Error::from)?;
+        pivot_root(".", PIVOT_OLD).map_err(std::io::Error::from)?;
+        chdir("/").map_err(std::io::Error::from)?;
+
+        umount2(PIVOT_OLD_ABS, MntFlags::MNT_DETACH).map_err(std::io::Error::from)?;
+        let _ = std::fs::remove_dir(PIVOT_OLD_ABS);
+
+        // A devpts instance of our own, so that terminals allocated in the
+        // container (including its console) have names in it.
+        mount(
+            Some("devpts"),
+            "/dev/pts",
+            Some("devpts"),
+            MsFlags::MS_NOSUID | MsFlags::MS_NOEXEC,
+            Some(DEVPTS_OPTIONS),
+        )
+        .map_err(std::io::Error::from)?;
+
+        sethostname(&self.hostname).map_err(std::io::Error::from)?;
+
+        let console = if self.tty {
+            Some(pty::open_pair(c"/dev/pts/ptmx")?)
+        } else {
+            None
+        };
+
+        // SAFETY: we are the single-threaded child of the daemon's fork.
+        match unsafe { fork() }.map_err(std::io::Error::from)? {
+            ForkResult::Parent { child } => match &console {
+                Some((master, _)) => run_tty_shim(child, master.as_raw_fd()),
+                None => run_shim(child),
+            },
+            ForkResult::Child => {}
+        }
+
+        // procfs shows the PID namespace of whoever mounts it.
+        mount(
+            Some("proc"),
+            "/proc",
+            Some("proc"),
+            MsFlags::MS_NOSUID | MsFlags::MS_NODEV | MsFlags::MS_NOEXEC,
+            None::<&str>,
+        )
+        .map_err(std::io::Error::from)?;
+
+        if let Some((master, slave)) = console {
+            drop(master);
+            for fd in 0..3 {
+                dup2(slave.as_raw_fd(), fd).map_err(std::io::Error::from)?;
+            }
+            drop(slave);
+            pty::set_controlling_terminal()?;
+        }
+
+        // As in Docker, a missing working directory is created (by root).
+        for dir in &self.working_dir {
+            // SAFETY: a plain syscall on a path prepared before the fork.
+            if unsafe { libc::mkdir(dir.as_ptr(), 0o755) } < 0 {
+                let error = std::io::Error::last_os_error();
+                if error.raw_os_error() != Some(libc::EEXIST) {
+                    return Err(error);
+                }
+            }
+        }
+        if let Some(dir) = self.working_dir.last() {
+            chdir(dir.as_c_str()).map_err(std::io::Error::from)?;
+        }
+        setgroups(&self.groups).map_err(std::io::Error::from)?;
+        setgid(self.gid).map_err(std::io::Error::from)?;
+        setuid(self.uid).map_err(std::io::Error::from)?;
+        Ok(())
+    }
+}
+
+/// What an exec'd process needs to join a running container, prepared
+/// before `fork` like [`NamespaceSetup`].
+struct ExecSetup {
+    cgroup_procs: Option<CString>,
+    namespaces: Vec<(OwnedFd, CloneFlags)>,
+    working_dir: CString,
+    uid: Uid,
+    gid: Gid,
+    groups: Vec<Gid>,
+    tty: bool,
+}
+
+impl ExecSetup {
+    /// Runs in the child between `fork` and `exec`. Joining a PID namespace
+    /// only applies to children, so as in [`NamespaceSetup::enter`] a shim
+    /// stays behind and the grandchild runs the command.
+    fn enter(&self) -> std::io::Result<()> {
+        // Through the host's mount of the hierarchy, before joining the
+        // container's mount namespace.
+        if let Some(procs) = &self.cgroup_procs {
+            join_cgroup(procs)?;
+        }
+        for (fd, nstype) in &self.namespaces {
+            setns(fd, *nstype).map_err(std::io::Error::from)?;
+        }
+
+        // SAFETY: we are the single-threaded child of the daemon's fork.
+        match unsafe { fork() }.map_err(std::io::Error::from)? {
+            ForkResult::Parent { child } => run_shim(child),
+            ForkResult::Child => {}
+        }
+
+        if self.tty {
+            pty::set_controlling_terminal()?;
+        }
+        chdir(self.working_dir.as_c_str()).map_err(std::io::Error::from)?;
+        setgroups(&self.groups).map_err(std::io::Error::from)?;
+        setgid(self.gid).map_err(std::io::Error::from)?;
+        setuid(self.uid).map_err(std::io::Error::from)?;
+        Ok(())
+    }
+}
+
+/// Moves the calling process into the cgroup whose `cgroup.procs` is given.
+/// Only makes syscalls, so it can be used between `fork` and `exec`.
+fn join_cgroup(procs: &CString) -> std::io::Result<()> {
+    // SAFETY: plain syscalls on a descriptor we own until `close`.
+    unsafe {
+        let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // "0" stands for the writing process.
+        let written = libc::write(fd, c"0".as_ptr().cast(), 1);
+        let error = std::io::Error::last_os_error();
+        libc::close(fd);
+        if written < 0 {
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+/// Body of the intermediate process left outside the container's PID
+/// namespace. It never returns: it waits for the workload and exits with the
+/// same code (128 + signal number if the workload was killed).
+fn run_shim(workload: Pid) -> ! {
+    // Drop every inherited descriptor, notably std's exec-status pipe, so the
+    // daemon's spawn() returns as soon as the workload has exec'd.
+    // SAFETY: plain syscall on our own descriptor table.
+    unsafe {
+        libc::syscall(libc::SYS_close_range, 3, libc::c_uint::MAX, 0);
+    }
+    exit_with(workload)
+}
+
+/// [`run_shim`] for containers with a TTY. Until the terminal is closed the
+/// shim also copies between its master and the shim's own stdin and stdout,
+/// which are the container's FIFOs.
+fn run_tty_shim(workload: Pid, master: RawFd) -> ! {
+    // Keep only the master, as fd 3. Dropping our copy of the slave lets the
+    // master see the terminal close once the container is gone.
+    // SAFETY: plain syscalls on our own descriptor table.
+    unsafe {
+        libc::dup2(master, 3);
+        libc::syscall(libc::SYS_close_range, 4, libc::c_uint::MAX, 0);
+    }
+    relay_terminal(3);
+    exit_with(workload)
+}
+
+/// Copies terminal output to stdout and stdin to the terminal until the
+/// terminal is closed. Uses a stack buffer only, like the rest of the shim.
+fn relay_terminal(master: RawFd) {
+    let mut buf = [0u8; 4096];
+    let mut fds = [
+        libc::pollfd { fd: master, events: libc::POLLIN, revents: 0 },
+        libc::pollfd { fd: 0, events: libc::POLLIN, revents: 0 },
+    ];
+    loop {
+        // SAFETY: `fds` outlives the call.
+        if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+            if nix::errno::Errno::last() == nix::errno::Errno::EINTR {
+                continue;
+            }
+            return;
+        }
+        if fds[0].revents != 0 {
+            // SAFETY: reading into `buf`.
+            match unsafe { libc::read(master, buf.as_mut_ptr().cast(), buf.len()) } {
+                n if n > 0 => write_all(1, &buf[..n as usize]),
+                -1 if nix::errno::Errno::last() == nix::errno::Errno::EINTR => {}
+                // EIO: every slave handle is closed.
+                _ => return,
+            }
+        }
+        if fds[1].revents != 0 {
+            // SAFETY: reading into `buf`.
+            match unsafe { libc::read(0, buf.as_mut_ptr().cast(), buf.len()) } {
+                n if n > 0 => write_all(master, &buf[..n as usize]),
+                -1 if nix::errno::Errno::last() == nix::errno::Errno::EINTR => {}
+                // End of stdin; a negative fd is ignored by poll.
+                _ => fds[1].fd = -1,
+            }
+        }
+    }
+}
+
+/// Writes all of `data`, dropping it if `fd` fails.
+fn write_all(fd: RawFd, mut data: &[u8]) {
+    while !data.is_empty() {
+        // SAFETY: writing from `data`.
+        match unsafe { libc::write(fd, data.as_ptr().cast(), data.len()) } {
+            n if n > 0 => data = &data[n as usize..],
+            -1 if nix::errno::Errno::last() == nix::errno::Errno::EINTR => {}
+            _ => return,
+        }
+    }
+}
+
+/// Waits for the workload and exits with its code.
+fn exit_with(workload: Pid) -> ! {
+    loop {
+        let code = match waitpid(workload, None) {
+            Ok(WaitStatus::Exited(_, code)) => code,
+            Ok(WaitStatus::Signaled(_, signal, _)) => 128 + signal as i32,
+            Ok(_) | Err(nix::errno::Errno::EINTR) => continue,
+            Err(_) => 255,
+        };
+        // SAFETY: `_exit` skips the daemon's atexit handlers, which must not
+        // run in this forked copy.
+        unsafe { libc::_exit(code) }
+    }
+}
+
+/// Finds the host PID of the container's init process, i.e. the only child
+/// of the shim.
+fn workload_pid(shim_pid: u32) -> Option<u32> {
+    let children = format!("/proc/{0}/task/{0}/children", shim_pid);
+    if let Ok(content) = std::fs::read_to_string(children) {
+        if let Some(pid) = content.split_whitespace().find_map(|p| p.parse().ok()) {
+            return Some(pid);
+        }
+    }
+
+    // Kernels without CONFIG_PROC_CHILDREN: scan for a process whose parent
+    // is the shim.
+    std::fs::read_dir("/proc").ok()?.filter_map(|e| e.ok()).find_map(|entry| {
+        let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+        let stat = std::fs::read_to_string(entry.path().join("stat")).ok()?;
+        // The command name may contain spaces; fields resume after the last ')'.
+        let ppid: u32 = stat.rsplit_once(')')?.1.split_whitespace().nth(1)?.parse().ok()?;
+        (ppid == shim_pid).then_some(pid)
+    })
+}
+
+/// The container's hostname: the first 12 characters of its ID.
+fn hostname(container_id: &str) -> String {
+    container_id.chars().filter(|c| *c != '-').take(12).collect()
+}
+
+/// Clears the command's environment and sets the container's, with a default
+/// `PATH`.
+fn container_env(cmd: &mut Command, hostname: &str, env: &[String]) {
+    cmd.env_clear();
+    if !env.iter().any(|e| e.starts_with("PATH=")) {
+        cmd.env("PATH", DEFAULT_PATH);
+    }
+    cmd.env("HOSTNAME", hostname);
+    for env_var in env {
+        if let Some((key, value)) = env_var.split_once('=') {
+            cmd.env(key, value);
+        }
+    }
+}
+
+/// Creates `path` inside the rootfs as a mount target of the same kind as
+/// `source` (directory or empty file).
+fn ensure_mount_target(source: &Path, target: &Path) -> std::io::Result<()> {
+    if source.is_dir() {
+        std::fs::create_dir_all(target)
+    } else {
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        if !target.exists() {
+            std::fs::File::create(target)?;
+        }
+        Ok(())
+    }
+}
+
+impl LinuxRuntime {
+    /// Freezes or thaws the container through its cgroup.
+    /// Thaws a paused container that was sent a signal to end it, so that
+    /// it handles the signal.
+    async fn thaw(&self, container_id: &str) {
+        if let Err(e) = self.freeze(container_id, false).await {
+            tracing::warn!("Failed to thaw container {} after signalling it: {}", container_id, e);
+        }
+    }
+
+    async fn freeze(&self, container_id: &str, frozen: bool) -> Result<(), Error> {
+        let unsupported = |reason: &str| Err(Error::new(RuntimeError::PauseUnsupported(reason.to_string())));
+        let Some(cgroups) = self.cgroups.clone() else {
+            return unsupported("no cgroup v2 hierarchy");
+        };
+        if !cgroups.path(container_id).join("cgroup.freeze").exists() {
+            return unsupported("the container's cgroup has no freezer");
+        }
+
+        let id = container_id.to_string();
+        tokio::task::spawn_blocking(move || cgroups.freeze(&id, frozen))
+            .await
+            .map_err(|e| Error::new(RuntimeError::FreezeFailed(std::io::Error::other(e))))?
+            .map_err(|e| Error::new(RuntimeError::FreezeFailed(e)))
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for LinuxRuntime {
+    async fn start(&self, container: &Container<Created>) -> Result<Running, Error> {
+        let config = container.config();
+
+        info!("Starting Linux container {} with image: {}", container.id(), config.image);
+
+        // 1. Resolve image and compose the rootfs
+        let rootfs_dir = prepare_rootfs(
+            &self.image_store,
+            self.metadata_store.as_ref(),
+            &self.containers_root,
+            container,
+        ).await?;
+        let rootfs_dir = rootfs_dir
+            .canonicalize()
+            .map_err(|e| Error::new(RuntimeError::RootfsCompositionFailed(e.to_string())))?;
+
+        // 2. Create mount targets on the host side, before entering namespaces
+        let setup_err = |e: std::io::Error| Error::new(RuntimeError::NamespaceSetupFailed(e.to_string()));
+        std::fs::create_dir_all(rootfs_dir.join(PIVOT_OLD)).map_err(setup_err)?;
+        std::fs::create_dir_all(rootfs_dir.join("proc")).map_err(setup_err)?;
+        std::fs::create_dir_all(rootfs_dir.join("dev/pts")).map_err(setup_err)?;
+        let ptmx = rootfs_dir.join("dev/ptmx");
+        if ptmx.symlink_metadata().is_err() {
+            std::os::unix::fs::symlink("pts/ptmx", &ptmx).map_err(setup_err)?;
+        }
+
+        let mut binds = Vec::new();
+        let mut targets = vec!["/proc".to_string(), "/dev/pts".to_string(), "/dev/ptmx".to_string()];
+        for node in DEV_NODES {
+            let source = Path::new("/dev").join(node);
+            if !source.exists() {
+                continue;
+            }
+            let target = rootfs_dir.join("dev").join(node);
+            ensure_mount_target(&source, &target).map_err(setup_err)?;
+            binds.push((cstring(&source)?, cstring(&target)?, false));
+            targets.push(format!("/dev/{}", node));
+        }
+
+        for vol in &config.volumes {
+            let source = PathBuf::from(&vol.host_path);
+            let target = rootfs_dir.join(vol.container_path.trim_start_matches('/'));
+            ensure_mount_target(&source, &target).map_err(setup_err)?;
+            binds.push((cstring(&source)?, cstring(&target)?, vol.readonly));
+            targets.push(vol.container_path.clone());
+            info!("Bind-mounting {} -> {}", vol.host_path, vol.container_path);
+        }
+
+        // Mount targets are part of the setup, not changes of the container.
+        let targets: Vec<&str> = targets.iter().map(String::as_str).collect();
+        if let Err(e) = rootfs::record_runtime_paths(&self.containers_root.join(container.id()), &targets) {
+            tracing::warn!("Failed to record the mount targets of container {}: {}", container.id(), e);
+        }
+
+        let cgroup_procs = match &self.cgroups {
+            Some(cgroups) => Some(cstring(&cgroups.create(container.id(), &config.resources)?.join("cgroup.procs"))?),
+            None => None,
+        };
+
+        // The user is looked up in the image's own passwd and group files.
+        let user = user::resolve(&rootfs_dir, config.user.as_deref().unwrap_or(""))?;
+        let working_dir = Path::new(config.working_dir.as_deref().unwrap_or("/"));
+        let mut working_dirs = working_dir
+            .ancestors()
+            .filter(|dir| dir.parent().is_some())
+            .map(cstring)
+            .collect::<Result<Vec<_>, Error>>()?;
+        working_dirs.reverse();
+
+        let setup = NamespaceSetup {
+            rootfs: cstring(&rootfs_dir)?,
+            binds,
+            hostname: hostname(container.id()),
+            tty: config.tty,
+            cgroup_procs,
+            working_dir: working_dirs,
+            uid: Uid::from_raw(user.uid),
+            gid: Gid::from_raw(user.gid),
+            groups: std::iter::once(user.gid).chain(user.additional_gids).map(Gid::from_raw).collect(),
+        };
+
+        // 3. Build the command. The environment is the container's, plus
+        //    HOME from the user's passwd entry unless it sets one.
+        let command = config.command();
+        // Containers created before image defaults were applied may have
+        // no command at all.
+        let (program, args) = match command.split_first() {
+            Some((program, args)) => (program.as_str(), args),
+            None => ("sh", &[][..]),
+        };
+
+        let mut cmd = Command::new(program);
+        cmd.args(args);
+        container_env(&mut cmd, &setup.hostname, &config.env);
+        if !config.env.iter().any(|e| e.starts_with("HOME=")) {
+            cmd.env("HOME", &user.home);
+        }
+        if config.tty && !config.env.iter().any(|e| e.starts_with("TERM=")) {
+            cmd.env("TERM", "xterm");
+        }
+
+        // SAFETY: `enter` only performs syscalls and a fork; everything it
+        // needs was allocated beforehand.
+        unsafe {
+            cmd.pre_exec(move || setup.enter());
+        }
+
+        // 4. Logs and stdin: FIFOs that outlive the daemon, see `logs`. With
+        //    a TTY the shim copies between them and the terminal.
+        let (stdout_fifo, stderr_fifo, log_copy) = self.logs.fifos(container.id(), &config.log_config)?;
+
+        let stdin = if config.open_stdin {
+            Stdio::from(self.logs.stdin_fifo(container.id(), config.stdin_once).await?)
+        } else {
+            Stdio::null()
+        };
+
+        info!("Spawning namespaced process: {} {:?} in {:?}", program, args, rootfs_dir);
+        let child = cmd
+            .stdin(stdin)
+            .stdout(Stdio::from(stdout_fifo))
+            .stderr(Stdio::from(stderr_fifo))
+            .spawn()
+            .map_err(|e| Error::new(RuntimeError::SpawnFailed(e)))?;
+
+        let shim_pid = child.id().ok_or_else(|| Error::new(RuntimeError::NoPid))?;
+        // If the workload already exited there is nothing left but the shim.
+        let pid = workload_pid(shim_pid).unwrap_or(shim_pid);
+        // The shim mirrors the workload's exit code, so waiting on it is enough.
+        self.supervisor.watch(container.id(), pid, child, log_copy);
+
+        info!("Container started with PID: {} (shim {})", pid, shim_pid);
+
+        Ok(Running {
+            pid,
+            started_at: time::OffsetDateTime::now_utc(),
+            process_start: process_control::process_start(pid),
+        })
+    }
+
+    async fn stop(&self, container: &Container<Running>, options: &StopOptions) -> Result<i32, Error> {
+        // Mounts live in the container's mount namespace and disappear with it.
+        stop_gracefully(&self.supervisor, container, options).await
+    }
+
+    async fn kill(&self, container: &Container<Running>, signal: &str) -> Result<(), Error> {
+        signal_container(&self.supervisor, container, signal)
+    }
+
+    async fn stop_paused(&self, container: &Container<Paused>, options: &StopOptions) -> Result<i32, Error> {
+        let process = frozen_process(container.state());
+        stop_process(&self.supervisor, container.id(), container.config(), &process, options, self.thaw(container.id())).await
+    }
+
+    async fn kill_paused(&self, container: &Container<Paused>, signal: &str) -> Result<(), Error> {
+        let process = frozen_process(container.state());
+        if signal_process(&self.supervisor, container.id(), container.config(), &process, signal)? {
+            self.thaw(container.id()).await;
+        }
+        Ok(())
+    }
+
+    async fn pause(&self, container: &Container<Running>) -> Result<(), Error> {
+        self.freeze(container.id(), true).await
+    }
+
+    async fn unpause(&self, container: &Container<Paused>) -> Result<(), Error> {
+        self.freeze(container.id(), false).await
+    }
+
+    async fn resize(&self, container: &Container<Running>, height: u16, width: u16) -> Result<(), Error> {
+        if !container.config().tty {
+            return Ok(());
+        }
+        pty::resize_process(container.state().pid, height, width)
+            .map_err(|e| Error::new(RuntimeError::ResizeFailed(e)))
+    }
+}
+
+#[async_trait]
+impl ExecRuntime for LinuxRuntime {
+    async fn spawn_exec(&self, container: &Container<Running>, config: &ExecConfig) -> Result<ExecProcess, Error> {
+        let container_config = container.config();
+        let pid = container.state().pid;
+        let setup_err = |e: std::io::Error| Error::new(RuntimeError::NamespaceSetupFailed(e.to_string()));
+
+        // The container's own files, seen through its init process.
+        let container_root = PathBuf::from(format!("/proc/{}/root", pid));
+        let user_spec = config.user.as_ref().or(container_config.user.as_ref());
+        let user = user::resolve(&container_root, user_spec.map(String::as_str).unwrap_or(""))?;
+
+        let namespaces = EXEC_NAMESPACES
+            .iter()
+            .map(|(name, nstype)| {
+                let fd = std::fs::File::open(format!("/proc/{}/ns/{}", pid, name)).map_err(setup_err)?;
+                Ok((OwnedFd::from(fd), *nstype))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let working_dir = config
+            .working_dir
+            .as_ref()
+            .or(container_config.working_dir.as_ref())
+            .map(String::as_str)
+            .unwrap_or("/");
+        // Containers started without a cgroup have none to join.
+        let cgroup_procs = self
+            .cgroups
+            .as_ref()
+            .map(|cgroups| cgroups.path(container.id()).join("cgroup.procs"))
+            .filter(|procs| procs.exists())
+            .map(|procs| cstring(&procs))
+            .transpose()?;
+        let setup = ExecSetup {
+            cgroup_procs,
+            namespaces,
+            working_dir: cstring(Path::new(working_dir))?,
+            uid: Uid::from_raw(user.uid),
+            gid: Gid::from_raw(user.gid),
+            groups: std::iter::once(user.gid).chain(user.additional_gids).map(Gid::from_raw).collect(),
+            tty: config.tty,
+        };
+
+        let mut cmd = Command::new(&config.cmd[0]);
+        cmd.args(&config.cmd[1..]);
+        let env: Vec<String> = container_config.env.iter().chain(&config.env).cloned().collect();
+        container_env(&mut cmd, &hostname(container.id()), &env);
+        if !env.iter().any(|e| e.starts_with("HOME=")) {
+            cmd.env("HOME", &user.home);
+        }
+        if config.tty && !env.iter().any(|e| e.starts_with("TERM=")) {
+            cmd.env("TERM", "xterm");
+        }
+
+        // SAFETY: `enter` only performs syscalls and a fork; everything it
+        // needs was allocated beforehand.
+        unsafe {
+            cmd.pre_exec(move || setup.enter());
+        }
+
+        let pty_master = if config.tty {
+            // Containers started before they had a devpts of their own get
+            // a terminal of the host's.
+            let ptmx = container_root.join("dev/pts/ptmx");
+            let (master, slave) = if ptmx.exists() { pty::open_in(&ptmx, None) } else { pty::open(None) }.map_err(setup_err)?;
+            cmd.stdin(Stdio::from(slave.try_clone().map_err(setup_err)?))
+                .stdout(Stdio::from(slave.try_clone().map_err(setup_err)?))
+                .stderr(Stdio::from(slave));
+            Some(master)
+        } else {
+            let stdio = |attached: bool| if attached { Stdio::piped() } else { Stdio::null() };
+            cmd.stdin(stdio(config.attach_stdin))
+                .stdout(stdio(config.attach_stdout))
+                .stderr(stdio(config.attach_stderr));
+            None
+        };
+
+        info!("Exec in container {}: {:?} as {}:{}", container.id(), config.cmd, user.uid, user.gid);
+        let mut child = cmd.spawn().map_err(|e| Error::new(RuntimeError::SpawnFailed(e)))?;
+        // Close our copies of the PTY slave so the master sees EOF at exit.
+        drop(cmd);
+
+        let shim_pid = child.id().ok_or_else(|| Error::new(RuntimeError::NoPid))?;
+        let io = match pty_master {
+            Some(master) => ExecIo::Pty(master),
+            None => ExecIo::Pipes {
+                stdin: child.stdin.take(),
+                stdout: child.stdout.take(),
+                stderr: child.stderr.take(),
+            },
+        };
+        Ok(ExecProcess {
+            child,
+            pid: workload_pid(shim_pid).unwrap_or(shim_pid),
+            io,
+        })
+    }
+}
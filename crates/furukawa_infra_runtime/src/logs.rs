@@ -0,0 +1,686 @@
+//! json-file log driver.
+//!
+//! Container stdout/stderr are read by the daemon and stored one JSON object
+//! per line, in the same format as Docker's `json-file` driver:
+//!
+//! ```text
+//! {"log":"hello\n","stream":"stdout","time":"2024-05-01T10:00:00.123456789Z"}
+//! ```
+//!
+//! With a `max-size` the writer rotates `<id>-json.log` to `.1`, `.2`, ...
+//! (gzipped if `compress` is set), keeping `max-file` files in total. It
+//! runs on a blocking thread fed through a channel, so that writing,
+//! rotating and compressing never hold up the runtime.
+//!
+//! On Linux the workload writes into FIFOs rather than plain pipes. The shim
+//! holds a read-write handle on them, so the workload never sees `EPIPE`
+//! while the daemon is down, and a restarted daemon can pick the logs up
+//! again with [`LogStore::reattach`]. Containers created with `OpenStdin`
+//! get a stdin FIFO as well, whose write end is handed to the
+//! [`AttachHub`].
+//!
+//! Everything read is also published to the [`AttachHub`] for attached
+//! clients, so output produced while nobody is attached still ends up here.
+
+use crate::attach::AttachHub;
+use crate::RuntimeError;
+use furukawa_common::diagnostic::Error;
+use furukawa_domain::container::LogConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use time::OffsetDateTime;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+/// Longer lines are split into several entries (Docker uses the same size).
+const MAX_LINE: u64 = 16 * 1024;
+
+/// How long an exit waits for the last output to be written to the log.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Entries queued for the writer before the copiers wait for it.
+const WRITE_QUEUE: usize = 1024;
+
+/// Entries read from a rotated file per blocking call.
+const ROTATED_BATCH: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub log: String,
+    pub stream: Stream,
+    #[serde(with = "time::serde::rfc3339")]
+    pub time: OffsetDateTime,
+}
+
+/// Container logs on disk: `<root>/<id>/<id>-json.log` plus rotated files.
+pub struct LogStore {
+    root: PathBuf,
+    hub: Arc<AttachHub>,
+}
+
+impl LogStore {
+    pub fn new(root: PathBuf, hub: Arc<AttachHub>) -> Self {
+        Self { root, hub }
+    }
+
+    /// The hub attach sessions read output from and write stdin to.
+    pub fn hub(&self) -> &AttachHub {
+        &self.hub
+    }
+
+    fn container_dir(&self, container_id: &str) -> PathBuf {
+        self.root.join(container_id)
+    }
+
+    /// Path of the log file currently being written.
+    pub fn log_path(&self, container_id: &str) -> PathBuf {
+        self.container_dir(container_id).join(format!("{}-json.log", container_id))
+    }
+
+    /// Deletes all logs of a removed container.
+    pub fn remove(&self, container_id: &str) -> std::io::Result<()> {
+        match std::fs::remove_dir_all(self.container_dir(container_id)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Starts copying the process's stdout and stderr into the container's log.
+    pub fn copy<O, E>(&self, container_id: &str, config: &LogConfig, stdout: O, stderr: E) -> Result<LogCopy, Error>
+    where
+        O: AsyncRead + Send + Unpin + 'static,
+        E: AsyncRead + Send + Unpin + 'static,
+    {
+        std::fs::create_dir_all(self.container_dir(container_id))
+            .map_err(|e| Error::new(RuntimeError::LogSetupFailed(e)))?;
+        let (entries, writer) = LogWriter::spawn(self.log_path(container_id), config.clone())
+            .map_err(|e| Error::new(RuntimeError::LogSetupFailed(e)))?;
+        let output = |stream| Output {
+            container_id: container_id.to_string(),
+            stream,
+            hub: Arc::clone(&self.hub),
+        };
+        // The writer is last, so draining waits for what the copiers sent.
+        Ok(LogCopy {
+            tasks: vec![
+                tokio::spawn(copy_stream(stdout, output(Stream::Stdout), entries.clone())),
+                tokio::spawn(copy_stream(stderr, output(Stream::Stderr), entries)),
+                writer,
+            ],
+        })
+    }
+
+    #[cfg(unix)]
+    fn fifo_path(&self, container_id: &str, stream: Stream) -> PathBuf {
+        let name = match stream {
+            Stream::Stdout => "stdout.fifo",
+            Stream::Stderr => "stderr.fifo",
+        };
+        self.container_dir(container_id).join(name)
+    }
+
+    #[cfg(unix)]
+    fn stdin_fifo_path(&self, container_id: &str) -> PathBuf {
+        self.container_dir(container_id).join("stdin.fifo")
+    }
+
+    /// Creates the container's stdin FIFO, hands its write end to the
+    /// [`AttachHub`] and returns the end for the process.
+    ///
+    /// With `stdin_once` the process gets a read-only end, so it reads EOF
+    /// once the hub closes stdin (or the daemon goes away). Otherwise it gets
+    /// a read-write end, like the output FIFOs, and stdin stays open across
+    /// daemon restarts.
+    #[cfg(unix)]
+    pub async fn stdin_fifo(&self, container_id: &str, stdin_once: bool) -> Result<std::fs::File, Error> {
+        use nix::fcntl::{fcntl, FcntlArg, OFlag};
+        use nix::sys::stat::Mode;
+        use std::os::fd::AsRawFd;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let setup_err = |e| Error::new(RuntimeError::LogSetupFailed(e));
+        std::fs::create_dir_all(self.container_dir(container_id)).map_err(setup_err)?;
+        let path = self.stdin_fifo_path(container_id);
+        if let Err(e) = nix::unistd::mkfifo(&path, Mode::S_IRUSR | Mode::S_IWUSR) {
+            if e != nix::errno::Errno::EEXIST {
+                return Err(setup_err(e.into()));
+            }
+        }
+
+        let end = if stdin_once {
+            // Non-blocking so that opening does not wait for a writer; the
+            // process then reads in blocking mode as usual.
+            let end = std::fs::OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&path)
+                .map_err(setup_err)?;
+            fcntl(end.as_raw_fd(), FcntlArg::F_SETFL(OFlag::empty())).map_err(|e| setup_err(e.into()))?;
+            end
+        } else {
+            std::fs::OpenOptions::new().read(true).write(true).open(&path).map_err(setup_err)?
+        };
+
+        self.reattach_stdin(container_id).await.map_err(setup_err)?;
+        Ok(end)
+    }
+
+    /// Opens the write end of the container's stdin FIFO, if it has one and
+    /// the process still holds the other end.
+    #[cfg(unix)]
+    async fn reattach_stdin(&self, container_id: &str) -> std::io::Result<()> {
+        let path = self.stdin_fifo_path(container_id);
+        if !path.exists() {
+            return Ok(());
+        }
+        match tokio::net::unix::pipe::OpenOptions::new().open_sender(&path) {
+            Ok(sender) => {
+                self.hub.set_stdin(container_id, Box::new(sender)).await;
+                Ok(())
+            }
+            // No reader: the process is gone or closed its stdin.
+            Err(e) if e.raw_os_error() == Some(libc::ENXIO) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Creates the container's output FIFOs. Returns the (stdout, stderr)
+    /// ends to hand to the process and the copy reading from them.
+    #[cfg(unix)]
+    pub fn fifos(&self, container_id: &str, config: &LogConfig) -> Result<(std::fs::File, std::fs::File, LogCopy), Error> {
+        use nix::sys::stat::Mode;
+
+        let setup_err = |e| Error::new(RuntimeError::LogSetupFailed(e));
+        std::fs::create_dir_all(self.container_dir(container_id)).map_err(setup_err)?;
+
+        let open = |stream| {
+            let path = self.fifo_path(container_id, stream);
+            if let Err(e) = nix::unistd::mkfifo(&path, Mode::S_IRUSR | Mode::S_IWUSR) {
+                if e != nix::errno::Errno::EEXIST {
+                    return Err(setup_err(e.into()));
+                }
+            }
+            // Read-write, so that opening does not block waiting for a reader.
+            std::fs::OpenOptions::new().read(true).write(true).open(&path).map_err(setup_err)
+        };
+        let stdout = open(Stream::Stdout)?;
+        let stderr = open(Stream::Stderr)?;
+
+        let copy = self.fifo_copy(container_id, config).map_err(setup_err)?;
+        Ok((stdout, stderr, copy))
+    }
+
+    /// Resumes copying from the FIFOs of a container started by a previous
+    /// daemon, and reconnects its stdin. Containers without FIFOs get an
+    /// empty copy.
+    #[cfg(unix)]
+    pub async fn reattach(&self, container_id: &str, config: &LogConfig) -> std::io::Result<LogCopy> {
+        self.reattach_stdin(container_id).await?;
+        self.fifo_copy(container_id, config)
+    }
+
+    /// Starts copying from the container's output FIFOs, if it has them.
+    #[cfg(unix)]
+    fn fifo_copy(&self, container_id: &str, config: &LogConfig) -> std::io::Result<LogCopy> {
+        use tokio::net::unix::pipe;
+
+        let stdout_path = self.fifo_path(container_id, Stream::Stdout);
+        let stderr_path = self.fifo_path(container_id, Stream::Stderr);
+        if !stdout_path.exists() || !stderr_path.exists() {
+            return Ok(LogCopy::default());
+        }
+
+        let stdout = pipe::OpenOptions::new().open_receiver(&stdout_path)?;
+        let stderr = pipe::OpenOptions::new().open_receiver(&stderr_path)?;
+        self.copy(container_id, config, stdout, stderr)
+            .map_err(|e| std::io::Error::other(e.to_string()))
+    }
+
+    #[cfg(not(unix))]
+    pub async fn reattach(&self, _container_id: &str, _config: &LogConfig) -> std::io::Result<LogCopy> {
+        Ok(LogCopy::default())
+    }
+
+    /// Opens the log of a container, starting at its oldest rotated file;
+    /// `None` if the container has never written any output.
+    pub async fn reader(&self, container_id: &str) -> std::io::Result<Option<LogReader>> {
+        let path = self.log_path(container_id);
+        let current = match tokio::fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let identity = file_identity(&current.metadata().await?);
+        let dir_path = path.clone();
+        let rotated = blocking(move || rotated_files(&dir_path)).await?;
+
+        Ok(Some(LogReader {
+            rotated,
+            rotated_lines: None,
+            buffered: VecDeque::new(),
+            current: BufReader::new(current),
+            identity,
+            path,
+            partial: Vec::new(),
+        }))
+    }
+}
+
+/// Runs blocking file I/O off the runtime.
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> std::io::Result<T> + Send + 'static) -> std::io::Result<T> {
+    tokio::task::spawn_blocking(f).await.map_err(std::io::Error::other)?
+}
+
+/// Rotated files of the log at `path`, oldest first. Blocking.
+fn rotated_files(path: &Path) -> std::io::Result<VecDeque<PathBuf>> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name().and_then(|n| n.to_str())) else {
+        return Ok(VecDeque::new());
+    };
+    let prefix = format!("{}.", name);
+
+    let mut rotated: Vec<(u32, PathBuf)> = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(suffix) = file_name.to_str().and_then(|n| n.strip_prefix(&prefix)) else {
+            continue;
+        };
+        if let Ok(index) = suffix.trim_end_matches(".gz").parse() {
+            rotated.push((index, entry.path()));
+        }
+    }
+    rotated.sort_by_key(|(index, _)| std::cmp::Reverse(*index));
+    Ok(rotated.into_iter().map(|(_, p)| p).collect())
+}
+
+fn rotated_path(path: &Path, index: u32, compress: bool) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    if compress {
+        name.push(".gz");
+    }
+    PathBuf::from(name)
+}
+
+/// Identifies the file behind a path, to notice that it was rotated away.
+#[cfg(unix)]
+fn file_identity(metadata: &std::fs::Metadata) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Some(metadata.ino())
+}
+
+#[cfg(not(unix))]
+fn file_identity(metadata: &std::fs::Metadata) -> Option<u64> {
+    let created = metadata.created().ok()?;
+    created.duration_since(std::time::UNIX_EPOCH).ok().map(|d| d.as_nanos() as u64)
+}
+
+/// Entries on their way to the [`LogWriter`]; shared by the stdout and
+/// stderr copiers so that lines never interleave.
+#[derive(Clone)]
+struct LogSender(mpsc::Sender<LogEntry>);
+
+impl LogSender {
+    async fn write(&self, stream: Stream, log: String) {
+        let entry = LogEntry {
+            log,
+            stream,
+            time: OffsetDateTime::now_utc(),
+        };
+        // The writer only stops once every sender is gone.
+        let _ = self.0.send(entry).await;
+    }
+}
+
+/// Appends entries to a container's log file and rotates it, on a blocking
+/// thread of its own.
+struct LogWriter {
+    path: PathBuf,
+    config: LogConfig,
+    file: std::fs::File,
+    size: u64,
+}
+
+impl LogWriter {
+    /// Opens the log and starts writing what is sent to it, until every
+    /// sender has been dropped.
+    fn spawn(path: PathBuf, config: LogConfig) -> std::io::Result<(LogSender, JoinHandle<()>)> {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        let mut writer = Self { path, config, file, size };
+
+        let (tx, mut rx) = mpsc::channel(WRITE_QUEUE);
+        let task = tokio::task::spawn_blocking(move || {
+            while let Some(entry) = rx.blocking_recv() {
+                writer.write(&entry);
+            }
+        });
+        Ok((LogSender(tx), task))
+    }
+
+    fn write(&mut self, entry: &LogEntry) {
+        let Ok(mut line) = serde_json::to_vec(entry) else {
+            return;
+        };
+        line.push(b'\n');
+
+        if let Some(max_size) = self.config.max_size {
+            if self.size > 0 && self.size + line.len() as u64 > max_size {
+                if let Err(e) = self.rotate() {
+                    warn!("Failed to rotate {:?}: {}", self.path, e);
+                }
+            }
+        }
+        match self.file.write_all(&line) {
+            Ok(()) => self.size += line.len() as u64,
+            Err(e) => warn!("Failed to write container log: {}", e),
+        }
+    }
+
+    /// Shifts `.1` -> `.2` ... (dropping the oldest), moves the current file
+    /// to `.1` and starts a new one. With `max-file` 1 the log just starts over.
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let compress = self.config.compress;
+        let keep = self.config.max_file.saturating_sub(1);
+
+        if keep == 0 {
+            // Readers following the old file keep their handle on it.
+            let _ = std::fs::remove_file(&self.path);
+        } else {
+            for index in (1..keep).rev() {
+                let from = rotated_path(&self.path, index, compress);
+                if from.exists() {
+                    let to = rotated_path(&self.path, index + 1, compress);
+                    let _ = std::fs::remove_file(&to);
+                    std::fs::rename(&from, &to)?;
+                }
+            }
+
+            let first = rotated_path(&self.path, 1, false);
+            let _ = std::fs::remove_file(&first);
+            std::fs::rename(&self.path, &first)?;
+            if compress {
+                gzip(&first, &rotated_path(&self.path, 1, true))?;
+                std::fs::remove_file(&first)?;
+            }
+        }
+
+        self.file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn gzip(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mut input = std::fs::File::open(from)?;
+    let mut encoder = flate2::write::GzEncoder::new(std::fs::File::create(to)?, flate2::Compression::default());
+    std::io::copy(&mut input, &mut encoder)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+/// The tasks copying a container's output into its log.
+#[derive(Default)]
+pub struct LogCopy {
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl LogCopy {
+    /// Waits (briefly) until the output written before the process exited
+    /// has reached the log. Background processes that inherited stdout may
+    /// keep the streams open; their output keeps being copied regardless.
+    pub async fn drain(self) {
+        let all = async {
+            for task in self.tasks {
+                let _ = task.await;
+            }
+        };
+        let _ = tokio::time::timeout(DRAIN_TIMEOUT, all).await;
+    }
+}
+
+/// Where a copier's output goes besides the log.
+struct Output {
+    container_id: String,
+    stream: Stream,
+    hub: Arc<AttachHub>,
+}
+
+async fn copy_stream<R: AsyncRead + Unpin>(reader: R, output: Output, writer: LogSender) {
+    let stream = output.stream;
+    let mut reader = BufReader::new(reader);
+
+    // wsl.exe prints its own errors (distro missing, ...) in UTF-16.
+    if let Ok(start) = reader.fill_buf().await {
+        if start.starts_with(&[0xff, 0xfe]) {
+            let mut bytes = Vec::new();
+            let _ = reader.read_to_end(&mut bytes).await;
+            let units: Vec<u16> = bytes[2..]
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            let text = String::from_utf16_lossy(&units);
+            output.hub.publish(&output.container_id, stream, text.as_bytes());
+            for line in text.split_inclusive('\n') {
+                writer.write(stream, line.to_string()).await;
+            }
+            return;
+        }
+    }
+
+    // Attached clients get output as it comes (prompts have no newline);
+    // the log gets whole lines, split at `MAX_LINE`.
+    let mut line = Vec::new();
+    loop {
+        let chunk = match reader.fill_buf().await {
+            Ok([]) => break,
+            Ok(chunk) => chunk,
+            Err(e) => {
+                warn!("Failed to read container {:?}: {}", stream, e);
+                break;
+            }
+        };
+        output.hub.publish(&output.container_id, stream, chunk);
+
+        for piece in chunk.split_inclusive(|b| *b == b'\n') {
+            line.extend_from_slice(piece);
+            while line.len() as u64 >= MAX_LINE {
+                let rest = line.split_off(MAX_LINE as usize);
+                writer.write(stream, String::from_utf8_lossy(&line).into_owned()).await;
+                line = rest;
+            }
+            if line.ends_with(b"\n") {
+                writer.write(stream, String::from_utf8_lossy(&line).into_owned()).await;
+                line.clear();
+            }
+        }
+        let len = chunk.len();
+        reader.consume(len);
+    }
+    if !line.is_empty() {
+        writer.write(stream, String::from_utf8_lossy(&line).into_owned()).await;
+    }
+}
+
+/// Reads a container's log entries in order, rotated files first. At the end
+/// of the log it returns `None`, and picks up from there (following the log
+/// across rotations) if called again after more output has been written.
+pub struct LogReader {
+    /// Rotated files not read yet, oldest first.
+    rotated: VecDeque<PathBuf>,
+    /// The rotated file being read, if there are more entries in it.
+    rotated_lines: Option<RotatedLines>,
+    /// Entries read from it and not returned yet.
+    buffered: VecDeque<LogEntry>,
+    current: BufReader<tokio::fs::File>,
+    identity: Option<u64>,
+    path: PathBuf,
+    partial: Vec<u8>,
+}
+
+impl LogReader {
+    pub async fn next_entry(&mut self) -> std::io::Result<Option<LogEntry>> {
+        loop {
+            if let Some(entry) = self.buffered.pop_front() {
+                return Ok(Some(entry));
+            }
+            let lines = match self.rotated_lines.take() {
+                Some(lines) => lines,
+                None => {
+                    let Some(rotated) = self.rotated.pop_front() else {
+                        break;
+                    };
+                    match blocking(move || open_rotated(&rotated)).await {
+                        Ok(lines) => lines,
+                        // Rotated away (dropped as the oldest) while we were reading.
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                        Err(e) => return Err(e),
+                    }
+                }
+            };
+            (self.buffered, self.rotated_lines) = blocking(move || read_rotated(lines)).await?;
+        }
+
+        loop {
+            if let Some(entry) = self.next_current().await? {
+                return Ok(Some(entry));
+            }
+            // At the end of the file: if the writer has moved on to a new
+            // file, everything in this one has been read.
+            if !self.reopen_if_rotated().await? {
+                return Ok(None);
+            }
+        }
+    }
+
+    async fn next_current(&mut self) -> std::io::Result<Option<LogEntry>> {
+        loop {
+            self.current.read_until(b'\n', &mut self.partial).await?;
+            if !self.partial.ends_with(b"\n") {
+                // Nothing more yet, or a line still being written.
+                return Ok(None);
+            }
+
+            let line = std::mem::take(&mut self.partial);
+            if let Some(entry) = parse_line(&line) {
+                return Ok(Some(entry));
+            }
+        }
+    }
+
+    async fn reopen_if_rotated(&mut self) -> std::io::Result<bool> {
+        let file = match tokio::fs::File::open(&self.path).await {
+            Ok(file) => file,
+            // Between removal and re-creation.
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        let identity = file_identity(&file.metadata().await?);
+        if identity == self.identity {
+            return Ok(false);
+        }
+
+        self.current = BufReader::new(file);
+        self.identity = identity;
+        self.partial.clear();
+        Ok(true)
+    }
+}
+
+/// A rotated file, decompressed as it is read.
+type RotatedLines = Box<dyn BufRead + Send>;
+
+/// Blocking.
+fn open_rotated(path: &Path) -> std::io::Result<RotatedLines> {
+    let file = std::fs::File::open(path)?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        Ok(Box::new(std::io::BufReader::new(flate2::read::GzDecoder::new(file))))
+    } else {
+        Ok(Box::new(std::io::BufReader::new(file)))
+    }
+}
+
+/// Reads the next [`ROTATED_BATCH`] entries of a rotated file, handing the
+/// file back unless it has been read to the end. Blocking.
+fn read_rotated(mut lines: RotatedLines) -> std::io::Result<(VecDeque<LogEntry>, Option<RotatedLines>)> {
+    let mut entries = VecDeque::new();
+    let mut line = Vec::new();
+    while entries.len() < ROTATED_BATCH {
+        line.clear();
+        if lines.read_until(b'\n', &mut line)? == 0 {
+            return Ok((entries, None));
+        }
+        entries.extend(parse_line(&line));
+    }
+    Ok((entries, Some(lines)))
+}
+
+fn parse_line(line: &[u8]) -> Option<LogEntry> {
+    match serde_json::from_slice(line) {
+        Ok(entry) => Some(entry),
+        Err(e) => {
+            warn!("Skipping malformed log line: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `lines` through a fresh writer and waits until they are on disk.
+    async fn write_all(path: &Path, config: &LogConfig, stream: Stream, lines: impl IntoIterator<Item = String>) {
+        let (entries, writer) = LogWriter::spawn(path.to_path_buf(), config.clone()).unwrap();
+        for line in lines {
+            entries.write(stream, line).await;
+        }
+        drop(entries);
+        writer.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rotated_logs_read_back_in_order() {
+        let root = tempfile::TempDir::new().unwrap();
+        let logs = LogStore::new(root.path().to_path_buf(), Default::default());
+        std::fs::create_dir_all(root.path().join("c1")).unwrap();
+        let path = logs.log_path("c1");
+
+        // Every entry is ~80 bytes, so each file holds two of them.
+        let config = LogConfig { max_size: Some(200), max_file: 3, compress: true };
+        write_all(&path, &config, Stream::Stdout, (0..10).map(|i| format!("line {}\n", i))).await;
+
+        assert!(rotated_path(&path, 2, true).exists());
+        assert!(!rotated_path(&path, 3, true).exists());
+
+        let mut reader = logs.reader("c1").await.unwrap().expect("log exists");
+        let mut lines = Vec::new();
+        while let Some(entry) = reader.next_entry().await.unwrap() {
+            lines.push(entry.log);
+        }
+        assert_eq!(lines, ["line 4\n", "line 5\n", "line 6\n", "line 7\n", "line 8\n", "line 9\n"]);
+
+        // A reader at the end follows the log into the next file.
+        write_all(&path, &config, Stream::Stderr, ["line 10\n".to_string(), "line 11\n".to_string()]).await;
+        let entry = reader.next_entry().await.unwrap().expect("new entry");
+        assert_eq!((entry.log.as_str(), entry.stream), ("line 10\n", Stream::Stderr));
+        assert_eq!(reader.next_entry().await.unwrap().map(|e| e.log).as_deref(), Some("line 11\n"));
+    }
+}
@@ -1,23 +1,76 @@
+use crate::supervisor::Supervisor;
+use crate::RuntimeError;
 use furukawa_common::diagnostic::Error;
+use furukawa_domain::container::{Running, Stopped};
+use std::time::Duration;
 use tracing::{info, warn};
 
+/// Exit code of a process terminated by `SIGKILL` (128 + 9).
+pub const KILLED_EXIT_CODE: i32 = 137;
+
+pub const SIGKILL: i32 = 9;
+
+/// Linux signal numbers by name. Containers always run Linux workloads, so
+/// these are the numbers even when the daemon itself runs on Windows.
+const SIGNALS: &[(&str, i32)] = &[
+    ("HUP", 1), ("INT", 2), ("QUIT", 3), ("ILL", 4), ("TRAP", 5), ("ABRT", 6),
+    ("BUS", 7), ("FPE", 8), ("KILL", 9), ("USR1", 10), ("SEGV", 11), ("USR2", 12),
+    ("PIPE", 13), ("ALRM", 14), ("TERM", 15), ("STKFLT", 16), ("CHLD", 17),
+    ("CONT", 18), ("STOP", 19), ("TSTP", 20), ("TTIN", 21), ("TTOU", 22),
+    ("URG", 23), ("XCPU", 24), ("XFSZ", 25), ("VTALRM", 26), ("PROF", 27),
+    ("WINCH", 28), ("IO", 29), ("PWR", 30), ("SYS", 31),
+];
+
+/// Default grace period before a stop escalates to `SIGKILL` (Docker's default).
+const DEFAULT_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait for the exit to be observed after `SIGKILL`.
+const KILL_WAIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Polling interval for processes the supervisor does not track.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Parses a Docker-style signal: `SIGTERM`, `TERM` or `15`.
+pub fn parse_signal(signal: &str) -> Result<i32, Error> {
+    let invalid = || Error::new(RuntimeError::InvalidSignal(signal.to_string()));
+
+    if let Ok(number) = signal.parse::<i32>() {
+        return if (1..=64).contains(&number) { Ok(number) } else { Err(invalid()) };
+    }
+
+    let upper = signal.to_ascii_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SIGNALS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, number)| *number)
+        .ok_or_else(invalid)
+}
+
 #[cfg(windows)]
 mod sys {
-    use windows_sys::Win32::Foundation::{CloseHandle, FALSE, HANDLE};
+    use windows_sys::Win32::Foundation::{CloseHandle, FALSE, FILETIME, HANDLE, STILL_ACTIVE};
     use windows_sys::Win32::System::Threading::{
-        OpenProcess, TerminateProcess, PROCESS_TERMINATE,
+        GetExitCodeProcess, GetProcessTimes, OpenProcess, TerminateProcess,
+        PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_TERMINATE,
     };
 
-    pub fn kill_process(pid: u32) -> Result<(), std::io::Error> {
+    pub fn send_signal(pid: u32, signal: i32) -> Result<(), std::io::Error> {
+        // Windows has no signals; the only thing we can deliver is a kill.
+        if signal != super::SIGKILL {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "only SIGKILL can be delivered on Windows",
+            ));
+        }
+
         unsafe {
             let handle: HANDLE = OpenProcess(PROCESS_TERMINATE, FALSE, pid);
             if handle == 0 {
                 return Err(std::io::Error::last_os_error());
             }
 
-            // In a real "10 year" implementation, we'd send a close event first, wait, then terminate.
-            // For now, we wrap TerminateProcess safely.
-            let result = TerminateProcess(handle, 1);
+            let result = TerminateProcess(handle, super::KILLED_EXIT_CODE as u32);
             CloseHandle(handle);
 
             if result == 0 {
@@ -26,27 +79,235 @@ mod sys {
             Ok(())
         }
     }
+
+    pub fn is_alive(pid: u32) -> bool {
+        unsafe {
+            let handle: HANDLE = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+            if handle == 0 {
+                return false;
+            }
+
+            let mut code = 0u32;
+            let ok = GetExitCodeProcess(handle, &mut code);
+            CloseHandle(handle);
+
+            ok != 0 && code == STILL_ACTIVE as u32
+        }
+    }
+
+    pub fn is_gone(e: &std::io::Error) -> bool {
+        // ERROR_INVALID_PARAMETER: no process with that PID.
+        e.raw_os_error() == Some(87)
+    }
+
+    /// Creation time of the process as a FILETIME tick count.
+    pub fn process_start(pid: u32) -> Option<String> {
+        unsafe {
+            let handle: HANDLE = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+            if handle == 0 {
+                return None;
+            }
+
+            let zero = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
+            let (mut creation, mut exit, mut kernel, mut user) = (zero, zero, zero, zero);
+            let ok = GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user);
+            CloseHandle(handle);
+
+            if ok == 0 {
+                return None;
+            }
+            let ticks = ((creation.dwHighDateTime as u64) << 32) | creation.dwLowDateTime as u64;
+            Some(ticks.to_string())
+        }
+    }
 }
 
-#[cfg(not(windows))]
+#[cfg(unix)]
 mod sys {
-    pub fn kill_process(_pid: u32) -> Result<(), std::io::Error> {
+    use nix::errno::Errno;
+    use nix::sys::signal::{kill, Signal};
+    use nix::unistd::Pid;
+
+    fn pid(pid: u32) -> Result<Pid, std::io::Error> {
+        i32::try_from(pid)
+            .map(Pid::from_raw)
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidInput, "PID out of range"))
+    }
+
+    pub fn send_signal(target: u32, signal: i32) -> Result<(), std::io::Error> {
+        let signal = Signal::try_from(signal)?;
+        kill(pid(target)?, signal)?;
+        Ok(())
+    }
+
+    pub fn is_alive(target: u32) -> bool {
+        match pid(target) {
+            Ok(p) => !matches!(kill(p, None), Err(Errno::ESRCH)),
+            Err(_) => false,
+        }
+    }
+
+    pub fn is_gone(e: &std::io::Error) -> bool {
+        e.raw_os_error() == Some(Errno::ESRCH as i32)
+    }
+
+    /// Boot id plus the `starttime` field of `/proc/<pid>/stat` (clock ticks
+    /// since boot), which together identify a process across reboots.
+    #[cfg(target_os = "linux")]
+    pub fn process_start(target: u32) -> Option<String> {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", target)).ok()?;
+        // The command name may contain spaces; fields resume after the last ')'
+        // with field 3 (state), so starttime (field 22) is the 20th.
+        let start_ticks = stat.rsplit_once(')')?.1.split_whitespace().nth(19)?;
+        let boot_id = std::fs::read_to_string("/proc/sys/kernel/random/boot_id").ok()?;
+        Some(format!("{}:{}", boot_id.trim(), start_ticks))
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn process_start(_target: u32) -> Option<String> {
+        None
+    }
+}
+
+#[cfg(not(any(windows, unix)))]
+mod sys {
+    pub fn send_signal(_pid: u32, _signal: i32) -> Result<(), std::io::Error> {
         Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "Platform not supported"))
     }
+
+    pub fn is_alive(_pid: u32) -> bool {
+        false
+    }
+
+    pub fn is_gone(_e: &std::io::Error) -> bool {
+        false
+    }
+
+    pub fn process_start(_pid: u32) -> Option<String> {
+        None
+    }
+}
+
+pub use sys::{is_alive, process_start};
+
+/// Whether `pid` still belongs to the process that was started as the
+/// container. Without a recorded start time only liveness can be checked.
+pub fn is_same_process(pid: u32, start: Option<&str>) -> bool {
+    match start {
+        Some(expected) => process_start(pid).as_deref() == Some(expected),
+        None => is_alive(pid),
+    }
 }
 
-pub fn stop_container(pid: u32) -> Result<(), Error> {
-    info!("Stopping container process PID: {}", pid);
-    match sys::kill_process(pid) {
-        Ok(_) => {
-            info!("Successfully terminated process {}", pid);
+/// Sends `signal` to a container process. A process that is already gone is
+/// not an error, so signalling stays idempotent.
+pub fn send_signal(pid: u32, signal: i32) -> Result<(), Error> {
+    match sys::send_signal(pid, signal) {
+        Ok(()) => Ok(()),
+        Err(e) if sys::is_gone(&e) => {
+            info!("Process {} already exited; signal {} not delivered", pid, signal);
             Ok(())
         }
-        Err(e) => {
-            // If process is already gone, we consider it a success for idempotency
-            // But we should verify error code. For now, warn and proceed.
-            warn!("Failed to terminate process {}: {}. It might have already exited.", pid, e);
-            Ok(()) 
+        Err(e) => Err(Error::new(RuntimeError::SignalFailed { pid, signal, source: e })),
+    }
+}
+
+/// Waits up to `timeout` (forever if `None`) for the process to exit and
+/// returns its exit code, or `None` if it is still running.
+async fn wait_exit(
+    supervisor: &Supervisor,
+    container_id: &str,
+    process: &Running,
+    fallback_code: i32,
+    timeout: Option<Duration>,
+) -> Option<i32> {
+    let exited = async {
+        if supervisor.is_tracking(container_id) {
+            return supervisor.wait(container_id).await;
         }
+        // Not watched by the supervisor: poll instead.
+        while is_same_process(process.pid, process.process_start.as_deref()) {
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        Some(fallback_code)
+    };
+
+    match timeout {
+        Some(t) => tokio::time::timeout(t, exited).await.ok().flatten(),
+        None => exited.await,
+    }
+}
+
+/// Stops a container process: sends `signal`, waits up to `timeout` for it to
+/// exit and only then escalates to `SIGKILL`. Returns the exit code.
+/// `resume` runs once the first signal is sent (to thaw a paused container).
+///
+/// A negative `timeout_secs` waits indefinitely, as in Docker.
+pub async fn stop_container(
+    supervisor: &Supervisor,
+    container_id: &str,
+    process: &Running,
+    signal: Option<&str>,
+    timeout_secs: Option<i64>,
+    resume: impl std::future::Future<Output = ()>,
+) -> Result<i32, Error> {
+    let signal = parse_signal(signal.unwrap_or("SIGTERM"))?;
+    let timeout = match timeout_secs {
+        Some(secs) if secs < 0 => None,
+        Some(secs) => Some(Duration::from_secs(secs.unsigned_abs())),
+        None => Some(DEFAULT_STOP_TIMEOUT),
+    };
+    let pid = process.pid;
+
+    // Never signal a stranger that reused the PID of a dead container.
+    if !supervisor.is_tracking(container_id) && !is_same_process(pid, process.process_start.as_deref()) {
+        warn!("Process {} of container {} is already gone", pid, container_id);
+        return Ok(Stopped::UNKNOWN_EXIT_CODE);
+    }
+
+    info!("Stopping container process PID: {} with signal {}", pid, signal);
+
+    let sent = send_signal(pid, signal);
+    resume.await;
+    if signal != SIGKILL {
+        match sent {
+            Ok(()) => {
+                if let Some(code) = wait_exit(supervisor, container_id, process, 128 + signal, timeout).await {
+                    info!("Process {} exited with code {}", pid, code);
+                    return Ok(code);
+                }
+                warn!("Process {} did not exit within {:?}; sending SIGKILL", pid, timeout);
+            }
+            Err(e) => warn!("Failed to send signal {} to {}: {}; sending SIGKILL", signal, pid, e),
+        }
+        send_signal(pid, SIGKILL)?;
+    } else {
+        sent?;
+    }
+
+    let code = wait_exit(supervisor, container_id, process, KILLED_EXIT_CODE, Some(KILL_WAIT_TIMEOUT))
+        .await
+        .unwrap_or(KILLED_EXIT_CODE);
+    info!("Process {} killed (exit code {})", pid, code);
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_signal;
+
+    #[test]
+    fn test_parse_signal_forms() {
+        assert_eq!(parse_signal("SIGTERM").ok(), Some(15));
+        assert_eq!(parse_signal("term").ok(), Some(15));
+        assert_eq!(parse_signal("SIGKILL").ok(), Some(9));
+        assert_eq!(parse_signal("2").ok(), Some(2));
+    }
+
+    #[test]
+    fn test_parse_signal_rejects_unknown() {
+        assert!(parse_signal("SIGNOPE").is_err());
+        assert!(parse_signal("0").is_err());
+        assert!(parse_signal("65").is_err());
     }
 }
@@ -0,0 +1,181 @@
+//! Pseudo-terminals for processes started with `Tty: true`.
+//!
+//! The slave side becomes the process's stdin/stdout/stderr and controlling
+//! terminal. For execs the daemon keeps the master, reads the (raw) output
+//! from it and writes input to it. Native Linux containers allocate theirs
+//! from the container's own `devpts` and leave the master to the shim, so
+//! that the terminal outlives the daemon like the log FIFOs do.
+
+use std::io;
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use tokio::io::unix::AsyncFd;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// The daemon's end of a pseudo-terminal. Clones share the same terminal,
+/// so one can be read from while another is written to or resized.
+#[derive(Clone)]
+pub struct PtyMaster {
+    fd: Arc<AsyncFd<OwnedFd>>,
+}
+
+/// Opens a new pseudo-terminal of the given size (`None` keeps the default).
+/// Returns the master and the slave to hand to the process.
+pub fn open(size: Option<(u16, u16)>) -> io::Result<(PtyMaster, OwnedFd)> {
+    let pty = nix::pty::openpty(None, None).map_err(io::Error::from)?;
+    Ok((PtyMaster::new(pty.master, size)?, pty.slave))
+}
+
+/// Like [`open`], but allocates from the `devpts` instance of the given
+/// `ptmx` (e.g. a container's `/proc/<pid>/root/dev/pts/ptmx`), so that the
+/// terminal has a name inside that container.
+#[cfg(target_os = "linux")]
+pub fn open_in(ptmx: &std::path::Path, size: Option<(u16, u16)>) -> io::Result<(PtyMaster, OwnedFd)> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let ptmx = std::ffi::CString::new(ptmx.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    let (master, slave) = open_pair(&ptmx)?;
+    Ok((PtyMaster::new(master, size)?, slave))
+}
+
+/// Opens a master on `ptmx` and its slave. Only makes syscalls, so it can
+/// be used between `fork` and `exec`.
+#[cfg(target_os = "linux")]
+pub(crate) fn open_pair(ptmx: &std::ffi::CStr) -> io::Result<(OwnedFd, OwnedFd)> {
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: plain syscalls; each descriptor is owned as soon as it exists.
+    unsafe {
+        let fd = libc::open(ptmx.as_ptr(), libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let master = OwnedFd::from_raw_fd(fd);
+        if libc::unlockpt(master.as_raw_fd()) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = libc::ioctl(master.as_raw_fd(), libc::TIOCGPTPEER, libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((master, OwnedFd::from_raw_fd(fd)))
+    }
+}
+
+/// Resizes the terminal a process has as its stdin; `ENOTTY` if it has
+/// none.
+#[cfg(target_os = "linux")]
+pub fn resize_process(pid: u32, height: u16, width: u16) -> io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let terminal = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK)
+        .open(format!("/proc/{}/fd/0", pid))?;
+    set_size(terminal.as_raw_fd(), height, width)
+}
+
+fn set_size(fd: RawFd, height: u16, width: u16) -> io::Result<()> {
+    let size = libc::winsize {
+        ws_row: height,
+        ws_col: width,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: TIOCSWINSZ only reads the winsize we pass.
+    if unsafe { libc::ioctl(fd, libc::TIOCSWINSZ, &size) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+impl PtyMaster {
+    fn new(master: OwnedFd, size: Option<(u16, u16)>) -> io::Result<Self> {
+        let flags = nix::fcntl::fcntl(master.as_raw_fd(), nix::fcntl::FcntlArg::F_GETFL).map_err(io::Error::from)?;
+        let flags = nix::fcntl::OFlag::from_bits_truncate(flags) | nix::fcntl::OFlag::O_NONBLOCK;
+        nix::fcntl::fcntl(master.as_raw_fd(), nix::fcntl::FcntlArg::F_SETFL(flags)).map_err(io::Error::from)?;
+
+        if let Some((height, width)) = size {
+            set_size(master.as_raw_fd(), height, width)?;
+        }
+        Ok(Self {
+            fd: Arc::new(AsyncFd::new(master)?),
+        })
+    }
+
+    /// Sets the terminal size; the process gets a `SIGWINCH`.
+    pub fn resize(&self, height: u16, width: u16) -> io::Result<()> {
+        set_size(self.fd.as_raw_fd(), height, width)
+    }
+}
+
+/// Makes the slave the calling process's controlling terminal. Meant for
+/// `pre_exec`, after the slave has been installed as stdin.
+pub fn set_controlling_terminal() -> io::Result<()> {
+    nix::unistd::setsid().map_err(io::Error::from)?;
+    // SAFETY: plain ioctl on our own stdin.
+    if unsafe { libc::ioctl(0, libc::TIOCSCTTY as _, 0) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+impl AsyncRead for PtyMaster {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        loop {
+            let mut guard = ready!(self.fd.poll_read_ready(cx))?;
+            let unfilled = buf.initialize_unfilled();
+            let result = guard.try_io(|fd| {
+                // SAFETY: reading into the unfilled part of `buf`.
+                let n = unsafe { libc::read(fd.as_raw_fd(), unfilled.as_mut_ptr().cast(), unfilled.len()) };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            });
+            match result {
+                Ok(Ok(n)) => {
+                    buf.advance(n);
+                    return Poll::Ready(Ok(()));
+                }
+                // Linux reports EIO once every slave handle is closed: EOF.
+                Ok(Err(e)) if e.raw_os_error() == Some(libc::EIO) => return Poll::Ready(Ok(())),
+                Ok(Err(e)) => return Poll::Ready(Err(e)),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+}
+
+impl AsyncWrite for PtyMaster {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        loop {
+            let mut guard = ready!(self.fd.poll_write_ready(cx))?;
+            let result = guard.try_io(|fd| {
+                // SAFETY: writing from `buf`.
+                let n = unsafe { libc::write(fd.as_raw_fd(), buf.as_ptr().cast(), buf.len()) };
+                if n < 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(n as usize)
+                }
+            });
+            match result {
+                Ok(result) => return Poll::Ready(result),
+                Err(_would_block) => continue,
+            }
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,165 @@
+//! Restart policies.
+//!
+//! The [`RestartManager`] listens to the [`Supervisor`](crate::Supervisor)'s exit events and
+//! starts containers again according to their `RestartPolicy`, with the same
+//! backoff Docker uses: 100ms, doubling on every restart up to a minute, and
+//! reset once a run lasted at least ten seconds.
+
+use crate::supervisor::ExitEvent;
+use furukawa_common::diagnostic::Error;
+use furukawa_domain::container::runtime::ContainerRuntime;
+use furukawa_domain::container::store::ContainerStore;
+use furukawa_domain::container::{AnyContainer, Container, Stopped};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// A run at least this long resets the backoff.
+const BACKOFF_RESET_AFTER: Duration = Duration::from_secs(10);
+
+pub struct RestartManager {
+    store: Arc<dyn ContainerStore>,
+    runtime: Arc<dyn ContainerRuntime>,
+    /// Delay before the next restart, per container id.
+    backoff: Mutex<HashMap<String, Duration>>,
+}
+
+impl RestartManager {
+    pub fn new(store: Arc<dyn ContainerStore>, runtime: Arc<dyn ContainerRuntime>) -> Self {
+        Self {
+            store,
+            runtime,
+            backoff: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Handles exit events (from [`Supervisor::subscribe`](crate::Supervisor::subscribe)) until the
+    /// supervisor goes away.
+    pub async fn run(self: Arc<Self>, mut events: broadcast::Receiver<ExitEvent>) {
+        loop {
+            match events.recv().await {
+                Ok(event) => self.on_exit(event).await,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Restart manager missed {} exit events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// Starts the containers whose policy asks to be running whenever the
+    /// daemon is (`always`, and `unless-stopped` unless stopped by hand).
+    pub async fn start_on_boot(&self) -> Result<(), Error> {
+        for container in self.store.list().await? {
+            let AnyContainer::Stopped(container) = container else {
+                continue;
+            };
+            let policy = &container.config().restart_policy;
+            if !policy.start_on_boot(container.state().manually_stopped) {
+                continue;
+            }
+
+            let id = container.id().to_string();
+            info!(id = %id, policy = policy.name(), "Starting container on boot");
+            match container.start(self.runtime.as_ref()).await {
+                Ok(running) => self.store.save_running(&running).await?,
+                Err(e) => error!("Failed to start container {} on boot: {}", id, e),
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_exit(self: &Arc<Self>, event: ExitEvent) {
+        if event.manually_stopped {
+            self.reset_backoff(&event.container_id);
+            return;
+        }
+
+        let container = match self.store.get_any(&event.container_id).await {
+            Ok(Some(AnyContainer::Stopped(c))) => c,
+            Ok(_) => return,
+            Err(e) => {
+                error!("Failed to load container {} after exit: {}", event.container_id, e);
+                return;
+            }
+        };
+
+        let policy = &container.config().restart_policy;
+        let state = container.state();
+        if !policy.should_restart(state.exit_code, container.restart_count(), state.manually_stopped) {
+            self.reset_backoff(&event.container_id);
+            return;
+        }
+
+        let delay = self.next_backoff(&container);
+        info!(
+            id = %container.id(),
+            policy = policy.name(),
+            exit_code = state.exit_code,
+            "Restarting container in {:?}",
+            delay
+        );
+
+        let manager = Arc::clone(self);
+        let finished_at = state.finished_at;
+        let id = event.container_id;
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            manager.restart(&id, finished_at).await;
+        });
+    }
+
+    /// Restarts the container unless someone else touched it during the
+    /// backoff (started, removed or stopped it again).
+    async fn restart(&self, id: &str, finished_at: time::OffsetDateTime) {
+        let container = match self.store.get_any(id).await {
+            Ok(Some(AnyContainer::Stopped(c)))
+                if c.state().finished_at == finished_at && !c.state().manually_stopped =>
+            {
+                c
+            }
+            Ok(_) => return,
+            Err(e) => {
+                error!("Failed to load container {} for restart: {}", id, e);
+                return;
+            }
+        };
+
+        match container.auto_restart(self.runtime.as_ref()).await {
+            Ok(running) => {
+                if let Err(e) = self.store.save_running(&running).await {
+                    error!("Failed to save restarted container {}: {}", id, e);
+                }
+            }
+            Err(e) => error!("Failed to restart container {}: {}", id, e),
+        }
+    }
+
+    fn next_backoff(&self, container: &Container<Stopped>) -> Duration {
+        let state = container.state();
+        let ran_for = state
+            .started_at
+            .map(|started| state.finished_at - started)
+            .unwrap_or(time::Duration::ZERO);
+
+        let Ok(mut backoff) = self.backoff.lock() else {
+            return INITIAL_BACKOFF;
+        };
+        let delay = match backoff.get(container.id()) {
+            Some(previous) if ran_for < BACKOFF_RESET_AFTER => *previous,
+            _ => INITIAL_BACKOFF,
+        };
+        backoff.insert(container.id().to_string(), (delay * 2).min(MAX_BACKOFF));
+        delay
+    }
+
+    fn reset_backoff(&self, container_id: &str) {
+        if let Ok(mut backoff) = self.backoff.lock() {
+            backoff.remove(container_id);
+        }
+    }
+}
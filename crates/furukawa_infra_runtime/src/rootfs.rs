@@ -0,0 +1,170 @@
+//! Container root filesystems on disk.
+//!
+//! A container's rootfs is a full copy of its image layers, composed under
+//! `<containers_root>/<id>/rootfs` on first start. Composition leaves a
+//! [`SNAPSHOT`] of what it wrote next to it, to which the runtime adds its
+//! mount targets; anything else that differs from it is the container's
+//! own change, for `docker diff` and sizes.
+
+use crate::RuntimeError;
+use furukawa_common::diagnostic::Error;
+use furukawa_infra_fs::store::image::ImageStore;
+use furukawa_infra_fs::store::snapshot::{self, Change, RootfsSnapshot};
+use std::path::{Path, PathBuf};
+
+/// Written next to `rootfs` once composition has finished.
+pub const SNAPSHOT: &str = "rootfs.snapshot.json";
+
+/// Composes the rootfs in `container_dir` from image `layers` unless it
+/// exists already, and snapshots it. Returns the rootfs directory.
+pub async fn compose(image_store: &ImageStore, layers: &[String], container_dir: &Path) -> Result<PathBuf, Error> {
+    let rootfs_dir = container_dir.join("rootfs");
+    if rootfs_dir.exists() {
+        return Ok(rootfs_dir);
+    }
+
+    tracing::info!("Composing rootfs at {:?}", rootfs_dir);
+    let snapshot = image_store.compose_rootfs(layers, rootfs_dir.clone()).await
+        .map_err(|e| Error::new(RuntimeError::RootfsCompositionFailed(e.to_string())))?;
+    let path = container_dir.join(SNAPSHOT);
+    if let Err(e) = tokio::task::spawn_blocking(move || snapshot.save(&path)).await.map_err(std::io::Error::other).and_then(|r| r) {
+        tracing::warn!("Failed to save the snapshot of rootfs {:?}: {}", rootfs_dir, e);
+    }
+    Ok(rootfs_dir)
+}
+
+/// Adds `paths` (container paths the runtime created, such as mount
+/// targets) to the snapshot, so they do not show up as changes. Blocking
+/// file I/O.
+pub fn record_runtime_paths(container_dir: &Path, paths: &[&str]) -> std::io::Result<()> {
+    let path = container_dir.join(SNAPSHOT);
+    let Some(mut snapshot) = RootfsSnapshot::load(&path)? else {
+        return Ok(());
+    };
+    if snapshot.record(&container_dir.join("rootfs"), paths) {
+        snapshot.save(&path)?;
+    }
+    Ok(())
+}
+
+/// Changes of the rootfs in `container_dir` since composition; none if it
+/// has not been composed yet. Blocking file I/O.
+pub fn changes(container_dir: &Path) -> Result<Vec<Change>, Error> {
+    let rootfs = container_dir.join("rootfs");
+    if !rootfs.is_dir() {
+        return Ok(Vec::new());
+    }
+    let failed = |e: std::io::Error| Error::new(RuntimeError::ArchiveFailed(e.to_string()));
+    match RootfsSnapshot::load(&container_dir.join(SNAPSHOT)).map_err(failed)? {
+        Some(snapshot) => snapshot.diff(&rootfs).map_err(failed),
+        None => Err(Error::new(RuntimeError::ChangesUntracked(container_dir.display().to_string()))),
+    }
+}
+
+/// Writes the changes of the rootfs in `container_dir` to `out` as a
+/// gzipped image layer, see
+/// [`archive::changes_layer`](crate::archive::changes_layer). Blocking file
+/// I/O.
+pub fn diff_layer(container_dir: &Path, out: impl std::io::Write) -> Result<(), Error> {
+    let changes = changes(container_dir)?;
+    let mut gz = flate2::write::GzEncoder::new(out, flate2::Compression::default());
+    crate::archive::changes_layer(&container_dir.join("rootfs"), &changes, &mut gz)?;
+    gz.finish().map(|_| ()).map_err(|e| Error::new(RuntimeError::ArchiveFailed(e.to_string())))
+}
+
+/// Writes the changes of the rootfs in `container_dir` as a new layer of
+/// `image_store`; returns its digest and size in bytes. Blocking file I/O.
+pub fn commit_layer(image_store: &ImageStore, container_dir: &Path) -> Result<(String, u64), Error> {
+    let failed = |e: furukawa_infra_fs::store::image::StoreError| Error::new(RuntimeError::ArchiveFailed(e.to_string()));
+    let mut layer = image_store.layer_writer().map_err(failed)?;
+    diff_layer(container_dir, &mut layer)?;
+    layer.commit().map_err(failed)
+}
+
+/// `SizeRw` and `SizeRootFs` of a container, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DiskUsage {
+    /// Files created or modified since composition; unknown for rootfs
+    /// composed before snapshots were kept.
+    pub rw: Option<u64>,
+    pub total: u64,
+}
+
+/// Disk usage of the rootfs in `container_dir`, or `None` if it has not
+/// been composed yet. Symlinks are not followed. Blocking file I/O.
+pub fn disk_usage(container_dir: &Path) -> Option<DiskUsage> {
+    let rootfs = container_dir.join("rootfs");
+    if !rootfs.is_dir() {
+        return None;
+    }
+    let snapshot = RootfsSnapshot::load(&container_dir.join(SNAPSHOT)).ok().flatten();
+
+    let mut usage = DiskUsage {
+        rw: snapshot.as_ref().map(|_| 0),
+        total: 0,
+    };
+    walk(&rootfs, &rootfs, snapshot.as_ref(), &mut usage);
+    Some(usage)
+}
+
+fn walk(rootfs: &Path, dir: &Path, snapshot: Option<&RootfsSnapshot>, usage: &mut DiskUsage) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.path().symlink_metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            walk(rootfs, &entry.path(), snapshot, usage);
+            continue;
+        }
+
+        usage.total += metadata.len();
+        if let (Some(rw), Some(snapshot)) = (usage.rw.as_mut(), snapshot) {
+            if !snapshot.is_pristine(&snapshot::container_path(rootfs, &entry.path()), &metadata) {
+                *rw += metadata.len();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Composes `layers` for a new container and commits what `change`
+    /// does to it as one more layer.
+    async fn commit(store: &ImageStore, layers: &mut Vec<String>, dir: &Path, change: impl FnOnce(&Path)) {
+        let rootfs = compose(store, layers, dir).await.unwrap();
+        change(&rootfs);
+        layers.push(commit_layer(store, dir).unwrap().0);
+    }
+
+    #[tokio::test]
+    async fn test_whiteout_then_recreate_round_trips() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let store = ImageStore::new(tmp.path().join("images"));
+
+        let mut base = store.layer_writer().unwrap();
+        {
+            let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(&mut base, flate2::Compression::default()));
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4);
+            header.set_mode(0o644);
+            tar.append_data(&mut header, "a", &b"base"[..]).unwrap();
+            tar.into_inner().unwrap().finish().unwrap();
+        }
+        let mut layers = vec![base.commit().unwrap().0];
+
+        commit(&store, &mut layers, &tmp.path().join("c1"), |rootfs| std::fs::remove_file(rootfs.join("a")).unwrap()).await;
+        commit(&store, &mut layers, &tmp.path().join("c2"), |rootfs| {
+            assert!(!rootfs.join("a").exists());
+            std::fs::write(rootfs.join("a"), "again").unwrap();
+        })
+        .await;
+
+        let rootfs = compose(&store, &layers, &tmp.path().join("c3")).await.unwrap();
+        assert_eq!(std::fs::read_to_string(rootfs.join("a")).unwrap(), "again");
+    }
+}
@@ -0,0 +1,317 @@
+//! Resource usage of running containers.
+//!
+//! Counters come from the container's cgroup where it has one (see
+//! [`cgroup`](crate::cgroup)), and otherwise from `/proc`, summed over the
+//! container's process tree. Anything neither source provides is left out
+//! rather than reported as zero.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// `/proc` reports CPU times in USER_HZ, which is 100 on every Linux
+/// architecture.
+const USER_HZ: u64 = 100;
+const NANOS_PER_TICK: u64 = 1_000_000_000 / USER_HZ;
+
+/// One reading of a container's counters.
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub read: time::OffsetDateTime,
+    pub cpu: CpuUsage,
+    /// CPU time of the whole host in nanoseconds.
+    pub system_cpu_ns: Option<u64>,
+    pub online_cpus: u32,
+    pub memory: MemoryUsage,
+    pub pids: u64,
+    pub pids_limit: Option<u64>,
+    pub block_io: Vec<BlockIo>,
+    /// Per interface of the container's network namespace, without `lo`.
+    pub networks: BTreeMap<String, NetworkUsage>,
+}
+
+/// CPU time in nanoseconds; throttling only with a cgroup.
+#[derive(Debug, Clone, Default)]
+pub struct CpuUsage {
+    pub total_ns: u64,
+    pub user_ns: u64,
+    pub system_ns: u64,
+    pub periods: u64,
+    pub throttled_periods: u64,
+    pub throttled_ns: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryUsage {
+    pub usage: Option<u64>,
+    /// The cgroup's limit, or the host's memory.
+    pub limit: Option<u64>,
+    /// cgroup v2 `memory.stat`; empty without a cgroup.
+    pub stats: BTreeMap<String, u64>,
+}
+
+/// Bytes and operations on one block device (`0:0` for the `/proc`
+/// fallback, which does not tell devices apart).
+#[derive(Debug, Clone, Default)]
+pub struct BlockIo {
+    pub major: u64,
+    pub minor: u64,
+    pub read_bytes: u64,
+    pub write_bytes: u64,
+    pub reads: u64,
+    pub writes: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NetworkUsage {
+    pub rx_bytes: u64,
+    pub rx_packets: u64,
+    pub rx_errors: u64,
+    pub rx_dropped: u64,
+    pub tx_bytes: u64,
+    pub tx_packets: u64,
+    pub tx_errors: u64,
+    pub tx_dropped: u64,
+}
+
+/// Reads the counters of the container whose main process is `pid`, from
+/// `cgroup` (its cgroup directory) where possible. Blocking file I/O.
+pub fn collect(pid: u32, cgroup: Option<&Path>) -> Sample {
+    let read = time::OffsetDateTime::now_utc();
+    let cgroup_file = |name: &str| cgroup.and_then(|c| std::fs::read_to_string(c.join(name)).ok());
+
+    let pids: Vec<u32> = match cgroup_file("cgroup.procs") {
+        Some(procs) => procs.lines().filter_map(|l| l.trim().parse().ok()).collect(),
+        None => process_tree(pid),
+    };
+
+    let cpu = match cgroup_file("cpu.stat") {
+        Some(stat) => {
+            let stat = parse_flat_keyed(&stat);
+            let usec = |key: &str| stat.get(key).copied().unwrap_or(0) * 1_000;
+            CpuUsage {
+                total_ns: usec("usage_usec"),
+                user_ns: usec("user_usec"),
+                system_ns: usec("system_usec"),
+                periods: stat.get("nr_periods").copied().unwrap_or(0),
+                throttled_periods: stat.get("nr_throttled").copied().unwrap_or(0),
+                throttled_ns: usec("throttled_usec"),
+            }
+        }
+        None => {
+            let (user, system) = pids.iter().filter_map(|&p| process_cpu_ticks(p)).fold((0, 0), |(u, s), (pu, ps)| (u + pu, s + ps));
+            CpuUsage {
+                total_ns: (user + system) * NANOS_PER_TICK,
+                user_ns: user * NANOS_PER_TICK,
+                system_ns: system * NANOS_PER_TICK,
+                ..Default::default()
+            }
+        }
+    };
+
+    let memory = MemoryUsage {
+        usage: cgroup_file("memory.current")
+            .and_then(|v| v.trim().parse().ok())
+            .or_else(|| (!pids.is_empty()).then(|| pids.iter().filter_map(|&p| process_rss(p)).sum())),
+        limit: cgroup_file("memory.max")
+            .and_then(|v| v.trim().parse().ok())
+            .or_else(host_memory),
+        stats: cgroup_file("memory.stat").map(|s| parse_flat_keyed(&s)).unwrap_or_default(),
+    };
+
+    let block_io = match cgroup_file("io.stat") {
+        Some(stat) => parse_io_stat(&stat),
+        None => {
+            let total = pids.iter().filter_map(|&p| process_io(p)).fold(BlockIo::default(), |mut total, io| {
+                total.read_bytes += io.read_bytes;
+                total.write_bytes += io.write_bytes;
+                total.reads += io.reads;
+                total.writes += io.writes;
+                total
+            });
+            if pids.is_empty() { Vec::new() } else { vec![total] }
+        }
+    };
+
+    Sample {
+        read,
+        cpu,
+        system_cpu_ns: system_cpu_ns(),
+        online_cpus: std::thread::available_parallelism().map(|n| n.get() as u32).unwrap_or(1),
+        memory,
+        pids: cgroup_file("pids.current")
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(pids.len() as u64),
+        pids_limit: cgroup_file("pids.max").and_then(|v| v.trim().parse().ok()),
+        block_io,
+        networks: std::fs::read_to_string(format!("/proc/{}/net/dev", pid))
+            .map(|dev| parse_net_dev(&dev))
+            .unwrap_or_default(),
+    }
+}
+
+/// `pid` and all of its descendants.
+pub(crate) fn process_tree(pid: u32) -> Vec<u32> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let parents: Vec<(u32, u32)> = entries
+        .flatten()
+        .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+        .filter_map(|p| Some((p, stat_fields(p)?.get(1)?.parse().ok()?)))
+        .collect();
+
+    if !parents.iter().any(|&(p, _)| p == pid) {
+        return Vec::new();
+    }
+    let mut tree = vec![pid];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        tree.extend(parents.iter().filter(|&&(_, ppid)| ppid == parent).map(|&(p, _)| p));
+        i += 1;
+    }
+    tree
+}
+
+/// The fields of `/proc/<pid>/stat` after the command name, starting with
+/// the state.
+fn stat_fields(pid: u32) -> Option<Vec<String>> {
+    let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+    // The command name is in parentheses and may contain anything.
+    let rest = &stat[stat.rfind(')')? + 1..];
+    Some(rest.split_whitespace().map(str::to_string).collect())
+}
+
+/// (utime, stime) in ticks.
+fn process_cpu_ticks(pid: u32) -> Option<(u64, u64)> {
+    let fields = stat_fields(pid)?;
+    Some((fields.get(11)?.parse().ok()?, fields.get(12)?.parse().ok()?))
+}
+
+fn process_rss(pid: u32) -> Option<u64> {
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    kb_field(&status, "VmRSS:")
+}
+
+fn process_io(pid: u32) -> Option<BlockIo> {
+    let io = parse_flat_keyed(&std::fs::read_to_string(format!("/proc/{}/io", pid)).ok()?.replace(':', ""));
+    Some(BlockIo {
+        read_bytes: *io.get("read_bytes")?,
+        write_bytes: *io.get("write_bytes")?,
+        reads: io.get("syscr").copied().unwrap_or(0),
+        writes: io.get("syscw").copied().unwrap_or(0),
+        ..Default::default()
+    })
+}
+
+fn host_memory() -> Option<u64> {
+    kb_field(&std::fs::read_to_string("/proc/meminfo").ok()?, "MemTotal:")
+}
+
+/// A `<key> <n> kB` line of `/proc/meminfo` or `/proc/<pid>/status`, in bytes.
+fn kb_field(text: &str, key: &str) -> Option<u64> {
+    let line = text.lines().find(|l| l.starts_with(key))?;
+    Some(line[key.len()..].split_whitespace().next()?.parse::<u64>().ok()? * 1024)
+}
+
+/// The host's total CPU time from the `cpu` line of `/proc/stat`.
+fn system_cpu_ns() -> Option<u64> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let line = stat.lines().find(|l| l.starts_with("cpu "))?;
+    let ticks: u64 = line.split_whitespace().skip(1).filter_map(|v| v.parse::<u64>().ok()).sum();
+    Some(ticks * NANOS_PER_TICK)
+}
+
+/// cgroup v2 flat keyed files (`cpu.stat`, `memory.stat`): `<key> <value>`
+/// per line.
+fn parse_flat_keyed(text: &str) -> BTreeMap<String, u64> {
+    text.lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(' ')?;
+            Some((key.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// `io.stat`: `<major>:<minor> rbytes=.. wbytes=.. rios=.. wios=.. ...`
+fn parse_io_stat(text: &str) -> Vec<BlockIo> {
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let (major, minor) = fields.next()?.split_once(':')?;
+            let mut io = BlockIo {
+                major: major.parse().ok()?,
+                minor: minor.parse().ok()?,
+                ..Default::default()
+            };
+            for field in fields {
+                let Some((key, value)) = field.split_once('=') else { continue };
+                let value = value.parse().unwrap_or(0);
+                match key {
+                    "rbytes" => io.read_bytes = value,
+                    "wbytes" => io.write_bytes = value,
+                    "rios" => io.reads = value,
+                    "wios" => io.writes = value,
+                    _ => {}
+                }
+            }
+            Some(io)
+        })
+        .collect()
+}
+
+/// `/proc/<pid>/net/dev`, skipping its two header lines and loopback.
+fn parse_net_dev(text: &str) -> BTreeMap<String, NetworkUsage> {
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let name = name.trim();
+            let c: Vec<u64> = counters.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+            if name == "lo" || c.len() < 12 {
+                return None;
+            }
+            Some((
+                name.to_string(),
+                NetworkUsage {
+                    rx_bytes: c[0],
+                    rx_packets: c[1],
+                    rx_errors: c[2],
+                    rx_dropped: c[3],
+                    tx_bytes: c[8],
+                    tx_packets: c[9],
+                    tx_errors: c[10],
+                    tx_dropped: c[11],
+                },
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_counters() {
+        let cpu = parse_flat_keyed("usage_usec 1500\nuser_usec 1000\nsystem_usec 500\nnr_periods 0\n");
+        assert_eq!(cpu.get("usage_usec"), Some(&1500));
+
+        let io = parse_io_stat("8:0 rbytes=4096 wbytes=8192 rios=1 wios=2 dbytes=0 dios=0\n");
+        assert_eq!((io[0].major, io[0].minor, io[0].read_bytes, io[0].writes), (8, 0, 4096, 2));
+
+        let dev = "Inter-|   Receive |  Transmit\n face |bytes packets errs drop fifo frame compressed multicast|bytes packets errs drop fifo colls carrier compressed\n    lo: 10 1 0 0 0 0 0 0 10 1 0 0 0 0 0 0\n  eth0: 1000 10 1 2 0 0 0 0 500 5 3 4 0 0 0 0\n";
+        let networks = parse_net_dev(dev);
+        assert_eq!(networks.len(), 1);
+        let eth0 = &networks["eth0"];
+        assert_eq!((eth0.rx_bytes, eth0.rx_dropped, eth0.tx_bytes, eth0.tx_dropped), (1000, 2, 500, 4));
+    }
+
+    #[test]
+    fn test_collect_own_process() {
+        let sample = collect(std::process::id(), None);
+        assert!(sample.pids >= 1);
+        assert!(sample.memory.usage.is_some_and(|usage| usage > 0));
+        assert!(collect(u32::MAX, None).memory.usage.is_none());
+    }
+}
@@ -0,0 +1,333 @@
+//! Exit tracking for container processes.
+//!
+//! Runtimes hand every spawned process to the [`Supervisor`], which waits on
+//! it in the background. When the process ends the container is moved to
+//! `Stopped` with the real exit code and persisted, whether it was stopped
+//! through the API or exited by itself.
+//!
+//! Every recorded exit is also published on a per-container
+//! [`ExitNotice`] channel that outlives single runs, which is what
+//! `POST /containers/:id/wait` blocks on.
+//!
+//! At startup, [`Supervisor::reconcile`] deals with containers a previous
+//! daemon left `running`: dead ones are marked stopped, live ones re-adopted.
+
+use crate::logs::{LogCopy, LogStore};
+use crate::process_control;
+use furukawa_common::diagnostic::Error;
+use furukawa_domain::container::store::ContainerStore;
+use furukawa_domain::container::{AnyContainer, Stopped};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::process::Child;
+use tokio::sync::{broadcast, watch};
+use tracing::{error, info, warn};
+
+/// How often adopted processes (not our children) are checked for exit.
+const ADOPTED_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long an exit waits for the container's start to be persisted.
+const START_SAVE_TIMEOUT: Duration = Duration::from_secs(5);
+const START_SAVE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+pub struct Supervisor {
+    store: Arc<dyn ContainerStore>,
+    logs: Arc<LogStore>,
+    /// Latest exit status per container id (`None` while still running).
+    exits: Mutex<HashMap<String, watch::Receiver<Option<i32>>>>,
+    /// Containers whose next exit was requested (stop/kill), so that
+    /// restart policies leave them alone.
+    expected: Mutex<HashSet<String>>,
+    events: broadcast::Sender<ExitEvent>,
+    starts: broadcast::Sender<StartEvent>,
+    /// Exit history per container id, kept until the container is removed.
+    notices: Mutex<HashMap<String, watch::Sender<ExitNotice>>>,
+}
+
+/// Published every time a container's exit has been recorded.
+#[derive(Debug, Clone)]
+pub struct ExitEvent {
+    pub container_id: String,
+    pub exit_code: i32,
+    pub manually_stopped: bool,
+}
+
+/// Published every time the supervisor takes charge of a container process:
+/// on every start, and when re-adopting one after a daemon restart.
+#[derive(Debug, Clone)]
+pub struct StartEvent {
+    pub container_id: String,
+    pub pid: u32,
+    /// [`ExitNotice::exits`] at the start; the run is over once it grows.
+    pub exits: u64,
+}
+
+/// What has happened to a container since the daemon started, as seen by
+/// [`Supervisor::notices`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExitNotice {
+    /// Number of exits recorded so far.
+    pub exits: u64,
+    /// Exit code of the latest one.
+    pub exit_code: Option<i32>,
+    /// Set once the container has been removed.
+    pub removed: bool,
+}
+
+impl Supervisor {
+    pub fn new(store: Arc<dyn ContainerStore>, logs: Arc<LogStore>) -> Self {
+        let (events, _) = broadcast::channel(64);
+        let (starts, _) = broadcast::channel(64);
+        Self {
+            store,
+            logs,
+            exits: Mutex::new(HashMap::new()),
+            expected: Mutex::new(HashSet::new()),
+            events,
+            starts,
+            notices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Receives an [`ExitEvent`] for every recorded container exit.
+    pub fn subscribe(&self) -> broadcast::Receiver<ExitEvent> {
+        self.events.subscribe()
+    }
+
+    /// Receives a [`StartEvent`] for every container process taken charge of.
+    pub fn subscribe_starts(&self) -> broadcast::Receiver<StartEvent> {
+        self.starts.subscribe()
+    }
+
+    /// Follows the exits (and the removal) of a container. Compare
+    /// [`ExitNotice::exits`] against the value seen at subscription to wait
+    /// for the next exit.
+    pub fn notices(&self, container_id: &str) -> watch::Receiver<ExitNotice> {
+        self.notice_sender(container_id).subscribe()
+    }
+
+    fn announce_start(&self, container_id: &str, pid: u32) {
+        let exits = self.notice_sender(container_id).borrow().exits;
+        // Nobody listening (no health monitor) is fine.
+        let _ = self.starts.send(StartEvent {
+            container_id: container_id.to_string(),
+            pid,
+            exits,
+        });
+    }
+
+    fn notice_sender(&self, container_id: &str) -> watch::Sender<ExitNotice> {
+        let mut notices = match self.notices.lock() {
+            Ok(notices) => notices,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        notices
+            .entry(container_id.to_string())
+            .or_insert_with(|| watch::channel(ExitNotice::default()).0)
+            .clone()
+    }
+
+    /// Marks the container's next exit as requested through the API.
+    pub fn expect_exit(&self, container_id: &str) {
+        if let Ok(mut expected) = self.expected.lock() {
+            expected.insert(container_id.to_string());
+        }
+    }
+
+    /// Takes ownership of a freshly spawned container process.
+    ///
+    /// `pid` is the PID recorded in the container's `Running` state; it is
+    /// used to ignore exits of a previous run once the container has been
+    /// started again. The exit is recorded once `logs` has caught up.
+    pub fn watch(self: &Arc<Self>, container_id: &str, pid: u32, mut child: Child, logs: LogCopy) {
+        let (tx, rx) = watch::channel(None);
+        if let Ok(mut exits) = self.exits.lock() {
+            exits.insert(container_id.to_string(), rx);
+        }
+        if let Ok(mut expected) = self.expected.lock() {
+            expected.remove(container_id);
+        }
+        self.announce_start(container_id, pid);
+
+        let supervisor = Arc::clone(self);
+        let id = container_id.to_string();
+        tokio::spawn(async move {
+            let exit_code = match child.wait().await {
+                Ok(status) => exit_code(status),
+                Err(e) => {
+                    warn!("Failed to wait on container {} (PID {}): {}", id, pid, e);
+                    -1
+                }
+            };
+            info!(id = %id, pid, exit_code, "Container process exited");
+            logs.drain().await;
+
+            supervisor.record_exit(&id, pid, exit_code).await;
+            let _ = tx.send(Some(exit_code));
+        });
+    }
+
+    /// Tracks a container process started by a previous daemon instance.
+    /// It is not our child, so its exit is detected by polling and its exit
+    /// code is unknown.
+    pub fn adopt(self: &Arc<Self>, container_id: &str, pid: u32, process_start: Option<String>, logs: LogCopy) {
+        let (tx, rx) = watch::channel(None);
+        if let Ok(mut exits) = self.exits.lock() {
+            exits.insert(container_id.to_string(), rx);
+        }
+        if let Ok(mut expected) = self.expected.lock() {
+            expected.remove(container_id);
+        }
+        self.announce_start(container_id, pid);
+
+        let supervisor = Arc::clone(self);
+        let id = container_id.to_string();
+        tokio::spawn(async move {
+            while process_control::is_same_process(pid, process_start.as_deref()) {
+                tokio::time::sleep(ADOPTED_POLL_INTERVAL).await;
+            }
+            let exit_code = Stopped::UNKNOWN_EXIT_CODE;
+            info!(id = %id, pid, "Adopted container process exited");
+            logs.drain().await;
+
+            supervisor.record_exit(&id, pid, exit_code).await;
+            let _ = tx.send(Some(exit_code));
+        });
+    }
+
+    /// Checks every container the store believes is running (or paused)
+    /// against the live process table (PID plus process start time). Dead
+    /// ones are marked stopped with an unknown exit code; live ones are
+    /// adopted.
+    pub async fn reconcile(self: &Arc<Self>) -> Result<(), Error> {
+        for container in self.store.list().await? {
+            let (pid, process_start) = match &container {
+                AnyContainer::Running(c) => (c.state().pid, c.state().process_start.clone()),
+                AnyContainer::Paused(c) => (c.state().pid, c.state().process_start.clone()),
+                _ => continue,
+            };
+
+            if pid != 0 && process_control::is_same_process(pid, process_start.as_deref()) {
+                info!(id = %container.id(), pid, "Re-adopting {} container", container.status());
+                let log_copy = match self.logs.reattach(container.id(), &container.config().log_config).await {
+                    Ok(copy) => copy,
+                    Err(e) => {
+                        warn!("Failed to reattach logs of {}: {}", container.id(), e);
+                        LogCopy::default()
+                    }
+                };
+                self.adopt(container.id(), pid, process_start, log_copy);
+            } else {
+                warn!(id = %container.id(), pid, "Container process is gone; marking stopped");
+                let stopped = match container {
+                    AnyContainer::Running(c) => c.exited(Stopped::UNKNOWN_EXIT_CODE),
+                    AnyContainer::Paused(c) => c.exited(Stopped::UNKNOWN_EXIT_CODE),
+                    _ => continue,
+                };
+                self.store.save_stopped(&stopped).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Waits for the tracked process of a container to exit and returns its
+    /// exit code. Returns `None` if the process is not tracked (for example
+    /// because the container was never started by this daemon).
+    pub async fn wait(&self, container_id: &str) -> Option<i32> {
+        let mut rx = self.exits.lock().ok()?.get(container_id).cloned()?;
+        let code = rx.wait_for(Option::is_some).await.ok()?;
+        *code
+    }
+
+    /// Whether this supervisor owns the container's current process.
+    pub fn is_tracking(&self, container_id: &str) -> bool {
+        self.exits
+            .lock()
+            .map(|exits| exits.contains_key(container_id))
+            .unwrap_or(false)
+    }
+
+    /// Drops the exit status of a removed container and wakes whoever waits
+    /// for its removal.
+    pub fn forget(&self, container_id: &str) {
+        let notice = match self.notices.lock() {
+            Ok(mut notices) => notices.remove(container_id),
+            Err(poisoned) => poisoned.into_inner().remove(container_id),
+        };
+        if let Some(notice) = notice {
+            notice.send_modify(|notice| notice.removed = true);
+        }
+        if let Ok(mut exits) = self.exits.lock() {
+            exits.remove(container_id);
+        }
+        if let Ok(mut expected) = self.expected.lock() {
+            expected.remove(container_id);
+        }
+    }
+
+    async fn record_exit(&self, id: &str, pid: u32, exit_code: i32) {
+        // A short-lived process can exit before whoever started it has saved
+        // the `Running` state, so give that save a moment to land.
+        let mut waited = Duration::ZERO;
+        let container = loop {
+            match self.store.get_any(id).await {
+                Ok(Some(c @ AnyContainer::Running(_))) | Ok(Some(c @ AnyContainer::Paused(_))) if c.pid() == Some(pid) => break c,
+                // Restarted since, or removed.
+                Ok(Some(AnyContainer::Running(_))) | Ok(Some(AnyContainer::Paused(_))) | Ok(None) => return,
+                Ok(Some(_)) if waited < START_SAVE_TIMEOUT => {
+                    tokio::time::sleep(START_SAVE_POLL_INTERVAL).await;
+                    waited += START_SAVE_POLL_INTERVAL;
+                }
+                Ok(Some(_)) => {
+                    warn!("Container {} exited but its start was never recorded", id);
+                    return;
+                }
+                Err(e) => {
+                    error!("Failed to load container {} after exit: {}", id, e);
+                    return;
+                }
+            }
+        };
+
+        let manually_stopped = self
+            .expected
+            .lock()
+            .map(|mut expected| expected.remove(id))
+            .unwrap_or(false);
+        let stopped = match (container, manually_stopped) {
+            (AnyContainer::Running(c), true) => c.stopped_manually(exit_code),
+            (AnyContainer::Running(c), false) => c.exited(exit_code),
+            (AnyContainer::Paused(c), true) => c.stopped_manually(exit_code),
+            (AnyContainer::Paused(c), false) => c.exited(exit_code),
+            _ => return,
+        };
+        if let Err(e) = self.store.save_stopped(&stopped).await {
+            error!("Failed to save stopped state for {}: {}", id, e);
+            return;
+        }
+
+        self.notice_sender(id).send_modify(|notice| {
+            notice.exits += 1;
+            notice.exit_code = Some(exit_code);
+        });
+        // Nobody listening (no restart manager) is fine.
+        let _ = self.events.send(ExitEvent {
+            container_id: id.to_string(),
+            exit_code,
+            manually_stopped,
+        });
+    }
+}
+
+/// Docker reports processes killed by a signal as `128 + signal`.
+pub(crate) fn exit_code(status: std::process::ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return 128 + signal;
+        }
+    }
+    status.code().unwrap_or(-1)
+}
@@ -0,0 +1,113 @@
+//! Processes of running containers, as listed by `ps`.
+//!
+//! The container's processes are those in its own PID namespace, which also
+//! covers exec'd processes (they are not descendants of the main process).
+//! Runtimes without a PID namespace fall back to the container's cgroup, and
+//! then to the main process tree. As in Docker, the host's `ps` does the
+//! formatting, so any `ps_args` work; its rows are then filtered by PID.
+
+use crate::{stats, RuntimeError};
+use furukawa_common::diagnostic::Error;
+use std::collections::HashSet;
+use std::path::Path;
+use std::process::Command;
+
+/// Docker's default `ps_args`.
+pub const DEFAULT_PS_ARGS: &str = "-ef";
+
+/// `ps` output restricted to a container's processes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Top {
+    pub titles: Vec<String>,
+    pub processes: Vec<Vec<String>>,
+}
+
+/// PIDs (host view) of the container whose main process is `pid`, with
+/// `cgroup` its cgroup directory if it has one. Blocking file I/O.
+pub fn container_pids(pid: u32, cgroup: Option<&Path>) -> Vec<u32> {
+    let namespace = |p: &str| std::fs::read_link(format!("/proc/{}/ns/pid", p)).ok();
+    if let Some(ns) = namespace(&pid.to_string()).filter(|ns| Some(ns) != namespace("self").as_ref()) {
+        let Ok(entries) = std::fs::read_dir("/proc") else {
+            return Vec::new();
+        };
+        let mut pids: Vec<u32> = entries
+            .flatten()
+            .filter_map(|e| e.file_name().to_str()?.parse::<u32>().ok())
+            .filter(|p| namespace(&p.to_string()).as_ref() == Some(&ns))
+            .collect();
+        pids.sort_unstable();
+        return pids;
+    }
+
+    match cgroup.and_then(|c| std::fs::read_to_string(c.join("cgroup.procs")).ok()) {
+        Some(procs) => procs.lines().filter_map(|l| l.trim().parse().ok()).collect(),
+        None => stats::process_tree(pid),
+    }
+}
+
+/// Runs `ps` with `ps_args` (whitespace-separated) and keeps the rows of
+/// `pids`. Blocking.
+pub fn ps(ps_args: &str, pids: &[u32]) -> Result<Top, Error> {
+    let ps_args = if ps_args.trim().is_empty() { DEFAULT_PS_ARGS } else { ps_args };
+    let output = Command::new("ps")
+        .args(ps_args.split_whitespace())
+        .output()
+        .map_err(|e| Error::new(RuntimeError::PsFailed(e.to_string())))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::new(RuntimeError::PsFailed(format!("{}: {}", output.status, stderr.trim()))));
+    }
+    parse_ps(&String::from_utf8_lossy(&output.stdout), pids)
+}
+
+/// Splits `ps` output into titles and the rows whose PID is in `pids`. The
+/// last column (the command) may contain spaces and takes the rest of the
+/// line.
+fn parse_ps(output: &str, pids: &[u32]) -> Result<Top, Error> {
+    let mut lines = output.lines();
+    let titles: Vec<String> = lines.next().unwrap_or_default().split_whitespace().map(str::to_string).collect();
+    let pid_column = titles
+        .iter()
+        .position(|t| t == "PID")
+        .ok_or_else(|| Error::new(RuntimeError::PsFailed("couldn't find PID field in ps output".to_string())))?;
+
+    let wanted: HashSet<u32> = pids.iter().copied().collect();
+    let mut processes = Vec::new();
+    for line in lines.filter(|l| !l.trim().is_empty()) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let pid = fields.get(pid_column).copied().unwrap_or_default();
+        let pid: u32 = pid
+            .parse()
+            .map_err(|_| Error::new(RuntimeError::PsFailed(format!("unexpected pid '{}'", pid))))?;
+        if !wanted.contains(&pid) {
+            continue;
+        }
+
+        let split = fields.len().min(titles.len().saturating_sub(1));
+        let mut row: Vec<String> = fields[..split].iter().map(|f| f.to_string()).collect();
+        row.push(fields[split..].join(" "));
+        processes.push(row);
+    }
+    Ok(Top { titles, processes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ps() {
+        let output = "UID          PID    PPID  C STIME TTY          TIME CMD\n\
+                      root           1       0  0 08:00 ?        00:00:01 /sbin/init\n\
+                      root        4242       1  0 08:01 pts/0    00:00:00 sh -c while true; do sleep 1; done\n\
+                      root        4243    4242  0 08:01 pts/0    00:00:00 sleep 1\n";
+        let top = parse_ps(output, &[4242, 4243]).unwrap();
+        assert_eq!(top.titles, ["UID", "PID", "PPID", "C", "STIME", "TTY", "TIME", "CMD"]);
+        assert_eq!(top.processes.len(), 2);
+        assert_eq!(top.processes[0][7], "sh -c while true; do sleep 1; done");
+        assert_eq!(top.processes[1][1], "4243");
+
+        let err = parse_ps("USER COMMAND\nroot init\n", &[1]).unwrap_err();
+        assert_eq!(err.code(), "RUNTIME_PS_FAILED");
+    }
+}
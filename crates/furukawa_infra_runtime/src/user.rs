@@ -0,0 +1,155 @@
+//! Resolution of Docker `User` specs against a container's own
+//! `/etc/passwd` and `/etc/group`.
+
+use crate::{archive, RuntimeError};
+use furukawa_common::diagnostic::Error;
+use std::path::Path;
+
+/// Default `HOME` for users without a passwd entry.
+const DEFAULT_HOME: &str = "/";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedUser {
+    pub uid: u32,
+    pub gid: u32,
+    /// Supplementary groups the user is listed in.
+    pub additional_gids: Vec<u32>,
+    pub home: String,
+}
+
+/// Resolves `spec` (`user`, `user:group`, `uid` or `uid:gid`, empty for
+/// root) using the files under `root`. Missing files are treated as empty.
+pub fn resolve(root: &Path, spec: &str) -> Result<ResolvedUser, Error> {
+    let passwd = read_in_root(root, "/etc/passwd");
+    let group = read_in_root(root, "/etc/group");
+    resolve_with(&passwd, &group, spec)
+}
+
+/// Reads a file of the container, following symlinks inside `root` only:
+/// an image's `/etc` must not lead to the host's.
+fn read_in_root(root: &Path, path: &str) -> String {
+    archive::resolve(root, &format!("{}/", path))
+        .ok()
+        .and_then(|resolved| std::fs::read_to_string(archive::on_host(root, &resolved)).ok())
+        .unwrap_or_default()
+}
+
+fn resolve_with(passwd: &str, group: &str, spec: &str) -> Result<ResolvedUser, Error> {
+    let unknown = |reason: String| Error::new(RuntimeError::UnknownUser(reason));
+    let (user_part, group_part) = match spec.split_once(':') {
+        Some((user, group)) => (user, Some(group)),
+        None => (spec, None),
+    };
+    let user_part = if user_part.is_empty() { "0" } else { user_part };
+
+    // passwd: name:password:uid:gid:gecos:home:shell
+    let entry = passwd
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .filter(|fields| fields.len() >= 6)
+        .find(|fields| fields[0] == user_part || fields[2] == user_part);
+
+    let (name, mut resolved) = match entry {
+        Some(fields) => (
+            Some(fields[0]),
+            ResolvedUser {
+                uid: fields[2].parse().map_err(|_| unknown(format!("invalid uid for user {}", fields[0])))?,
+                gid: fields[3].parse().map_err(|_| unknown(format!("invalid gid for user {}", fields[0])))?,
+                additional_gids: Vec::new(),
+                home: fields[5].to_string(),
+            },
+        ),
+        // Numeric users do not need to exist.
+        None => match user_part.parse() {
+            Ok(uid) => (
+                None,
+                ResolvedUser {
+                    uid,
+                    gid: 0,
+                    additional_gids: Vec::new(),
+                    home: DEFAULT_HOME.to_string(),
+                },
+            ),
+            Err(_) => {
+                return Err(unknown(format!("unable to find user {}: no matching entries in passwd file", user_part)));
+            }
+        },
+    };
+
+    // group: name:password:gid:member,member
+    let groups: Vec<Vec<&str>> = group
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .filter(|fields| fields.len() >= 3)
+        .collect();
+
+    if let Some(group_part) = group_part.filter(|g| !g.is_empty()) {
+        resolved.gid = match groups.iter().find(|fields| fields[0] == group_part || fields[2] == group_part) {
+            Some(fields) => fields[2].parse().map_err(|_| unknown(format!("invalid gid for group {}", fields[0])))?,
+            None => group_part
+                .parse()
+                .map_err(|_| unknown(format!("unable to find group {}: no matching entries in group file", group_part)))?,
+        };
+    }
+
+    if let Some(name) = name {
+        resolved.additional_gids = groups
+            .iter()
+            .filter(|fields| fields.get(3).is_some_and(|members| members.split(',').any(|m| m == name)))
+            .filter_map(|fields| fields[2].parse().ok())
+            .filter(|gid| *gid != resolved.gid)
+            .collect();
+    }
+
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWD: &str = "root:x:0:0:root:/root:/bin/sh\nnobody:x:65534:65534:nobody:/nonexistent:/bin/false\napp:x:1000:1000::/home/app:/bin/sh\n";
+    const GROUP: &str = "root:x:0:\nwheel:x:10:root,app\napp:x:1000:\nstaff:x:50:\n";
+
+    #[test]
+    fn test_resolve_user_by_name_and_id() {
+        let root = resolve_with(PASSWD, GROUP, "").unwrap();
+        assert_eq!((root.uid, root.gid, root.home.as_str()), (0, 0, "/root"));
+        assert_eq!(root.additional_gids, vec![10]);
+
+        let app = resolve_with(PASSWD, GROUP, "app").unwrap();
+        assert_eq!((app.uid, app.gid), (1000, 1000));
+        assert_eq!(app.additional_gids, vec![10]);
+        assert_eq!(resolve_with(PASSWD, GROUP, "1000").unwrap(), app);
+
+        let unknown_uid = resolve_with(PASSWD, GROUP, "4242").unwrap();
+        assert_eq!((unknown_uid.uid, unknown_uid.gid, unknown_uid.home.as_str()), (4242, 0, "/"));
+    }
+
+    #[test]
+    fn test_resolve_user_with_group() {
+        let app = resolve_with(PASSWD, GROUP, "app:staff").unwrap();
+        assert_eq!((app.uid, app.gid), (1000, 50));
+        let numeric = resolve_with(PASSWD, GROUP, "1000:4242").unwrap();
+        assert_eq!(numeric.gid, 4242);
+
+        assert_eq!(resolve_with(PASSWD, GROUP, "ghost").unwrap_err().code(), "RUNTIME_UNKNOWN_USER");
+        assert_eq!(resolve_with(PASSWD, GROUP, "app:ghosts").unwrap_err().code(), "RUNTIME_UNKNOWN_USER");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_follows_symlinks_inside_root() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir(root.join("conf")).unwrap();
+        std::fs::write(root.join("conf/passwd"), PASSWD).unwrap();
+        std::fs::write(root.join("conf/group.real"), GROUP).unwrap();
+        // On the host these would be /conf and /conf/group.real.
+        std::os::unix::fs::symlink("/conf", root.join("etc")).unwrap();
+        std::os::unix::fs::symlink("/conf/group.real", root.join("conf/group")).unwrap();
+
+        let app = resolve(root, "app:staff").unwrap();
+        assert_eq!((app.uid, app.gid), (1000, 50));
+    }
+}
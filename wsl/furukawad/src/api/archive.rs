@@ -0,0 +1,182 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::Engine;
+use furukawa_common::diagnostic::Error;
+use furukawa_infra_runtime::{archive, user};
+use serde::Deserialize;
+use serde_json::json;
+use tokio_stream::StreamExt;
+use tokio_util::io::{StreamReader, SyncIoBridge};
+use tracing::{error, info};
+use crate::state::AppState;
+use super::{lookup, params, tar_stream};
+
+/// Base64 (URL alphabet) JSON stat of the path, as Docker sends it.
+const PATH_STAT_HEADER: &str = "X-Docker-Container-Path-Stat";
+
+#[derive(Deserialize)]
+pub struct ArchiveQueryParams {
+    #[serde(default)]
+    path: String,
+}
+
+#[derive(Deserialize)]
+pub struct ExtractQueryParams {
+    #[serde(default)]
+    path: String,
+    #[serde(default, rename = "noOverwriteDirNonDir", deserialize_with = "params::flag")]
+    no_overwrite_dir_non_dir: bool,
+    #[serde(default, rename = "copyUIDGID", deserialize_with = "params::flag")]
+    copy_uid_gid: bool,
+}
+
+/// `HEAD /containers/:id/archive`: stats a path in the container.
+pub async fn head(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<ArchiveQueryParams>,
+) -> Response {
+    if params.path.is_empty() {
+        return path_required();
+    }
+    let (id, rootfs) = match lookup::container_rootfs(&state, &id).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+    let path = params.path.clone();
+    match tokio::task::spawn_blocking(move || archive::stat(&rootfs, &path)).await {
+        Ok(Ok(stat)) => (StatusCode::OK, stat_header(&stat)).into_response(),
+        Ok(Err(e)) => error_response(&id, &params.path, e),
+        Err(e) => {
+            error!("Stat task failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// `GET /containers/:id/archive`: a tar archive of a path in the container.
+pub async fn get(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<ArchiveQueryParams>,
+) -> Response {
+    info!(id = %id, path = %params.path, "Received container archive request");
+    if params.path.is_empty() {
+        return path_required();
+    }
+    let (id, rootfs) = match lookup::container_rootfs(&state, &id).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+    // Errors such as a missing path show up here, before the response starts.
+    let stat_rootfs = rootfs.clone();
+    let path = params.path.clone();
+    let stat = match tokio::task::spawn_blocking(move || archive::stat(&stat_rootfs, &path)).await {
+        Ok(Ok(stat)) => stat,
+        Ok(Err(e)) => return error_response(&id, &params.path, e),
+        Err(e) => {
+            error!("Stat task failed: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let mut headers = stat_header(&stat);
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/x-tar"));
+    let path = params.path;
+    let body = tar_stream::body(move |out| {
+        if let Err(e) = archive::archive(&rootfs, &path, out) {
+            error!("Archive of {} in container {} failed: {}", path, id, e);
+        }
+    });
+    (StatusCode::OK, headers, body).into_response()
+}
+
+/// `PUT /containers/:id/archive`: extracts a tar archive into a directory
+/// of the container.
+pub async fn put(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<ExtractQueryParams>,
+    body: Body,
+) -> Response {
+    info!(id = %id, path = %params.path, "Received container extract request");
+    if params.path.is_empty() {
+        return path_required();
+    }
+    let (id, rootfs) = match lookup::container_rootfs(&state, &id).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+    let user = match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => c.config().user.clone().unwrap_or_default(),
+        _ => String::new(),
+    };
+
+    // Extracted as it arrives; archives can be far larger than memory.
+    let input = SyncIoBridge::new(StreamReader::new(body.into_data_stream().map(|chunk| chunk.map_err(std::io::Error::other))));
+    let path = params.path.clone();
+    let extracted = tokio::task::spawn_blocking(move || {
+        // copyUIDGID: files belong to the container's user, not the archive's.
+        let owner = if params.copy_uid_gid {
+            let user = user::resolve(&rootfs, &user)?;
+            Some((user.uid, user.gid))
+        } else {
+            None
+        };
+        let options = archive::ExtractOptions {
+            no_overwrite_dir_non_dir: params.no_overwrite_dir_non_dir,
+            owner,
+        };
+        archive::extract(&rootfs, &path, input, &options)
+    })
+    .await;
+
+    match extracted {
+        Ok(Ok(())) => StatusCode::OK.into_response(),
+        Ok(Err(e)) => error_response(&id, &params.path, e),
+        Err(e) => {
+            error!("Extract task failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+fn path_required() -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "message": "path cannot be empty" }))).into_response()
+}
+
+fn stat_header(stat: &archive::PathStat) -> HeaderMap {
+    let mtime = time::OffsetDateTime::from(stat.mtime)
+        .format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_default();
+    let json = json!({
+        "name": stat.name,
+        "size": stat.size,
+        "mode": stat.mode,
+        "mtime": mtime,
+        "linkTarget": stat.link_target,
+    });
+    let encoded = base64::engine::general_purpose::URL_SAFE.encode(json.to_string());
+    let mut headers = HeaderMap::new();
+    if let Ok(value) = HeaderValue::from_str(&encoded) {
+        headers.insert(PATH_STAT_HEADER, value);
+    }
+    headers
+}
+
+fn error_response(id: &str, path: &str, e: Error) -> Response {
+    let (status, message) = match e.code() {
+        "RUNTIME_PATH_NOT_FOUND" => (StatusCode::NOT_FOUND, format!("Could not find the file {} in container {}", path, id)),
+        "RUNTIME_NOT_A_DIRECTORY" | "RUNTIME_OVERWRITE_CONFLICT" | "RUNTIME_UNKNOWN_USER" => (StatusCode::BAD_REQUEST, e.to_string()),
+        _ => {
+            error!("Archive operation on container {} failed: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        }
+    };
+    (status, Json(json!({ "message": message }))).into_response()
+}
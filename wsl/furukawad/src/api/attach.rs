@@ -0,0 +1,214 @@
+use axum::{
+    extract::{ws::WebSocketUpgrade, Path, Query, Request, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use furukawa_domain::container::AnyContainer;
+use furukawa_infra_runtime::logs::{LogEntry, Stream};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::{broadcast, mpsc, watch};
+use tracing::{error, warn};
+use crate::state::AppState;
+use super::{hijack, lookup, params, stdcopy};
+
+#[derive(Deserialize)]
+pub struct AttachQueryParams {
+    /// Stream live output; without it only `logs` is replayed.
+    #[serde(default, deserialize_with = "params::flag")]
+    stream: bool,
+    #[serde(default, deserialize_with = "params::flag")]
+    stdin: bool,
+    #[serde(default, deserialize_with = "params::flag")]
+    stdout: bool,
+    #[serde(default, deserialize_with = "params::flag")]
+    stderr: bool,
+    /// Replay the container's log first.
+    #[serde(default, deserialize_with = "params::flag")]
+    logs: bool,
+    #[serde(rename = "detachKeys")]
+    detach_keys: Option<String>,
+}
+
+impl AttachQueryParams {
+    fn wants(&self, stream: Stream) -> bool {
+        match stream {
+            Stream::Stdout => self.stdout,
+            Stream::Stderr => self.stderr,
+        }
+    }
+}
+
+/// `POST /containers/:id/attach`, hijacking the connection if asked to.
+pub async fn handle(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<AttachQueryParams>,
+    mut req: Request,
+) -> impl IntoResponse {
+    let upgrade = hijack::take_upgrade(&mut req);
+    match session(&state, &id, query, true).await {
+        Ok(session) => hijack::respond(upgrade, session),
+        Err(response) => response,
+    }
+}
+
+/// `GET /containers/:id/attach/ws`: the same session over a websocket,
+/// with output never multiplexed.
+pub async fn websocket(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<AttachQueryParams>,
+    ws: WebSocketUpgrade,
+) -> impl IntoResponse {
+    match session(&state, &id, query, false).await {
+        Ok(session) => hijack::respond_websocket(ws, session),
+        Err(response) => response,
+    }
+}
+
+/// Connects a client to the container's streams. Output produced while
+/// nobody is attached only goes to the log.
+async fn session(
+    state: &AppState,
+    id: &str,
+    query: AttachQueryParams,
+    multiplexed: bool,
+) -> Result<hijack::Session, Response> {
+    let id = lookup::container_id(state, id).await.map_err(IntoResponse::into_response)?;
+
+    let detach_keys = query.detach_keys.as_deref().filter(|k| !k.is_empty()).unwrap_or(params::DEFAULT_DETACH_KEYS);
+    let detach_keys = params::detach_keys(detach_keys)
+        .map_err(|message| (StatusCode::BAD_REQUEST, Json(json!({ "message": message }))).into_response())?;
+
+    // Subscribe before looking at the state, so that nothing between the
+    // two is missed.
+    let live = state.logs.hub().subscribe(&id);
+    let exits = state.supervisor.notices(&id);
+    let seen = exits.borrow().exits;
+
+    let container = match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return Err((StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such container: {}", id) }))).into_response());
+        }
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    let config = container.config().clone();
+    // A terminal has a single output stream.
+    let multiplexed = multiplexed && !config.tty;
+    let follow = query.stream && !matches!(container, AnyContainer::Stopped(_));
+
+    let (tx, output) = mpsc::channel(64);
+    let output_state = state.clone();
+    let output_id = id.clone();
+    let (replay, attach_stdin) = (query.logs, query.stdin);
+    let wants = move |stream| query.wants(stream);
+    tokio::spawn(async move {
+        let frame = |stream, data: &[u8]| {
+            if multiplexed {
+                stdcopy::frame(stream, data)
+            } else {
+                Bytes::copy_from_slice(data)
+            }
+        };
+        if replay {
+            if let Err(e) = replay_log(&output_state, &output_id, &wants, &frame, &tx).await {
+                error!("Failed to replay logs of {}: {}", output_id, e);
+            }
+        }
+        if follow {
+            forward_output(&output_id, live, exits, seen, &wants, &frame, &tx).await;
+        }
+    });
+
+    let input = (attach_stdin && config.open_stdin).then(|| {
+        let (input, mut rx) = mpsc::channel(16);
+        let state = state.clone();
+        tokio::spawn(async move {
+            while let Some(input) = rx.recv().await {
+                match input {
+                    hijack::Input::Data(data) => {
+                        if let Err(e) = state.logs.hub().write_stdin(&id, &data).await {
+                            warn!("Failed to write to stdin of {}: {}", id, e);
+                            return;
+                        }
+                    }
+                    hijack::Input::Eof => {
+                        if config.stdin_once {
+                            state.logs.hub().close_stdin(&id).await;
+                        }
+                        return;
+                    }
+                }
+            }
+        });
+        input
+    });
+
+    Ok(hijack::Session {
+        output,
+        input,
+        tty: config.tty,
+        detach_keys,
+    })
+}
+
+async fn replay_log(
+    state: &AppState,
+    id: &str,
+    wants: &impl Fn(Stream) -> bool,
+    frame: &impl Fn(Stream, &[u8]) -> Bytes,
+    tx: &mpsc::Sender<Bytes>,
+) -> std::io::Result<()> {
+    let Some(mut reader) = state.logs.reader(id).await? else {
+        return Ok(());
+    };
+    while let Some(LogEntry { log, stream, .. }) = reader.next_entry().await? {
+        if wants(stream) && tx.send(frame(stream, log.as_bytes())).await.is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Forwards live output until the container exits (past the `seen` exits)
+/// or is removed, or the client is gone.
+async fn forward_output(
+    id: &str,
+    mut live: broadcast::Receiver<furukawa_infra_runtime::attach::Output>,
+    mut exits: watch::Receiver<furukawa_infra_runtime::supervisor::ExitNotice>,
+    seen: u64,
+    wants: &impl Fn(Stream) -> bool,
+    frame: &impl Fn(Stream, &[u8]) -> Bytes,
+    tx: &mpsc::Sender<Bytes>,
+) {
+    loop {
+        tokio::select! {
+            chunk = live.recv() => match chunk {
+                Ok(chunk) => {
+                    if wants(chunk.stream) && tx.send(frame(chunk.stream, &chunk.data)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => warn!("Attach client of {} missed {} output chunks", id, n),
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            // Output is published before the exit is recorded.
+            _ = async { exits.wait_for(|notice| notice.removed || notice.exits > seen).await.map(|_| ()) } => {
+                while let Ok(chunk) = live.try_recv() {
+                    if wants(chunk.stream) && tx.send(frame(chunk.stream, &chunk.data)).await.is_err() {
+                        return;
+                    }
+                }
+                return;
+            }
+            _ = tx.closed() => return,
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use axum::{extract::{Query, State}, response::IntoResponse, Json};
-use serde::{Deserialize, Serialize};
+use furukawa_domain::image::{store::ImageMetadata, ImageConfig};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use tracing::info;
 use crate::state::AppState;
 
@@ -93,15 +95,22 @@ pub async fn handle(
 
     // Run the build (stream would be nicer, but sync JSON response for now)
     match furukawa_build::run_build(&build_ctx, output_dir).await {
-        Ok(layer_path) => {
-            info!("Build succeeded, layer at {:?}", layer_path);
-            (
-                axum::http::StatusCode::OK,
-                Json(serde_json::json!({
-                    "stream": format!("Successfully built {}\n", tag),
-                    "tag": tag,
-                })),
-            )
+        Ok(output) => {
+            info!("Build succeeded, layer at {:?}", output.layer_path);
+            match register_image(&state, &tag, &output.layer_path, output.config).await {
+                Ok(id) => (
+                    axum::http::StatusCode::OK,
+                    Json(serde_json::json!({
+                        "stream": format!("Successfully built {}\n", tag),
+                        "tag": tag,
+                        "aux": { "ID": format!("sha256:{}", id) },
+                    })),
+                ),
+                Err(e) => (
+                    axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(serde_json::json!({"error": format!("Failed to register image: {}", e)})),
+                ),
+            }
         }
         Err(e) => (
             axum::http::StatusCode::INTERNAL_SERVER_ERROR,
@@ -109,3 +118,44 @@ pub async fn handle(
         ),
     }
 }
+
+/// Stores the built layer and its config in the image store, tagged `tag`,
+/// and returns the image ID: the digest of its config, as for pulled images.
+async fn register_image(
+    state: &AppState,
+    tag: &str,
+    layer_path: &std::path::Path,
+    config: ImageConfig,
+) -> anyhow::Result<String> {
+    let layer = tokio::fs::read(layer_path).await?;
+    let layer_digest = format!("sha256:{}", hex::encode(Sha256::digest(&layer)));
+    let size = layer.len() as i64;
+    state.image_store.save_layer(&layer_digest, layer.into()).await?;
+
+    let now = time::OffsetDateTime::now_utc();
+    let config_json = serde_json::json!({
+        "architecture": "amd64",
+        "os": "linux",
+        "created": now.format(&time::format_description::well_known::Rfc3339)?,
+        "config": config,
+        "rootfs": { "type": "layers", "diff_ids": [&layer_digest] },
+    });
+    let id = hex::encode(Sha256::digest(serde_json::to_vec(&config_json)?));
+    state.image_store.save_config(&id, config_json).await?;
+
+    // An untagged name means `:latest`, as for pulls
+    let has_tag = tag.rsplit('/').next().is_some_and(|name| name.contains(':'));
+    let repo_tag = if has_tag { tag.to_string() } else { format!("{}:latest", tag) };
+    state
+        .image_metadata_store
+        .save(&ImageMetadata {
+            id: id.clone(),
+            repo_tags: vec![repo_tag],
+            parent_id: None,
+            created: now.unix_timestamp(),
+            size,
+            layers: vec![layer_digest],
+        })
+        .await?;
+    Ok(id)
+}
@@ -0,0 +1,56 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use furukawa_common::diagnostic::Error;
+use furukawa_infra_docker::v1_45::FilesystemChange;
+use furukawa_infra_runtime::rootfs;
+use serde_json::json;
+use tracing::{error, info};
+use crate::state::AppState;
+use super::lookup;
+
+/// `GET /containers/:id/changes`: paths added, modified or deleted since
+/// the container's rootfs was composed from its image.
+pub async fn handle(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    info!(id = %id, "Received container changes request");
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(StatusCode::NOT_FOUND) => {
+            return (StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such container: {}", id) }))).into_response();
+        }
+        Err(status) => return status.into_response(),
+    };
+
+    let container_dir = state.containers_root.join(&id);
+    match tokio::task::spawn_blocking(move || rootfs::changes(&container_dir)).await {
+        Ok(Ok(changes)) => Json(
+            changes
+                .into_iter()
+                .map(|c| FilesystemChange { path: c.path, kind: c.kind as u8 })
+                .collect::<Vec<_>>(),
+        )
+        .into_response(),
+        Ok(Err(e)) if e.code() == "RUNTIME_CHANGES_UNTRACKED" => untracked(&e),
+        Ok(Err(e)) => {
+            error!("Failed to diff container {}: {}", id, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "message": e.to_string() }))).into_response()
+        }
+        Err(e) => {
+            error!("Changes task failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+/// Containers first started before snapshots were kept: expected, and
+/// fixed by recreating them, so a conflict rather than a server error.
+pub fn untracked(e: &Error) -> Response {
+    let message = match e.suggestion() {
+        Some(suggestion) => format!("{}. {}", e, suggestion),
+        None => e.to_string(),
+    };
+    (StatusCode::CONFLICT, Json(json!({ "message": message }))).into_response()
+}
@@ -0,0 +1,225 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use furukawa_domain::container::AnyContainer;
+use furukawa_domain::image::store::ImageMetadata;
+use furukawa_infra_runtime::rootfs;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tracing::{error, info, warn};
+use crate::state::AppState;
+use super::{changes, lookup, params};
+
+/// Query of `POST /commit`. Taken from the raw pairs, as `changes` may be
+/// repeated (once per `--change`).
+struct CommitParams {
+    container: String,
+    repo: String,
+    tag: String,
+    comment: String,
+    author: String,
+    changes: Vec<String>,
+    pause: bool,
+}
+
+impl Default for CommitParams {
+    fn default() -> Self {
+        Self {
+            container: String::new(),
+            repo: String::new(),
+            tag: String::new(),
+            comment: String::new(),
+            author: String::new(),
+            changes: Vec::new(),
+            pause: true,
+        }
+    }
+}
+
+impl CommitParams {
+    fn from_pairs(pairs: Vec<(String, String)>) -> Self {
+        let mut params = Self::default();
+        for (key, value) in pairs {
+            match key.as_str() {
+                "container" => params.container = value,
+                "repo" => params.repo = value,
+                "tag" => params.tag = value,
+                "comment" => params.comment = value,
+                "author" => params.author = value,
+                "changes" => params.changes.push(value),
+                "pause" => params.pause = params::is_true(&value),
+                _ => {}
+            }
+        }
+        params
+    }
+}
+
+/// `POST /commit`: a new image from a container, made of its image's
+/// layers plus one holding the container's changes, with the container's
+/// config (and `changes` applied) as defaults. A running container is
+/// frozen meanwhile unless `pause=0`.
+pub async fn handle(State(state): State<AppState>, Query(pairs): Query<Vec<(String, String)>>) -> Response {
+    let params = CommitParams::from_pairs(pairs);
+    info!(container = %params.container, repo = %params.repo, "Received commit request");
+    let not_found = |message: String| (StatusCode::NOT_FOUND, Json(json!({ "message": message }))).into_response();
+
+    let id = match lookup::container_id(&state, &params.container).await {
+        Ok(id) => id,
+        Err(StatusCode::NOT_FOUND) => return not_found(format!("No such container: {}", params.container)),
+        Err(status) => return status.into_response(),
+    };
+    let container = match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return not_found(format!("No such container: {}", params.container)),
+        Err(e) => return internal_error(format!("Failed to load container: {}", e)),
+    };
+    let config = container.config().clone();
+    let parent = match state.image_metadata_store.get(&config.image).await {
+        Ok(Some(parent)) => parent,
+        Ok(None) => return not_found(format!("No such image: {}", config.image)),
+        Err(e) => return internal_error(format!("Failed to resolve image {}: {}", config.image, e)),
+    };
+
+    let mut image_config = config.image_config();
+    if let Err(e) = furukawa_build::apply_changes(&mut image_config, &params.changes) {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "message": e.to_string() }))).into_response();
+    }
+
+    // A running container is frozen while its files are read, so that the
+    // layer is consistent; it is not recorded as paused. Hosts without a
+    // freezer commit it as it runs.
+    let frozen = match container {
+        AnyContainer::Running(c) if params.pause => match c.pause(state.runtime.as_ref()).await {
+            Ok(paused) => Some(paused),
+            Err(e) if e.code() == "RUNTIME_PAUSE_UNSUPPORTED" => {
+                warn!("Committing container {} without freezing it: {}", id, e);
+                None
+            }
+            Err(e) => return internal_error(format!("Failed to pause container {}: {}", id, e)),
+        },
+        _ => None,
+    };
+    let image_store = state.image_store.clone();
+    let container_dir = state.containers_root.join(&id);
+    let layer = tokio::task::spawn_blocking(move || rootfs::commit_layer(&image_store, &container_dir)).await;
+    if let Some(paused) = frozen {
+        if let Err(e) = paused.unpause(state.runtime.as_ref()).await {
+            error!("Failed to thaw container {} after commit: {}", id, e);
+        }
+    }
+    let (layer_digest, layer_size) = match layer {
+        Ok(Ok(layer)) => layer,
+        Ok(Err(e)) if e.code() == "RUNTIME_CHANGES_UNTRACKED" => return changes::untracked(&e),
+        Ok(Err(e)) => return internal_error(e.to_string()),
+        Err(e) => return internal_error(format!("Commit task failed: {}", e)),
+    };
+
+    // The platform is the parent's
+    let parent_config = state.image_store.load_config(&parent.id).await.ok().flatten().unwrap_or_default();
+    let mut layers = parent.layers.clone();
+    layers.push(layer_digest);
+    let now = time::OffsetDateTime::now_utc();
+    let config_json = json!({
+        "architecture": parent_config.get("architecture").and_then(|v| v.as_str()).unwrap_or("amd64"),
+        "os": parent_config.get("os").and_then(|v| v.as_str()).unwrap_or("linux"),
+        "created": now.format(&time::format_description::well_known::Rfc3339).unwrap_or_default(),
+        "author": params.author,
+        "comment": params.comment,
+        "container": id,
+        "config": image_config,
+        "rootfs": { "type": "layers", "diff_ids": &layers },
+    });
+    let image_id = hex::encode(Sha256::digest(config_json.to_string()));
+    if let Err(e) = state.image_store.save_config(&image_id, config_json).await {
+        return internal_error(format!("Failed to save image config: {}", e));
+    }
+
+    // Without a repository the image is left untagged
+    let repo_tags = if params.repo.is_empty() {
+        Vec::new()
+    } else {
+        let tag = if params.tag.is_empty() { "latest" } else { params.tag.as_str() };
+        vec![format!("{}:{}", params.repo, tag)]
+    };
+    let metadata = ImageMetadata {
+        id: image_id.clone(),
+        repo_tags,
+        parent_id: Some(parent.id),
+        created: now.unix_timestamp(),
+        size: parent.size + layer_size as i64,
+        layers,
+    };
+    if let Err(e) = state.image_metadata_store.save(&metadata).await {
+        return internal_error(format!("Failed to register image: {}", e));
+    }
+
+    info!("Committed container {} as image {}", id, image_id);
+    (StatusCode::CREATED, Json(json!({ "Id": format!("sha256:{}", image_id) }))).into_response()
+}
+
+fn internal_error(message: String) -> Response {
+    error!("Commit failed: {}", message);
+    (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "message": message }))).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use furukawa_domain::container::store::ContainerStore;
+    use furukawa_domain::container::{Config, Container, Running};
+    use furukawa_domain::image::store::{ImageMetadata, ImageMetadataStore};
+    use furukawa_infra_runtime::rootfs;
+    use tower::ServiceExt;
+
+    /// The process runtime cannot freeze, so the default `pause` falls back
+    /// to committing the running container as is.
+    #[tokio::test]
+    async fn test_commit_running_container_without_freezer() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let (state, store) = crate::api::testing::app_state(tmp.path(), None).await;
+
+        let mut base = state.image_store.layer_writer().unwrap();
+        {
+            let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(&mut base, flate2::Compression::default()));
+            let mut header = tar::Header::new_gnu();
+            header.set_size(4);
+            header.set_mode(0o644);
+            tar.append_data(&mut header, "a", &b"base"[..]).unwrap();
+            tar.into_inner().unwrap().finish().unwrap();
+        }
+        let (base_layer, _) = base.commit().unwrap();
+        ImageMetadataStore::save(store.as_ref(), &ImageMetadata {
+            id: "base".to_string(),
+            repo_tags: Vec::new(),
+            parent_id: None,
+            created: 0,
+            size: 4,
+            layers: vec![base_layer.clone()],
+        }).await.unwrap();
+
+        let config = Config { image: "base".to_string(), ..Config::default() };
+        let running = Container::<Running>::restore(
+            "c-0001".to_string(),
+            "dev".to_string(),
+            config.clone(),
+            Running { pid: 4242, started_at: time::OffsetDateTime::now_utc(), process_start: None },
+        );
+        ContainerStore::save(store.as_ref(), &Container::new("c-0001".to_string(), "dev".to_string(), config)).await.unwrap();
+        store.save_running(&running).await.unwrap();
+        let rootfs = rootfs::compose(&state.image_store, &[base_layer], &state.containers_root.join("c-0001")).await.unwrap();
+        std::fs::write(rootfs.join("b"), "configured").unwrap();
+
+        let request = Request::post("/commit?container=dev&repo=snapshot").body(Body::empty()).unwrap();
+        let response = crate::api::router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let image = ImageMetadataStore::get(store.as_ref(), "snapshot:latest").await.unwrap().expect("committed image");
+        assert_eq!(image.layers.len(), 2);
+        assert!(matches!(store.get_any("c-0001").await.unwrap(), Some(furukawa_domain::container::AnyContainer::Running(_))));
+    }
+}
@@ -40,7 +40,7 @@ pub async fn up(
     match furukawa_compose::compose_up(&compose, API_BASE, &project_name).await {
         Ok(started) => (
             axum::http::StatusCode::OK,
-            Json(serde_json::json!({ "started": started })),
+            Json(serde_json::to_value(ComposeUpResponse { started }).unwrap_or_default()),
         ),
         Err(e) => (
             axum::http::StatusCode::INTERNAL_SERVER_ERROR,
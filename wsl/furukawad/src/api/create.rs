@@ -1,8 +1,9 @@
-use axum::{extract::{Query, Json, State}, response::IntoResponse, Json as AxumJson};
+use axum::{extract::{Query, Json, State}, http::StatusCode, response::IntoResponse, Json as AxumJson};
 use furukawa_infra_docker::v1_45::{ContainerConfig, ContainerCreateResponse};
 use serde::Deserialize;
-use tracing::info;
-use furukawa_domain::container::{Container, config::{PortMapping, VolumeMount}};
+use tracing::{error, info};
+use furukawa_domain::image::ImageConfig;
+use furukawa_domain::container::{default_name, validate_name, Container, Healthcheck, LogConfig, Resources, RestartPolicy, config::{parse_cpu_list, PortMapping, VolumeMount}};
 use crate::state::AppState;
 
 use uuid::Uuid;
@@ -25,13 +26,25 @@ pub async fn handle(
         "Received container creation request"
     );
 
-    // 1. Generate ID (Strict UUID v4)
+    // 1. Generate ID (Strict UUID v4) and validate the requested name
     let id = Uuid::new_v4().to_string();
+    let name = match params.name.as_deref() {
+        Some(n) if !n.is_empty() => match validate_name(n) {
+            Ok(n) => n,
+            Err(e) => {
+                return (StatusCode::BAD_REQUEST, AxumJson(serde_json::json!({ "message": e.to_string() }))).into_response();
+            }
+        },
+        _ => default_name(&id),
+    };
 
     // 2. Parse HostConfig: port bindings, volume binds, network mode
     let mut port_mappings = Vec::new();
     let mut volumes = Vec::new();
     let mut network = "bridge".to_string();
+    let mut restart_policy = RestartPolicy::No;
+    let mut log_config = LogConfig::default();
+    let mut resources = Resources::default();
 
     if let Some(host_config) = &body.host_config {
         // Port mappings
@@ -74,28 +87,162 @@ pub async fn handle(
         if let Some(nm) = &host_config.network_mode {
             network = nm.clone();
         }
+
+        // Restart policy
+        if let Some(rp) = &host_config.restart_policy {
+            let name = rp.name.as_deref().unwrap_or("");
+            restart_policy = match RestartPolicy::parse(name, rp.maximum_retry_count.unwrap_or(0)) {
+                Ok(policy) => policy,
+                Err(e) => {
+                    return (StatusCode::BAD_REQUEST, AxumJson(serde_json::json!({ "message": e.to_string() }))).into_response();
+                }
+            };
+        }
+
+        // Logging: json-file with optional rotation
+        if let Some(lc) = &host_config.log_config {
+            let driver = lc.type_.as_deref().unwrap_or("");
+            log_config = match LogConfig::parse(driver, &lc.config.clone().unwrap_or_default()) {
+                Ok(config) => config,
+                Err(e) => {
+                    return (StatusCode::BAD_REQUEST, AxumJson(serde_json::json!({ "message": e.to_string() }))).into_response();
+                }
+            };
+        }
+
+        // Resource limits; Docker's 0 means unset
+        let set = |value: Option<i64>| value.filter(|&v| v != 0);
+        resources = Resources {
+            memory: set(host_config.memory),
+            memory_swap: set(host_config.memory_swap),
+            nano_cpus: set(host_config.nano_cpus),
+            cpu_shares: set(host_config.cpu_shares),
+            cpuset_cpus: host_config.cpuset_cpus.clone().filter(|c| !c.is_empty()),
+            pids_limit: host_config.pids_limit.filter(|&n| n > 0),
+        };
+        if let Err(message) = resources.validate().map_err(|e| e.to_string()).and_then(|_| check_host_cpus(&resources)) {
+            return (StatusCode::BAD_REQUEST, AxumJson(serde_json::json!({ "message": message }))).into_response();
+        }
+    }
+
+    // Limits the host cannot enforce are dropped, with a warning each
+    let capabilities = state.cgroups.as_ref().map(|c| c.capabilities()).unwrap_or_default();
+    let warnings = capabilities.apply(&mut resources);
+
+    // Health check; an empty Test inherits, which means none here
+    let healthcheck = body.healthcheck.as_ref().and_then(|hc| {
+        let test = hc.test.clone().unwrap_or_default();
+        (!test.is_empty()).then(|| Healthcheck {
+            test,
+            interval: hc.interval.unwrap_or(0),
+            timeout: hc.timeout.unwrap_or(0),
+            start_period: hc.start_period.unwrap_or(0),
+            retries: hc.retries.unwrap_or(0),
+        })
+    });
+    if let Some(Err(e)) = healthcheck.as_ref().map(Healthcheck::validate) {
+        return (StatusCode::BAD_REQUEST, AxumJson(serde_json::json!({ "message": e.to_string() }))).into_response();
     }
 
     // 3. Parse Env: ["KEY=VALUE", ...]
     let env = body.env.unwrap_or_default();
 
-    let config = furukawa_domain::container::Config {
+    let mut config = furukawa_domain::container::Config {
         image: body.image.clone(),
+        entrypoint: body.entrypoint.clone(),
         cmd: body.cmd.clone().unwrap_or_default(),
         port_mappings,
         volumes,
         env,
         network,
+        stop_signal: body.stop_signal.clone(),
+        stop_timeout: body.stop_timeout.map(|t| t as i64),
+        restart_policy,
+        log_config,
+        user: body.user.clone().filter(|u| !u.is_empty()),
+        working_dir: body.working_dir.clone().filter(|w| !w.is_empty()),
+        open_stdin: body.open_stdin.unwrap_or(false),
+        stdin_once: body.stdin_once.unwrap_or(false),
+        tty: body.tty.unwrap_or(false),
+        resources,
+        healthcheck,
+        labels: body.labels.clone().unwrap_or_default(),
+    };
+
+    // 4. Image defaults: Entrypoint/Cmd, Env, WorkingDir, User and Labels
+    let image = match image_config(&state, &body.image).await {
+        Ok(image) => image,
+        Err(response) => return response,
     };
-    let container = Container::new(id.clone(), config);
+    config.apply_image_defaults(&image);
+    if config.command().is_empty() {
+        return (StatusCode::BAD_REQUEST, AxumJson(serde_json::json!({ "message": "No command specified" }))).into_response();
+    }
+    if let Some(dir) = config.working_dir.as_deref().filter(|dir| !dir.starts_with('/')) {
+        let message = format!("the working directory '{}' is invalid, it needs to be an absolute path", dir);
+        return (StatusCode::BAD_REQUEST, AxumJson(serde_json::json!({ "message": message }))).into_response();
+    }
+    let container = Container::new(id.clone(), name, config);
     
-    // 4. Persist State (SQLite)
-    state.container_store.save(&container).await.unwrap();
+    // 5. Persist State (SQLite). The name column is unique.
+    if let Err(e) = state.container_store.save(&container).await {
+        let status = if e.code() == "CONTAINER_NAME_CONFLICT" {
+            StatusCode::CONFLICT
+        } else {
+            error!("Failed to save container: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        return (status, AxumJson(serde_json::json!({ "message": e.to_string() }))).into_response();
+    }
 
     let resp = ContainerCreateResponse {
         id,
-        warnings: vec![],
+        warnings,
+    };
+
+    (StatusCode::CREATED, AxumJson(resp)).into_response()
+}
+
+/// The config of the image the container is created from. Images pulled
+/// before configs were kept have none, and give no defaults.
+async fn image_config(state: &AppState, image: &str) -> Result<ImageConfig, axum::response::Response> {
+    let failed = |e: String| {
+        error!("Failed to load image config for {}: {}", image, e);
+        StatusCode::INTERNAL_SERVER_ERROR.into_response()
     };
+    let metadata = match state.image_metadata_store.get(image).await {
+        Ok(Some(metadata)) => metadata,
+        Ok(None) => {
+            let message = format!("No such image: {}", image);
+            return Err((StatusCode::NOT_FOUND, AxumJson(serde_json::json!({ "message": message }))).into_response());
+        }
+        Err(e) => return Err(failed(e.to_string())),
+    };
+    match state.image_store.load_config(&metadata.id).await {
+        Ok(Some(json)) => Ok(json
+            .get("config")
+            .cloned()
+            .and_then(|config| serde_json::from_value(config).ok())
+            .unwrap_or_default()),
+        Ok(None) => Ok(ImageConfig::default()),
+        Err(e) => Err(failed(e.to_string())),
+    }
+}
 
-    (axum::http::StatusCode::CREATED, AxumJson(resp))
+/// Checks the CPU limits against the CPUs of this host, with Docker's
+/// messages.
+fn check_host_cpus(resources: &Resources) -> Result<(), String> {
+    let cpus = std::thread::available_parallelism().map(|n| n.get() as i64).unwrap_or(1);
+    if resources.nano_cpus.is_some_and(|n| n < 10_000_000 || n > cpus * 1_000_000_000) {
+        return Err(format!(
+            "Range of CPUs is from 0.01 to {}.00, as there are only {} CPUs available",
+            cpus, cpus
+        ));
+    }
+    if let Some(cpuset) = &resources.cpuset_cpus {
+        if parse_cpu_list(cpuset).is_some_and(|list| list.iter().any(|&cpu| i64::from(cpu) >= cpus)) {
+            return Err(format!("Requested CPUs are not available - requested {}, available: 0-{}", cpuset, cpus - 1));
+        }
+    }
+    Ok(())
 }
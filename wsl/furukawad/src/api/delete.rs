@@ -1,46 +1,92 @@
-use axum::{extract::{Path, State}, response::IntoResponse, http::StatusCode};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use furukawa_domain::container::runtime::StopOptions;
+use furukawa_domain::container::AnyContainer;
+use serde::Deserialize;
+use serde_json::json;
 use tracing::{info, error};
 use crate::state::AppState;
+use super::{lookup, params};
+
+#[derive(Deserialize)]
+pub struct DeleteQueryParams {
+    /// Kill the container first if it is running or paused.
+    #[serde(default, deserialize_with = "params::flag")]
+    force: bool,
+}
 
 pub async fn handle(
     State(state): State<AppState>,
     Path(id): Path<String>,
-) -> impl IntoResponse {
+    Query(params): Query<DeleteQueryParams>,
+) -> Response {
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status.into_response(),
+    };
+
     // 1. Check existence and state
-    let status = match state.container_store.get_status(&id).await {
-        Ok(Some(s)) => s,
-        Ok(None) => return StatusCode::NOT_FOUND,
+    let container = match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
         Err(e) => {
-            error!("Failed to check container status: {}", e);
-            return StatusCode::INTERNAL_SERVER_ERROR;
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
         }
     };
 
-    // 2. Enforce "Stopped" state constraint (Strict Mode)
-    // Docker Allows removing "created" containers too.
-    if status == "running" {
-        error!(id = %id, "Attempted to remove running container");
-        return StatusCode::CONFLICT; 
+    // 2. Enforce "Stopped" state constraint unless forced, as Docker does.
+    // Docker allows removing "created" containers too.
+    let kill = StopOptions {
+        signal: Some("SIGKILL".to_string()),
+        timeout: None,
+    };
+    let killed = match container {
+        AnyContainer::Running(_) if !params.force => {
+            let message = format!("You cannot remove a running container {}. Stop the container before attempting removal or force remove", id);
+            return conflict(message);
+        }
+        AnyContainer::Paused(_) if !params.force => {
+            let message = format!("You cannot remove a paused container {}. Unpause and then stop the container before attempting removal or force remove", id);
+            return conflict(message);
+        }
+        AnyContainer::Running(c) => c.stop(state.runtime.as_ref(), &kill).await.map(|_| ()),
+        AnyContainer::Paused(c) => c.stop(state.runtime.as_ref(), &kill).await.map(|_| ()),
+        AnyContainer::Created(_) | AnyContainer::Stopped(_) => Ok(()),
+    };
+    if let Err(e) = killed {
+        error!("Failed to kill container {} for removal: {}", id, e);
+        return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "message": e.to_string() }))).into_response();
     }
 
     // 3. Delete from DB
     if let Err(e) = state.container_store.delete(&id).await {
         error!("Failed to delete container from DB: {}", e);
-        return StatusCode::INTERNAL_SERVER_ERROR;
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    state.supervisor.forget(&id);
+    state.execs.forget_container(&id);
+    state.logs.hub().remove(&id);
+    if let Some(cgroups) = &state.cgroups {
+        cgroups.remove(&id);
     }
 
     // 4. Cleanup Logs (Best effort)
-    let log_path = std::path::Path::new("furukawa_logs").join(format!("{}.log", id));
-    if log_path.exists() {
-        if let Err(e) = tokio::fs::remove_file(&log_path).await {
-            error!("Failed to remove log file for {}: {}", id, e);
-            // We don't fail the request, just log error
-        } else {
-            info!("Removed log file for {}", id);
-        }
+    if let Err(e) = state.logs.remove(&id) {
+        error!("Failed to remove logs for {}: {}", id, e);
     }
 
     info!(id = %id, "Container removed");
-    
-    StatusCode::NO_CONTENT
+
+    StatusCode::NO_CONTENT.into_response()
+}
+
+fn conflict(message: String) -> Response {
+    error!("{}", message);
+    (StatusCode::CONFLICT, Json(json!({ "message": message }))).into_response()
 }
@@ -0,0 +1,214 @@
+use axum::{
+    body::to_bytes,
+    extract::{Path, Query, Request, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use furukawa_common::diagnostic::Error;
+use furukawa_infra_docker::v1_45::{ExecConfig, ExecCreateResponse, ExecInspectResponse, ExecProcessConfig, ExecStartConfig};
+use furukawa_infra_runtime::exec::ExecIo;
+use furukawa_infra_runtime::logs::Stream;
+use serde_json::json;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::mpsc;
+use tracing::{error, info};
+use crate::state::AppState;
+use super::{hijack, lookup, params, stdcopy};
+use super::resize::ResizeQueryParams;
+
+/// Largest `/exec/:id/start` body accepted; it only holds a few flags.
+const START_BODY_LIMIT: usize = 64 * 1024;
+
+fn error_response(e: &Error) -> Response {
+    let status = match e.code() {
+        "EXEC_NOT_FOUND" => StatusCode::NOT_FOUND,
+        "EXEC_ALREADY_STARTED" | "EXEC_CONTAINER_NOT_RUNNING" | "EXEC_CONTAINER_PAUSED" => StatusCode::CONFLICT,
+        "EXEC_INVALID_CONFIG" => StatusCode::BAD_REQUEST,
+        _ => {
+            error!("Exec failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    };
+    (status, Json(json!({ "message": e.to_string() }))).into_response()
+}
+
+pub async fn create(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(body): Json<ExecConfig>,
+) -> impl IntoResponse {
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status.into_response(),
+    };
+
+    if let Some(keys) = body.detach_keys.as_deref().filter(|k| !k.is_empty()) {
+        if let Err(message) = params::detach_keys(keys) {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "message": message }))).into_response();
+        }
+    }
+
+    let config = furukawa_infra_runtime::ExecConfig {
+        cmd: body.cmd.unwrap_or_default(),
+        env: body.env.unwrap_or_default(),
+        user: body.user.filter(|u| !u.is_empty()),
+        working_dir: body.working_dir.filter(|w| !w.is_empty()),
+        tty: body.tty.unwrap_or(false),
+        attach_stdin: body.attach_stdin.unwrap_or(false),
+        attach_stdout: body.attach_stdout.unwrap_or(false),
+        attach_stderr: body.attach_stderr.unwrap_or(false),
+        privileged: body.privileged.unwrap_or(false),
+        detach_keys: body.detach_keys,
+    };
+
+    match state.execs.create(&id, config).await {
+        Ok(exec_id) => {
+            info!(container = %id, exec = %exec_id, "Exec created");
+            (StatusCode::CREATED, Json(ExecCreateResponse { id: exec_id })).into_response()
+        }
+        Err(e) => error_response(&e),
+    }
+}
+
+pub async fn start(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    mut req: Request,
+) -> impl IntoResponse {
+    let upgrade = hijack::take_upgrade(&mut req);
+    let body = match to_bytes(req.into_body(), START_BODY_LIMIT).await {
+        Ok(body) => body,
+        Err(_) => return StatusCode::BAD_REQUEST.into_response(),
+    };
+    let start_config: ExecStartConfig = match parse_body(&body) {
+        Ok(config) => config,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "message": e.to_string() }))).into_response();
+        }
+    };
+
+    let Some(exec) = state.execs.get(&id) else {
+        return (StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such exec instance: {}", id) }))).into_response();
+    };
+    let io = match state.execs.start(&id).await {
+        Ok(io) => io,
+        Err(e) => return error_response(&e),
+    };
+    if let Some([height, width]) = start_config.console_size.as_deref() {
+        if let Err(e) = state.execs.resize(&id, *height, *width) {
+            error!("Failed to size exec TTY: {}", e);
+        }
+    }
+
+    let detach_keys = exec.config.detach_keys.as_deref().filter(|k| !k.is_empty()).unwrap_or(params::DEFAULT_DETACH_KEYS);
+    let detach_keys = params::detach_keys(detach_keys).unwrap_or_default();
+    let session = session(&state, &id, io, exec.config.tty, detach_keys);
+    if start_config.detach.unwrap_or(false) {
+        // Keep reading so the process never blocks on a full pipe.
+        let mut output = session.output;
+        tokio::spawn(async move { while output.recv().await.is_some() {} });
+        return StatusCode::OK.into_response();
+    }
+    hijack::respond(upgrade, session)
+}
+
+/// An empty body means all defaults.
+fn parse_body(body: &Bytes) -> serde_json::Result<ExecStartConfig> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        Ok(ExecStartConfig::default())
+    } else {
+        serde_json::from_slice(body)
+    }
+}
+
+/// Connects the exec's streams to a hijack session. The output ends only
+/// once the exit code is recorded, so clients can inspect it right away.
+fn session(state: &AppState, id: &str, io: ExecIo, tty: bool, detach_keys: Vec<u8>) -> hijack::Session {
+    let (tx, output) = mpsc::channel(64);
+    let execs = state.execs.clone();
+    let exec_id = id.to_string();
+
+    let input = match io {
+        ExecIo::Pipes { stdin, stdout, stderr } => {
+            tokio::spawn(async move {
+                tokio::join!(pump(stdout, Some(Stream::Stdout), &tx), pump(stderr, Some(Stream::Stderr), &tx));
+                execs.wait(&exec_id).await;
+            });
+            stdin.map(hijack::forward_input)
+        }
+        #[cfg(unix)]
+        ExecIo::Pty(master) => {
+            let output = master.clone();
+            tokio::spawn(async move {
+                pump(Some(output), None, &tx).await;
+                execs.wait(&exec_id).await;
+            });
+            Some(hijack::forward_input(master))
+        }
+    };
+
+    hijack::Session { output, input, tty, detach_keys }
+}
+
+/// Forwards a stream of the process, framed as `stream` or raw if `None`.
+/// Output is still read (and dropped) after the client has gone away.
+async fn pump<R: AsyncRead + Unpin>(reader: Option<R>, stream: Option<Stream>, tx: &mpsc::Sender<Bytes>) {
+    let Some(mut reader) = reader else {
+        return;
+    };
+    let mut buf = vec![0u8; 32 * 1024];
+    loop {
+        let n = match reader.read(&mut buf).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => n,
+        };
+        let chunk = match stream {
+            Some(stream) => stdcopy::frame(stream, &buf[..n]),
+            None => Bytes::copy_from_slice(&buf[..n]),
+        };
+        let _ = tx.send(chunk).await;
+    }
+}
+
+pub async fn inspect(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let Some(exec) = state.execs.get(&id) else {
+        return (StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such exec instance: {}", id) }))).into_response();
+    };
+    let config = exec.config;
+    let resp = ExecInspectResponse {
+        can_remove: false,
+        container_id: exec.container_id,
+        detach_keys: config.detach_keys.unwrap_or_default(),
+        exit_code: exec.exit_code,
+        id: exec.id,
+        open_stderr: config.attach_stderr,
+        open_stdin: config.attach_stdin,
+        open_stdout: config.attach_stdout,
+        process_config: ExecProcessConfig {
+            arguments: config.cmd[1..].to_vec(),
+            entrypoint: config.cmd[0].clone(),
+            privileged: config.privileged,
+            tty: config.tty,
+            user: config.user.unwrap_or_default(),
+        },
+        running: exec.running,
+        pid: exec.pid,
+    };
+    (StatusCode::OK, Json(resp)).into_response()
+}
+
+pub async fn resize(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<ResizeQueryParams>,
+) -> impl IntoResponse {
+    match state.execs.resize(&id, params.h, params.w) {
+        Ok(()) => StatusCode::OK.into_response(),
+        Err(e) => error_response(&e),
+    }
+}
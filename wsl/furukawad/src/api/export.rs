@@ -0,0 +1,29 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use furukawa_infra_runtime::archive;
+use tracing::{error, info};
+use crate::state::AppState;
+use super::{lookup, tar_stream};
+
+/// `GET /containers/:id/export`: the container's whole rootfs as a tar
+/// stream. Containers that never started export their image's files.
+pub async fn handle(State(state): State<AppState>, Path(id): Path<String>) -> Response {
+    info!(id = %id, "Received container export request");
+    let (id, rootfs) = match lookup::container_rootfs(&state, &id).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+
+    let body = tar_stream::body(move |out| {
+        if let Err(e) = archive::export(&rootfs, out) {
+            error!("Export of container {} failed: {}", id, e);
+        }
+    });
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-tar")
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
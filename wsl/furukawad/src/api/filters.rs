@@ -0,0 +1,89 @@
+//! Docker's `filters` query parameter: a JSON object mapping each filter to
+//! its values, e.g. `{"status":["running"],"label":["app=web"]}`. Older
+//! clients send `{"status":{"running":true}}`, which is accepted as well.
+//!
+//! Values of one filter match if any of them does; different filters must
+//! all match (except `label`, where every value must).
+
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Default)]
+pub struct Filters(BTreeMap<String, Vec<String>>);
+
+impl Filters {
+    /// Parses the raw parameter; missing or empty means no filters.
+    pub fn parse(raw: Option<&str>) -> Result<Self, String> {
+        let raw = raw.unwrap_or_default().trim();
+        if raw.is_empty() {
+            return Ok(Self::default());
+        }
+
+        let invalid = |e: &dyn std::fmt::Display| format!("invalid filter: {}", e);
+        let Value::Object(object) = serde_json::from_str::<Value>(raw).map_err(|e| invalid(&e))? else {
+            return Err(invalid(&"expected a JSON object"));
+        };
+        let mut filters = BTreeMap::new();
+        for (key, values) in object {
+            let values = match values {
+                Value::Array(values) => values
+                    .into_iter()
+                    .map(|v| match v {
+                        Value::String(s) => Ok(s),
+                        other => Err(invalid(&format!("unexpected value {} for '{}'", other, key))),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?,
+                Value::Object(values) => values.into_iter().filter(|(_, on)| on == &Value::Bool(true)).map(|(v, _)| v).collect(),
+                other => return Err(invalid(&format!("unexpected value {} for '{}'", other, key))),
+            };
+            filters.insert(key, values);
+        }
+        Ok(Self(filters))
+    }
+
+    /// Rejects filters other than `accepted`, as Docker does.
+    pub fn validate(&self, accepted: &[&str]) -> Result<(), String> {
+        match self.0.keys().find(|key| !accepted.contains(&key.as_str())) {
+            Some(key) => Err(format!("invalid filter '{}'", key)),
+            None => Ok(()),
+        }
+    }
+
+    /// Values of `key`; empty if the filter is not set.
+    pub fn get(&self, key: &str) -> &[String] {
+        self.0.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Whether `labels` satisfy every `label` filter, each either `key` (the
+    /// label is set) or `key=value`.
+    pub fn match_labels(&self, labels: &HashMap<String, String>) -> bool {
+        self.get("label").iter().all(|filter| match filter.split_once('=') {
+            Some((key, value)) => labels.get(key).is_some_and(|v| v == value),
+            None => labels.contains_key(filter),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_filters() {
+        let filters = Filters::parse(Some(r#"{"status":["running","paused"],"label":{"app=web":true,"x":false}}"#)).unwrap();
+        assert_eq!(filters.get("status"), ["running", "paused"]);
+        assert_eq!(filters.get("label"), ["app=web"]);
+        assert!(filters.get("name").is_empty());
+        assert!(filters.validate(&["status", "label"]).is_ok());
+        assert_eq!(filters.validate(&["status"]).unwrap_err(), "invalid filter 'label'");
+
+        let labels = HashMap::from([("app".to_string(), "web".to_string()), ("tier".to_string(), "1".to_string())]);
+        assert!(filters.match_labels(&labels));
+        let filters = Filters::parse(Some(r#"{"label":["app","tier=2"]}"#)).unwrap();
+        assert!(!filters.match_labels(&labels));
+
+        assert!(Filters::parse(Some("")).unwrap().get("status").is_empty());
+        assert!(Filters::parse(Some("[1]")).is_err());
+        assert!(Filters::parse(Some(r#"{"status":[1]}"#)).is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Docker's connection hijacking for interactive endpoints (attach, exec
+//! start).
+//!
+//! Clients that send `Upgrade: tcp` get `101 UPGRADED`; the connection then
+//! carries the client's stdin one way and the session's output the other.
+//! Other clients get the output as a plain streamed response. The websocket
+//! variant of attach carries the same streams in websocket frames.
+
+use axum::{
+    body::Body,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Request,
+    },
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use bytes::Bytes;
+use hyper::upgrade::OnUpgrade;
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tracing::{debug, error, info};
+use super::stdcopy;
+
+/// Client input for a session.
+pub enum Input {
+    Data(Bytes),
+    /// The client closed its stdin. Detaching or disconnecting just drops
+    /// the channel instead.
+    Eof,
+}
+
+/// Both ends of an interactive session.
+pub struct Session {
+    /// Output chunks, already framed unless the session has a TTY. The
+    /// connection is closed once every sender is gone.
+    pub output: mpsc::Receiver<Bytes>,
+    /// Where client input goes; `None` ignores it.
+    pub input: Option<mpsc::Sender<Input>>,
+    pub tty: bool,
+    /// Input sequence that ends the session without closing stdin.
+    pub detach_keys: Vec<u8>,
+}
+
+/// Copies session input into `writer`, closing it at the client's EOF.
+pub fn forward_input<W>(mut writer: W) -> mpsc::Sender<Input>
+where
+    W: AsyncWrite + Send + Unpin + 'static,
+{
+    let (tx, mut rx) = mpsc::channel(16);
+    tokio::spawn(async move {
+        while let Some(input) = rx.recv().await {
+            match input {
+                Input::Data(data) => {
+                    if writer.write_all(&data).await.is_err() || writer.flush().await.is_err() {
+                        break;
+                    }
+                }
+                Input::Eof => break,
+            }
+        }
+        let _ = writer.shutdown().await;
+    });
+    tx
+}
+
+/// Takes the pending upgrade out of the request if the client asked to
+/// hijack the connection.
+pub fn take_upgrade(req: &mut Request) -> Option<OnUpgrade> {
+    let wants_upgrade = req
+        .headers()
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("tcp"));
+    if wants_upgrade {
+        req.extensions_mut().remove::<OnUpgrade>()
+    } else {
+        None
+    }
+}
+
+/// Responds with `101` and serves the session on the upgraded connection,
+/// or streams its output if there is nothing to upgrade.
+pub fn respond(upgrade: Option<OnUpgrade>, session: Session) -> Response {
+    let content_type = if session.tty {
+        stdcopy::RAW_STREAM
+    } else {
+        stdcopy::MULTIPLEXED_STREAM
+    };
+
+    let Some(upgrade) = upgrade else {
+        let body = Body::from_stream(ReceiverStream::new(session.output).map(Ok::<_, Infallible>));
+        return Response::builder()
+            .header(header::CONTENT_TYPE, content_type)
+            .body(body)
+            .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response());
+    };
+
+    tokio::spawn(async move {
+        match upgrade.await {
+            Ok(upgraded) => serve_raw(TokioIo::new(upgraded), session).await,
+            Err(e) => error!("Failed to hijack connection: {}", e),
+        }
+    });
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(header::CONNECTION, "Upgrade")
+        .header(header::UPGRADE, "tcp")
+        .header(header::CONTENT_TYPE, content_type)
+        .body(Body::empty())
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Serves the session over the websocket once the handshake completes:
+/// output as binary messages, input from any data message.
+pub fn respond_websocket(ws: WebSocketUpgrade, session: Session) -> Response {
+    ws.on_upgrade(move |socket| serve_websocket(socket, session))
+}
+
+async fn serve_raw<S>(conn: S, session: Session)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (mut reader, mut writer) = tokio::io::split(conn);
+    let Session { mut output, input, detach_keys, .. } = session;
+
+    let mut input_task = tokio::spawn(async move {
+        let Some(input) = input else {
+            return std::future::pending().await;
+        };
+        let mut detach = DetachMatcher::new(detach_keys);
+        let mut buf = vec![0u8; 32 * 1024];
+        loop {
+            let n = match reader.read(&mut buf).await {
+                // The client half-closes its side once its stdin ends.
+                Ok(0) => {
+                    let _ = input.send(Input::Eof).await;
+                    return std::future::pending().await;
+                }
+                Ok(n) => n,
+                Err(e) => {
+                    debug!("Hijacked stdin ended: {}", e);
+                    return;
+                }
+            };
+            let (data, detached) = detach.feed(&buf[..n]);
+            if !data.is_empty() && input.send(Input::Data(data.into())).await.is_err() {
+                return std::future::pending().await;
+            }
+            if detached {
+                info!("Client detached");
+                return;
+            }
+        }
+    });
+
+    loop {
+        tokio::select! {
+            chunk = output.recv() => match chunk {
+                Some(chunk) if writer.write_all(&chunk).await.is_ok() => {}
+                _ => break,
+            },
+            // Detached or gone.
+            _ = &mut input_task => break,
+        }
+    }
+    let _ = writer.shutdown().await;
+    input_task.abort();
+}
+
+async fn serve_websocket(mut socket: WebSocket, session: Session) {
+    let Session { mut output, input, detach_keys, .. } = session;
+    let mut detach = DetachMatcher::new(detach_keys);
+
+    loop {
+        tokio::select! {
+            chunk = output.recv() => {
+                let Some(chunk) = chunk else { break };
+                if socket.send(Message::Binary(chunk.to_vec())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => {
+                // Pings are answered by the websocket itself.
+                let payload = match message {
+                    Some(Ok(Message::Binary(data))) => data,
+                    Some(Ok(Message::Text(text))) => text.into_bytes(),
+                    Some(Ok(Message::Ping(_) | Message::Pong(_))) => continue,
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Err(e)) => {
+                        debug!("Websocket input ended: {}", e);
+                        break;
+                    }
+                };
+                let Some(input) = &input else { continue };
+                let (data, detached) = detach.feed(&payload);
+                if !data.is_empty() && input.send(Input::Data(data.into())).await.is_err() {
+                    continue;
+                }
+                if detached {
+                    break;
+                }
+            }
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+/// Watches input for the detach key sequence. Bytes that could be the start
+/// of the sequence are held back until it is clear they are not.
+struct DetachMatcher {
+    keys: Vec<u8>,
+    matched: usize,
+}
+
+impl DetachMatcher {
+    fn new(keys: Vec<u8>) -> Self {
+        Self { keys, matched: 0 }
+    }
+
+    /// Returns the input to pass on and whether the sequence was completed.
+    fn feed(&mut self, data: &[u8]) -> (Vec<u8>, bool) {
+        let mut out = Vec::with_capacity(data.len());
+        for &byte in data {
+            if self.keys.is_empty() {
+                out.push(byte);
+                continue;
+            }
+            if byte == self.keys[self.matched] {
+                self.matched += 1;
+                if self.matched == self.keys.len() {
+                    self.matched = 0;
+                    return (out, true);
+                }
+                continue;
+            }
+            out.extend_from_slice(&self.keys[..self.matched]);
+            self.matched = usize::from(byte == self.keys[0]);
+            if self.matched == 0 {
+                out.push(byte);
+            }
+        }
+        (out, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::params;
+
+    #[test]
+    fn test_detach_keys() {
+        let keys = params::detach_keys(params::DEFAULT_DETACH_KEYS).unwrap();
+        assert_eq!(keys, vec![16, 17]);
+        assert_eq!(params::detach_keys("ctrl-a,x,ctrl-@").unwrap(), vec![1, b'x', 0]);
+        assert!(params::detach_keys("ctrl-1").is_err());
+        assert!(params::detach_keys("ab").is_err());
+
+        let mut detach = DetachMatcher::new(keys);
+        assert_eq!(detach.feed(b"ls\n"), (b"ls\n".to_vec(), false));
+        // A lone ctrl-p is passed on once the next byte shows it was not a detach.
+        assert_eq!(detach.feed(b"a\x10"), (b"a".to_vec(), false));
+        assert_eq!(detach.feed(b"b"), (b"\x10b".to_vec(), false));
+        assert_eq!(detach.feed(b"x\x10\x10"), (b"x\x10".to_vec(), false));
+        assert_eq!(detach.feed(b"\x11rest"), (Vec::new(), true));
+    }
+}
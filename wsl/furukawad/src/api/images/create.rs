@@ -35,7 +35,7 @@ pub async fn handle(
     let manifest: ManifestV2 = if let Ok(list) = serde_json::from_slice::<furukawa_infra_registry::manifest::ManifestList>(&manifest_bytes) {
         tracing::info!("Received Manifest List. Searching for linux/amd64...");
         let target_digest = list.manifests.iter().find(|m| {
-            m.platform.as_ref().map_or(false, |p| p.os == "linux" && p.architecture == "amd64")
+            m.platform.as_ref().is_some_and(|p| p.os == "linux" && p.architecture == "amd64")
         }).map(|m| &m.digest);
 
         if let Some(digest) = target_digest {
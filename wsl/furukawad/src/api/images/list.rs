@@ -1,12 +1,38 @@
-use axum::extract::State;
+use axum::extract::{Query, State};
 use axum::response::IntoResponse;
 use axum::Json;
+use serde::Deserialize;
+use crate::api::filters::Filters;
 use crate::state::AppState;
+use furukawa_domain::image::ImageConfig;
 use furukawa_infra_docker::v1_45::ImageSummary;
 
+/// Filters of `GET /images/json`.
+const FILTERS: &[&str] = &["dangling", "label", "reference"];
+
+#[derive(Deserialize)]
+pub struct ListImagesParams {
+    filters: Option<String>,
+}
+
 pub async fn handle(
     State(state): State<AppState>,
+    Query(params): Query<ListImagesParams>,
 ) -> impl IntoResponse {
+    let filters = match Filters::parse(params.filters.as_deref()).and_then(|f| f.validate(FILTERS).map(|_| f)) {
+        Ok(filters) => filters,
+        Err(message) => return (axum::http::StatusCode::BAD_REQUEST, Json(serde_json::json!({ "message": message }))).into_response(),
+    };
+    let dangling = match filters.get("dangling").last().map(String::as_str) {
+        None => None,
+        Some("true" | "1") => Some(true),
+        Some("false" | "0") => Some(false),
+        Some(value) => {
+            let message = format!("Invalid filter 'dangling=[{}]'", value);
+            return (axum::http::StatusCode::BAD_REQUEST, Json(serde_json::json!({ "message": message }))).into_response();
+        }
+    };
+
     let images_metadata = match state.image_metadata_store.list().await {
         Ok(images) => images,
         Err(e) => return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to list images: {}", e)).into_response(),
@@ -14,6 +40,32 @@ pub async fn handle(
 
     let mut summaries = Vec::new();
     for meta in images_metadata {
+        if dangling.is_some_and(|dangling| dangling != meta.repo_tags.is_empty()) {
+            continue;
+        }
+        let references = filters.get("reference");
+        if !references.is_empty() && !meta.repo_tags.iter().any(|tag| references.iter().any(|r| reference_matches(r, tag))) {
+            continue;
+        }
+
+        // Labels live in the stored image config.
+        let labels = match state.image_store.load_config(&meta.id).await {
+            Ok(Some(json)) => json
+                .get("config")
+                .cloned()
+                .and_then(|config| serde_json::from_value::<ImageConfig>(config).ok())
+                .and_then(|config| config.labels)
+                .unwrap_or_default(),
+            Ok(None) => Default::default(),
+            Err(e) => {
+                tracing::warn!("Failed to load config of image {}: {}", meta.id, e);
+                Default::default()
+            }
+        };
+        if !filters.match_labels(&labels) {
+            continue;
+        }
+
         summaries.push(ImageSummary {
             id: format!("sha256:{}", meta.id),
             parent_id: meta.parent_id.unwrap_or_default(), // Should maybe prefix sha256?
@@ -23,10 +75,54 @@ pub async fn handle(
             size: meta.size,
             shared_size: -1,
             virtual_size: meta.size,
-            labels: None,
+            labels: Some(labels),
             containers: -1,
         });
     }
 
     Json(summaries).into_response()
 }
+
+/// Whether a `reference` filter (a name, optionally with a tag, where `*`
+/// and `?` are wildcards) matches `tag`, e.g. `busybox` or `busy*:latest`
+/// for `library/busybox:latest`.
+fn reference_matches(pattern: &str, tag: &str) -> bool {
+    let familiar = tag.strip_prefix("library/").unwrap_or(tag);
+    let has_tag = |reference: &str| reference.rsplit('/').next().unwrap_or(reference).contains(':');
+    [tag, familiar].iter().any(|candidate| {
+        let candidate = match (has_tag(pattern), candidate.rsplit_once(':')) {
+            (false, Some((name, _))) if has_tag(candidate) => name,
+            _ => candidate,
+        };
+        glob_match(pattern.as_bytes(), candidate.as_bytes())
+    })
+}
+
+/// `path.Match` style globbing: `*` matches within a path segment, `?` one
+/// character.
+fn glob_match(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob_match(rest, &text[i..])),
+        Some((b'?', rest)) => text.first().is_some_and(|&c| c != b'/') && glob_match(rest, &text[1..]),
+        Some((c, rest)) => text.first() == Some(c) && glob_match(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_matches() {
+        assert!(reference_matches("busybox", "library/busybox:latest"));
+        assert!(reference_matches("busybox:latest", "library/busybox:latest"));
+        assert!(reference_matches("busy*", "library/busybox:latest"));
+        assert!(reference_matches("library/busybox", "library/busybox:latest"));
+        assert!(reference_matches("myorg/*:v?", "myorg/app:v1"));
+        assert!(!reference_matches("busybox:1.36", "library/busybox:latest"));
+        assert!(!reference_matches("*", "myorg/app:v1"));
+    }
+}
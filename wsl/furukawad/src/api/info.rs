@@ -14,7 +14,7 @@ pub async fn handle(
     let total = containers.len();
     let running = containers.iter().filter(|c| matches!(c, AnyContainer::Running(_))).count();
     let stopped = containers.iter().filter(|c| matches!(c, AnyContainer::Stopped(_))).count();
-    let paused = 0; // Not implemented yet
+    let paused = containers.iter().filter(|c| matches!(c, AnyContainer::Paused(_))).count();
 
     // Get Memory Total from /proc/meminfo (simple parsing)
     let mem_total = if let Ok(info) = std::fs::read_to_string("/proc/meminfo") {
@@ -27,6 +27,8 @@ pub async fn handle(
         0
     };
 
+    let capabilities = state.cgroups.as_ref().map(|c| c.capabilities()).unwrap_or_default();
+
     let n_cpu = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
 
     let info = SystemInfo {
@@ -44,19 +46,21 @@ pub async fn handle(
             authorization: None,
             log: Some(vec!["json-file".to_string()]),
         },
-        memory_limit: true,
-        swap_limit: true,
-        kernel_memory: true,
-        kernel_memory_t_c_p: true,
-        cpu_cfs_period: true,
-        cpu_cfs_quota: true,
-        c_p_u_shares: true,
-        c_p_u_set: true,
-        pids_limit: true,
-        oom_kill_disable: true,
-        i_pv4_forwarding: true,
-        bridge_nf_iptables: true,
-        bridge_nf_ip6tables: true,
+        memory_limit: capabilities.memory,
+        swap_limit: capabilities.swap,
+        // Neither exists in cgroup v2.
+        kernel_memory: false,
+        kernel_memory_t_c_p: false,
+        cpu_cfs_period: capabilities.cpu,
+        cpu_cfs_quota: capabilities.cpu,
+        c_p_u_shares: capabilities.cpu,
+        c_p_u_set: capabilities.cpuset,
+        pids_limit: capabilities.pids,
+        // Not a limit the daemon offers.
+        oom_kill_disable: false,
+        i_pv4_forwarding: sysctl_enabled("net/ipv4/ip_forward"),
+        bridge_nf_iptables: sysctl_enabled("net/bridge/bridge-nf-call-iptables"),
+        bridge_nf_ip6tables: sysctl_enabled("net/bridge/bridge-nf-call-ip6tables"),
         debug: true,
         n_fd: 0,
         o_o_m_score_adj: 0,
@@ -73,3 +77,9 @@ pub async fn handle(
 
     Json(info).into_response()
 }
+
+/// Whether a boolean sysctl (a path under `/proc/sys`) is set; `false` if it
+/// does not exist on this host.
+fn sysctl_enabled(name: &str) -> bool {
+    std::fs::read_to_string(format!("/proc/sys/{}", name)).is_ok_and(|v| v.trim() == "1")
+}
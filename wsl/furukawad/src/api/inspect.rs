@@ -2,7 +2,8 @@ use axum::{extract::{Path, State}, response::IntoResponse, http::StatusCode, Jso
 use serde::Serialize;
 use serde_json::json;
 use crate::state::AppState;
-use furukawa_domain::container::AnyContainer;
+use super::lookup;
+use furukawa_domain::container::{AnyContainer, LogConfig, RestartPolicy};
 
 #[derive(Serialize)]
 #[serde(rename_all = "PascalCase")]
@@ -43,43 +44,79 @@ pub struct ContainerState {
     pub error: String,
     pub started_at: String,
     pub finished_at: String,
+    /// Only for running containers with a health check.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub health: Option<ContainerHealth>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ContainerHealth {
+    pub status: String,
+    pub failing_streak: u32,
+    pub log: Vec<HealthcheckResult>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct HealthcheckResult {
+    pub start: String,
+    pub end: String,
+    pub exit_code: i32,
+    pub output: String,
+}
+
+/// Docker's zero value for timestamps that have not happened yet.
+const ZERO_TIME: &str = "0001-01-01T00:00:00Z";
+
+fn rfc3339(t: time::OffsetDateTime) -> String {
+    t.format(&time::format_description::well_known::Rfc3339)
+        .unwrap_or_else(|_| ZERO_TIME.to_string())
 }
 
 pub async fn handle(
     State(state): State<AppState>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status.into_response(),
+    };
+
     match state.container_store.get_any(&id).await {
         Ok(Some(container)) => {
             let config = container.config();
-            let mut path = "".to_string();
-            let mut args = vec![];
-            
-            if !config.cmd.is_empty() {
-                path = config.cmd[0].clone();
-                if config.cmd.len() > 1 {
-                    args = config.cmd[1..].to_vec();
-                }
-            }
+            let command = config.command();
+            let (path, args) = match command.split_first() {
+                Some((path, args)) => (path.clone(), args.to_vec()),
+                None => (String::new(), Vec::new()),
+            };
             
             let status = container.status().to_string();
-            let running = status == "running";
+            // Docker reports a paused container as running too.
+            let running = status == "running" || status == "paused";
             
             let (pid, started_at, finished_at, exit_code) = match &container {
-                AnyContainer::Running(c) => (c.state().pid, c.state().started_at.to_string(), "0001-01-01T00:00:00Z".to_string(), 0),
-                AnyContainer::Stopped(c) => (0, "0001-01-01T00:00:00Z".to_string(), c.state().finished_at.to_string(), c.state().exit_code),
-                _ => (0, "0001-01-01T00:00:00Z".to_string(), "0001-01-01T00:00:00Z".to_string(), 0),
+                AnyContainer::Running(c) => (c.state().pid, rfc3339(c.state().started_at), ZERO_TIME.to_string(), 0),
+                AnyContainer::Paused(c) => (c.state().pid, rfc3339(c.state().started_at), ZERO_TIME.to_string(), 0),
+                AnyContainer::Stopped(c) => (
+                    0,
+                    c.state().started_at.map(rfc3339).unwrap_or_else(|| ZERO_TIME.to_string()),
+                    rfc3339(c.state().finished_at),
+                    c.state().exit_code,
+                ),
+                _ => (0, ZERO_TIME.to_string(), ZERO_TIME.to_string(), 0),
             };
 
             let inspect = ContainerInspect {
                 id: container.id().to_string(),
-                created: "2024-01-01T00:00:00Z".to_string(), // TODO: persist created_at
+                created: rfc3339(container.created_at()),
                 path,
                 args,
                 state: ContainerState {
                     status,
                     running,
-                    paused: false,
+                    paused: matches!(container, AnyContainer::Paused(_)),
                     restarting: false,
                     o_o_m_killed: false,
                     dead: false,
@@ -88,21 +125,58 @@ pub async fn handle(
                     error: "".to_string(),
                     started_at,
                     finished_at,
+                    health: state.health.get(container.id()).map(|health| ContainerHealth {
+                        status: health.status.as_str().to_string(),
+                        failing_streak: health.failing_streak,
+                        log: health
+                            .log
+                            .iter()
+                            .map(|result| HealthcheckResult {
+                                start: rfc3339(result.start),
+                                end: rfc3339(result.end),
+                                exit_code: result.exit_code,
+                                output: result.output.clone(),
+                            })
+                            .collect(),
+                    }),
                 },
                 image: config.image.clone(),
-                name: format!("/{}", container.id()), // TODO: Implement naming
-                restart_count: 0,
+                name: format!("/{}", container.name()),
+                restart_count: container.restart_count(),
                 driver: "furukawa-fs".to_string(),
                 platform: "windows".to_string(),
                 mount_label: "".to_string(),
                 process_label: "".to_string(),
                 app_armor_profile: "".to_string(),
-                exec_i_ds: None,
-                host_config: json!({}),
+                exec_i_ds: Some(state.execs.ids_for_container(container.id())).filter(|ids| !ids.is_empty()),
+                host_config: json!({
+                    "RestartPolicy": {
+                        "Name": config.restart_policy.name(),
+                        "MaximumRetryCount": match config.restart_policy {
+                            RestartPolicy::OnFailure { max_retries } => max_retries,
+                            _ => 0,
+                        },
+                    },
+                    "LogConfig": {
+                        "Type": LogConfig::DRIVER,
+                        "Config": config.log_config.options(),
+                    },
+                    "Memory": config.resources.memory.unwrap_or(0),
+                    "MemorySwap": config.resources.memory_swap.unwrap_or(0),
+                    "NanoCpus": config.resources.nano_cpus.unwrap_or(0),
+                    "CpuShares": config.resources.cpu_shares.unwrap_or(0),
+                    "CpusetCpus": config.resources.cpuset_cpus.clone().unwrap_or_default(),
+                    "PidsLimit": config.resources.pids_limit,
+                }),
                 graph_driver: json!({}),
                 size_rw: None,
                 size_root_fs: None,
-                config: json!(config),
+                config: {
+                    // Docker clients read `Config.Labels`.
+                    let mut json = json!(config);
+                    json["Labels"] = json!(config.labels);
+                    json
+                },
                 network_settings: json!({
                     "Bridge": "",
                     "SandboxID": "",
@@ -0,0 +1,53 @@
+use axum::{extract::{Path, Query, State}, response::IntoResponse, http::StatusCode};
+use furukawa_domain::container::AnyContainer;
+use serde::Deserialize;
+use tracing::{info, error};
+use crate::state::AppState;
+use super::lookup;
+
+#[derive(Deserialize)]
+pub struct KillQueryParams {
+    signal: Option<String>,
+}
+
+pub async fn handle(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<KillQueryParams>,
+) -> impl IntoResponse {
+    let signal = params.signal.unwrap_or_else(|| "SIGKILL".to_string());
+    info!(id = %id, signal = %signal, "Received kill container request");
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status,
+    };
+
+    // 1. Load Container and signal it via the Runtime. Only running (or
+    // paused) containers can be signalled; the supervisor records the exit
+    // if the process terminates.
+    let signalled = match state.container_store.get_any(&id).await {
+        Ok(Some(AnyContainer::Running(c))) => state.runtime.kill(&c, &signal).await,
+        Ok(Some(AnyContainer::Paused(c))) => state.runtime.kill_paused(&c, &signal).await,
+        Ok(Some(_)) => return StatusCode::CONFLICT,
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    match signalled {
+        Ok(()) => {
+            info!(id = %id, signal = %signal, "Signal sent to container");
+            StatusCode::NO_CONTENT
+        }
+        Err(e) if e.code() == "RUNTIME_INVALID_SIGNAL" => {
+            error!("Failed to kill container: {}", e);
+            StatusCode::BAD_REQUEST
+        }
+        Err(e) => {
+            error!("Failed to kill container: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
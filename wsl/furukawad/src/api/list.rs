@@ -1,50 +1,144 @@
-use axum::{extract::{Query, State}, response::IntoResponse, Json as AxumJson};
+use axum::{extract::{Query, State}, http::StatusCode, response::{IntoResponse, Response}, Json as AxumJson};
 use furukawa_infra_docker::v1_45::{ContainerSummary, HostConfigSummary, SummaryNetworkSettings};
+use furukawa_domain::container::{store::ContainerQuery, AnyContainer};
+use furukawa_infra_runtime::health::HealthStatus;
+use furukawa_infra_runtime::rootfs;
+use regex::Regex;
 use serde::Deserialize;
-use tracing::info;
+use serde_json::json;
+use tracing::{error, info};
 use crate::state::AppState;
 use std::collections::HashMap;
+use super::{filters::Filters, lookup, params};
+
+/// Filters of `GET /containers/json`.
+const FILTERS: &[&str] = &[
+    "ancestor", "before", "exited", "health", "id", "label", "name", "network", "since", "status", "volume",
+];
+const STATUSES: &[&str] = &["created", "restarting", "running", "removing", "paused", "exited", "dead"];
+const HEALTH: &[&str] = &["starting", "healthy", "unhealthy", "none"];
 
 #[derive(Deserialize)]
 pub struct ListQueryParams {
-    all: Option<bool>,
+    #[serde(default, deserialize_with = "params::flag")]
+    all: bool,
     limit: Option<isize>,
-    size: Option<bool>,
+    #[serde(default, deserialize_with = "params::flag")]
+    size: bool,
     filters: Option<String>,
 }
 
 pub async fn handle(
     State(state): State<AppState>,
     Query(params): Query<ListQueryParams>,
-) -> impl IntoResponse {
+) -> Response {
     info!(
-        all = ?params.all,
+        all = params.all,
         limit = ?params.limit,
-        size = ?params.size,
+        size = params.size,
         filters = ?params.filters,
         "Received container list request"
     );
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, AxumJson(json!({ "message": message }))).into_response();
+
+    let filters = match Filters::parse(params.filters.as_deref()).and_then(|f| f.validate(FILTERS).map(|_| f)) {
+        Ok(filters) => filters,
+        Err(message) => return bad_request(message),
+    };
+    let limit = params.limit.filter(|l| *l > 0).map(|l| l as usize);
+    let mut query = match store_query(&state, &params, limit, &filters).await {
+        Ok(query) => query,
+        Err(response) => return response,
+    };
 
-    // Fetch from domain store
-    // In production, we would map domain filters to store queries
-    let containers = state.container_store.list().await.unwrap_or_else(|e| {
-        tracing::error!("Failed to list containers: {}", e);
-        vec![]
-    });
+    // Filters the store cannot apply are checked here, so the limit can
+    // only be left to the store without them.
+    let names = match filters.get("name").iter().map(|n| Regex::new(n)).collect::<Result<Vec<_>, _>>() {
+        Ok(names) => names,
+        Err(e) => return bad_request(format!("invalid filter 'name': {}", e)),
+    };
+    if let Some(value) = filters.get("health").iter().find(|h| !HEALTH.contains(&h.as_str())) {
+        return bad_request(format!("Unrecognised filter value for health: {}", value));
+    }
+    let in_memory = ["health", "label", "name", "network", "volume"].iter().any(|f| !filters.get(f).is_empty());
+    if !in_memory {
+        query.limit = limit;
+    }
+
+    let containers = match state.container_store.query(&query).await {
+        Ok(containers) => containers,
+        Err(e) => {
+            error!("Failed to list containers: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let network_ids: HashMap<String, String> = if filters.get("network").is_empty() {
+        HashMap::new()
+    } else {
+        match state.network_store.list().await {
+            Ok(networks) => networks.into_iter().map(|n| (n.name, n.id)).collect(),
+            Err(e) => {
+                error!("Failed to list networks: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        }
+    };
+
+    let containers: Vec<AnyContainer> = containers
+        .into_iter()
+        .filter(|c| {
+            let config = c.config();
+            let name = format!("/{}", c.name());
+            let health = match (c, state.health.get(c.id())) {
+                (AnyContainer::Running(_), Some(health)) => health.status.as_str(),
+                _ => "none",
+            };
+            filters.match_labels(&config.labels)
+                && (names.is_empty() || names.iter().any(|re| re.is_match(&name) || re.is_match(c.name())))
+                && (filters.get("health").is_empty() || filters.get("health").iter().any(|h| h == health))
+                && (filters.get("network").is_empty()
+                    || filters.get("network").iter().any(|n| {
+                        *n == config.network || network_ids.get(&config.network).is_some_and(|id| id.starts_with(n.as_str()))
+                    }))
+                && (filters.get("volume").is_empty()
+                    || filters.get("volume").iter().any(|v| {
+                        config.volumes.iter().any(|m| m.host_path == *v || m.container_path == *v)
+                    }))
+        })
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
 
     // Map Domain Container -> API ContainerSummary
-    let summary: Vec<ContainerSummary> = containers.into_iter().map(|c| {
-        ContainerSummary {
+    let now = time::OffsetDateTime::now_utc();
+    let mut image_sizes: HashMap<String, i64> = HashMap::new();
+    let mut summary = Vec::with_capacity(containers.len());
+    for c in containers {
+        let mut status = c.status_text(now);
+        if let (AnyContainer::Running(_), Some(health)) = (&c, state.health.get(c.id())) {
+            status = match health.status {
+                HealthStatus::Starting => format!("{} (health: starting)", status),
+                other => format!("{} ({})", status, other.as_str()),
+            };
+        }
+        let (size_rw, size_root_fs) = if params.size {
+            let (rw, total) = sizes(&state, &c, &mut image_sizes).await;
+            (rw, Some(total))
+        } else {
+            (None, None)
+        };
+        summary.push(ContainerSummary {
             id: c.id().to_string(),
-            names: vec![format!("/furukawa-{}", &c.id()[0..8])], 
+            names: vec![format!("/{}", c.name())],
             image: c.config().image.clone(),
             image_id: "sha256:placeholder".to_string(),
-            command: c.config().cmd.join(" "),
-            created: 1700000000, // Placeholder timestamp
+            command: c.config().command().join(" "),
+            created: c.created_at().unix_timestamp(),
             ports: vec![],
-            labels: HashMap::new(),
+            size_rw,
+            size_root_fs,
+            labels: c.config().labels.clone(),
             state: c.status().to_string(),
-            status: c.status().to_uppercase(), // "created" -> "CREATED"
+            status,
             host_config: HostConfigSummary {
                 network_mode: "default".to_string(),
             },
@@ -52,8 +146,148 @@ pub async fn handle(
                 networks: HashMap::new(),
             },
             mounts: vec![],
+        });
+    }
+
+    AxumJson(summary).into_response()
+}
+
+/// The part of the request the container store applies itself.
+async fn store_query(
+    state: &AppState,
+    params: &ListQueryParams,
+    limit: Option<usize>,
+    filters: &Filters,
+) -> Result<ContainerQuery, Response> {
+    let bad_request = |message: String| (StatusCode::BAD_REQUEST, AxumJson(json!({ "message": message }))).into_response();
+
+    if let Some(value) = filters.get("status").iter().find(|s| !STATUSES.contains(&s.as_str())) {
+        return Err(bad_request(format!("Unrecognised filter value for status: {}", value)));
+    }
+    let mut exit_codes = Vec::new();
+    for code in filters.get("exited") {
+        match code.parse() {
+            Ok(code) => exit_codes.push(code),
+            Err(_) => return Err(bad_request(format!("invalid filter 'exited={}'", code))),
         }
-    }).collect();
+    }
+    let created_before = match filters.get("before").last() {
+        Some(reference) => Some(created_at(state, reference).await?),
+        None => None,
+    };
+    let created_after = match filters.get("since").last() {
+        Some(reference) => Some(created_at(state, reference).await?),
+        None => None,
+    };
+    let mut images = Vec::new();
+    for reference in filters.get("ancestor") {
+        images.extend(image_references(state, reference).await);
+    }
+
+    // As in Docker, asking for a limit, a status or a position in the list
+    // includes stopped containers.
+    let all = params.all
+        || limit.is_some()
+        || !filters.get("status").is_empty()
+        || created_before.is_some()
+        || created_after.is_some();
+    Ok(ContainerQuery {
+        running_only: !all,
+        statuses: filters.get("status").to_vec(),
+        ids: filters.get("id").to_vec(),
+        images,
+        exit_codes,
+        created_before,
+        created_after,
+        limit: None,
+    })
+}
 
-    AxumJson(summary)
+/// Creation time of the container a `before`/`since` filter refers to.
+async fn created_at(state: &AppState, reference: &str) -> Result<time::OffsetDateTime, Response> {
+    let not_found = || (StatusCode::NOT_FOUND, AxumJson(json!({ "message": format!("No such container: {}", reference) }))).into_response();
+    let id = lookup::container_id(state, reference).await.map_err(|status| match status {
+        StatusCode::NOT_FOUND => not_found(),
+        status => status.into_response(),
+    })?;
+    match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => Ok(c.created_at()),
+        Ok(None) => Err(not_found()),
+        Err(e) => {
+            error!("Failed to load container {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+        }
+    }
+}
+
+/// Every `Config.image` value that names `reference` or an image built on
+/// top of it: IDs, and tags with and without `library/` and `:latest`.
+async fn image_references(state: &AppState, reference: &str) -> Vec<String> {
+    let mut references = vec![reference.to_string()];
+    let lookup = reference.strip_prefix("sha256:").unwrap_or(reference);
+    let ancestor = match state.image_metadata_store.get(lookup).await {
+        Ok(Some(image)) => image.id,
+        // An untagged name means `:latest`.
+        _ => match state.image_metadata_store.get(&format!("{}:latest", lookup)).await {
+            Ok(Some(image)) => image.id,
+            _ => return references,
+        },
+    };
+    let images = match state.image_metadata_store.list().await {
+        Ok(images) => images,
+        Err(e) => {
+            error!("Failed to list images: {}", e);
+            return references;
+        }
+    };
+    let parents: HashMap<&str, &str> = images
+        .iter()
+        .filter_map(|i| Some((i.id.as_str(), i.parent_id.as_deref()?)))
+        .collect();
+
+    for image in &images {
+        // Bounded, in case of a cycle in the parent links.
+        let mut lineage = std::iter::successors(Some(image.id.as_str()), |id| parents.get(id).copied());
+        if !lineage.by_ref().take(images.len()).any(|id| id == ancestor) {
+            continue;
+        }
+
+        references.push(image.id.clone());
+        references.push(image.id.trim_start_matches("sha256:").to_string());
+        for tag in &image.repo_tags {
+            for tag in [tag.as_str(), tag.strip_prefix("library/").unwrap_or(tag)] {
+                references.push(tag.to_string());
+                if let Some(name) = tag.strip_suffix(":latest") {
+                    references.push(name.to_string());
+                }
+            }
+        }
+    }
+    references.sort();
+    references.dedup();
+    references
+}
+
+/// `SizeRw` and `SizeRootFs`. Containers that never started have no rootfs
+/// yet and count as their image.
+async fn sizes(state: &AppState, container: &AnyContainer, image_sizes: &mut HashMap<String, i64>) -> (Option<i64>, i64) {
+    let dir = state.containers_root.join(container.id());
+    match tokio::task::spawn_blocking(move || rootfs::disk_usage(&dir)).await {
+        Ok(Some(usage)) => (usage.rw.map(|rw| rw as i64), usage.total as i64),
+        Ok(None) => {
+            let image = &container.config().image;
+            if !image_sizes.contains_key(image) {
+                let size = match state.image_metadata_store.get(image).await {
+                    Ok(Some(metadata)) => metadata.size,
+                    _ => 0,
+                };
+                image_sizes.insert(image.clone(), size);
+            }
+            (Some(0), image_sizes[image])
+        }
+        Err(e) => {
+            error!("Disk usage task failed: {}", e);
+            (None, 0)
+        }
+    }
 }
@@ -1,51 +1,242 @@
-use axum::{extract::{Path, State}, response::IntoResponse, http::StatusCode};
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use furukawa_infra_runtime::logs::{LogEntry, LogReader, Stream};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 use tracing::error;
 use crate::state::AppState;
-use std::path::Path as StdPath;
-use tokio::fs::File;
-use tokio::io::AsyncReadExt;
+use super::{lookup, params, stdcopy};
+
+/// How often a followed log is checked for new output.
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Deserialize)]
+pub struct LogsQueryParams {
+    #[serde(default, deserialize_with = "params::flag")]
+    follow: bool,
+    #[serde(default, deserialize_with = "params::flag")]
+    stdout: bool,
+    #[serde(default, deserialize_with = "params::flag")]
+    stderr: bool,
+    #[serde(default, deserialize_with = "params::flag")]
+    timestamps: bool,
+    since: Option<String>,
+    until: Option<String>,
+    /// Number of lines from the end, or "all".
+    tail: Option<String>,
+}
+
+struct LogOptions {
+    /// The container has a TTY: its output is sent raw, not multiplexed.
+    tty: bool,
+    follow: bool,
+    stdout: bool,
+    stderr: bool,
+    timestamps: bool,
+    since: Option<time::OffsetDateTime>,
+    until: Option<time::OffsetDateTime>,
+    tail: Option<usize>,
+}
+
+impl LogOptions {
+    fn from_params(params: LogsQueryParams, tty: bool) -> Result<Self, String> {
+        if !params.stdout && !params.stderr {
+            return Err("You must choose at least one stream".to_string());
+        }
+        Ok(Self {
+            tty,
+            follow: params.follow,
+            stdout: params.stdout,
+            stderr: params.stderr,
+            timestamps: params.timestamps,
+            since: params::unix_timestamp(params.since.as_deref().unwrap_or(""))?,
+            until: params::unix_timestamp(params.until.as_deref().unwrap_or(""))?,
+            // Like Docker, anything that is not a number means all lines.
+            tail: params.tail.and_then(|t| t.parse().ok()),
+        })
+    }
+
+    fn wants(&self, entry: &LogEntry) -> bool {
+        let stream = match entry.stream {
+            Stream::Stdout => self.stdout,
+            Stream::Stderr => self.stderr,
+        };
+        stream && self.since.is_none_or(|since| entry.time >= since)
+    }
+
+    fn is_past_until(&self, entry: &LogEntry) -> bool {
+        self.until.is_some_and(|until| entry.time > until)
+    }
+}
 
 pub async fn handle(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    Query(params): Query<LogsQueryParams>,
 ) -> impl IntoResponse {
-    // 1. Check if container exists
-    if let Err(_) = state.container_store.get_status(&id).await {
-        return StatusCode::NOT_FOUND.into_response();
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status.into_response(),
+    };
+
+    let tty = match state.container_store.get_any(&id).await {
+        Ok(Some(container)) => container.config().tty,
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let options = match LogOptions::from_params(params, tty) {
+        Ok(options) => options,
+        Err(message) => {
+            return (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "message": message }))).into_response();
+        }
+    };
+
+    let reader = match state.logs.reader(&id).await {
+        Ok(reader) => reader,
+        Err(e) => {
+            error!("Failed to open log file: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let (tx, rx) = mpsc::channel(64);
+    if let Some(reader) = reader {
+        tokio::spawn(async move {
+            if let Err(e) = stream_logs(&state, &id, reader, &options, &tx).await {
+                error!("Failed to read logs of {}: {}", id, e);
+            }
+        });
     }
 
-    // 2. Construct log path
-    let log_path = StdPath::new("furukawa_logs").join(format!("{}.log", id));
+    let content_type = if tty { stdcopy::RAW_STREAM } else { stdcopy::MULTIPLEXED_STREAM };
+    let body = Body::from_stream(ReceiverStream::new(rx).map(Ok::<_, Infallible>));
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type)
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Sends the existing log (honouring `tail`) and, when following, new
+/// entries until the container stops or the client goes away.
+async fn stream_logs(
+    state: &AppState,
+    id: &str,
+    mut reader: LogReader,
+    options: &LogOptions,
+    tx: &mpsc::Sender<Bytes>,
+) -> std::io::Result<()> {
+    let mut backlog = VecDeque::new();
+    while let Some(entry) = reader.next_entry().await? {
+        if options.is_past_until(&entry) {
+            break;
+        }
+        if options.wants(&entry) {
+            backlog.push_back(entry);
+            if options.tail.is_some_and(|tail| backlog.len() > tail) {
+                backlog.pop_front();
+            }
+        }
+    }
+    for entry in backlog {
+        if tx.send(frame(&entry, options.timestamps, options.tty)).await.is_err() {
+            return Ok(());
+        }
+    }
 
-    if !log_path.exists() {
-        return StatusCode::NOT_FOUND.into_response();
+    if !options.follow {
+        return Ok(());
     }
 
-    // 3. Read file
-    match File::open(&log_path).await {
-        Ok(mut file) => {
-            let mut bytes = Vec::new();
-            if let Err(e) = file.read_to_end(&mut bytes).await {
-                error!("Failed to read log file: {}", e);
-                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    loop {
+        // Output is in the log before the exit is recorded, so reading once
+        // more after seeing the container stopped gets all of it.
+        let running = matches!(state.container_store.get_status(id).await, Ok(Some(status)) if status == "running" || status == "paused");
+
+        while let Some(entry) = reader.next_entry().await? {
+            if options.is_past_until(&entry) {
+                return Ok(());
             }
+            if options.wants(&entry) && tx.send(frame(&entry, options.timestamps, options.tty)).await.is_err() {
+                return Ok(());
+            }
+        }
 
-            let contents = if bytes.starts_with(&[0xff, 0xfe]) {
-                // UTF-16 LE
-                let u16_data: Vec<u16> = bytes[2..]
-                    .chunks_exact(2)
-                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
-                    .collect();
-                String::from_utf16_lossy(&u16_data)
-            } else {
-                String::from_utf8_lossy(&bytes).into_owned()
-            };
-
-            (StatusCode::OK, contents).into_response()
-        },
-        Err(e) => {
-            error!("Failed to open log file: {}", e);
-            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        if !running || tx.is_closed() {
+            return Ok(());
         }
+        tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+    }
+}
+
+/// Encodes an entry as a multiplexed stream frame (or as is for a TTY),
+/// optionally prefixed with its timestamp.
+fn frame(entry: &LogEntry, timestamps: bool, tty: bool) -> Bytes {
+    let payload = if timestamps {
+        Bytes::from(format!("{} {}", rfc3339_nano(entry.time), entry.log))
+    } else {
+        Bytes::copy_from_slice(entry.log.as_bytes())
+    };
+    if tty {
+        payload
+    } else {
+        stdcopy::frame(entry.stream, &payload)
+    }
+}
+
+/// Docker's fixed-width `RFC3339Nano` used for log timestamps.
+pub(super) fn rfc3339_nano(t: time::OffsetDateTime) -> String {
+    let t = t.to_offset(time::UtcOffset::UTC);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:09}Z",
+        t.year(),
+        u8::from(t.month()),
+        t.day(),
+        t.hour(),
+        t.minute(),
+        t.second(),
+        t.nanosecond()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_header() {
+        let entry = LogEntry {
+            log: "err\n".to_string(),
+            stream: Stream::Stderr,
+            time: time::OffsetDateTime::from_unix_timestamp(1714557600).unwrap() + time::Duration::nanoseconds(5),
+        };
+        assert_eq!(&frame(&entry, false, false)[..], b"\x02\0\0\0\0\0\0\x04err\n");
+        assert_eq!(
+            &frame(&entry, true, false)[8..],
+            b"2024-05-01T10:00:00.000000005Z err\n"
+        );
+        assert_eq!(&frame(&entry, false, true)[..], b"err\n");
+    }
+
+    #[test]
+    fn test_unix_timestamp_param() {
+        let t = params::unix_timestamp("1714557600.5").unwrap().unwrap();
+        assert_eq!(t.unix_timestamp(), 1714557600);
+        assert_eq!(t.nanosecond(), 500_000_000);
+        assert_eq!(params::unix_timestamp("0").unwrap(), None);
+        assert!(params::unix_timestamp("yesterday").is_err());
     }
 }
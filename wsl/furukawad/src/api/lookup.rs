@@ -0,0 +1,71 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use furukawa_infra_runtime::rootfs;
+use serde_json::json;
+use tracing::error;
+use crate::state::AppState;
+
+/// Resolves the `:id` path segment of a `/containers/:id/...` route, which
+/// may be a full ID, a name or a unique ID prefix, to the full container ID.
+pub async fn container_id(state: &AppState, reference: &str) -> Result<String, StatusCode> {
+    match state.container_store.resolve_id(reference).await {
+        Ok(Some(id)) => Ok(id),
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) if e.code() == "CONTAINER_AMBIGUOUS_REFERENCE" => {
+            error!("{}", e);
+            Err(StatusCode::BAD_REQUEST)
+        }
+        Err(e) => {
+            error!("Failed to resolve container {}: {}", reference, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Resolves the container and its rootfs, composing the rootfs first if
+/// the container never started.
+pub async fn container_rootfs(state: &AppState, reference: &str) -> Result<(String, std::path::PathBuf), Response> {
+    let id = container_id(state, reference).await.map_err(|status| match status {
+        StatusCode::NOT_FOUND => {
+            (StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such container: {}", reference) }))).into_response()
+        }
+        status => status.into_response(),
+    })?;
+    let image = match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => c.config().image.clone(),
+        Ok(None) => {
+            return Err((StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such container: {}", reference) }))).into_response());
+        }
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+
+    let container_dir = state.containers_root.join(&id);
+    let rootfs_dir = container_dir.join("rootfs");
+    if rootfs_dir.exists() {
+        return Ok((id, rootfs_dir));
+    }
+    // As at start, a missing image is an error rather than an empty rootfs.
+    let layers = match state.image_metadata_store.get(&image).await {
+        Ok(Some(metadata)) => metadata.layers,
+        Ok(None) => {
+            return Err((StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such image: {}", image) }))).into_response());
+        }
+        Err(e) => {
+            error!("Failed to resolve image {}: {}", image, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        }
+    };
+    match rootfs::compose(&state.image_store, &layers, &container_dir).await {
+        Ok(rootfs_dir) => Ok((id, rootfs_dir)),
+        Err(e) => {
+            error!("Failed to compose rootfs of container {}: {}", id, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "message": e.to_string() }))).into_response())
+        }
+    }
+}
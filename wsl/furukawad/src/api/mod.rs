@@ -1,8 +1,27 @@
+pub mod archive;
+pub mod changes;
+pub mod commit;
 pub mod create;
 pub mod list;
 pub mod start;
 pub mod stop;
+pub mod kill;
+pub mod pause;
+pub mod filters;
+pub mod lookup;
+pub mod params;
+pub mod stdcopy;
+pub mod tar_stream;
+pub mod restart;
+pub mod resize;
+pub mod stats;
+pub mod top;
+pub mod wait;
 pub mod delete;
+pub mod attach;
+pub mod exec;
+pub mod export;
+pub mod hijack;
 pub mod logs;
 pub mod inspect;
 pub mod version;
@@ -13,8 +32,10 @@ pub mod networks;
 pub mod build;
 pub mod compose;
 pub mod volumes;
+#[cfg(test)]
+mod testing;
 
-use axum::{routing::{get, post, delete as axum_delete}, Router};
+use axum::{extract::DefaultBodyLimit, routing::{get, post, put, delete as axum_delete}, Router};
 use crate::state::AppState;
 
 pub fn router(state: AppState) -> Router {
@@ -30,9 +51,29 @@ pub fn router(state: AppState) -> Router {
         .route("/containers/json", get(list::handle))
         .route("/containers/:id/start", post(start::handle))
         .route("/containers/:id/stop", post(stop::handle))
+        .route("/containers/:id/kill", post(kill::handle))
+        .route("/containers/:id/restart", post(restart::handle))
+        .route("/containers/:id/pause", post(pause::pause))
+        .route("/containers/:id/unpause", post(pause::unpause))
         .route("/containers/:id/logs", get(logs::handle))
         .route("/containers/:id/json", get(inspect::handle))
+        .route("/containers/:id/attach", post(attach::handle))
+        .route("/containers/:id/attach/ws", get(attach::websocket))
+        .route("/containers/:id/resize", post(resize::handle))
+        .route("/containers/:id/stats", get(stats::handle))
+        .route("/containers/:id/top", get(top::handle))
+        .route("/containers/:id/wait", post(wait::handle))
+        .route("/containers/:id/changes", get(changes::handle))
+        .route("/containers/:id/export", get(export::handle))
+        .route("/containers/:id/archive", get(archive::get).head(archive::head))
+        // Archives can be any size
+        .route("/containers/:id/archive", put(archive::put).layer(DefaultBodyLimit::disable()))
         .route("/containers/:id", axum_delete(delete::handle))
+        // Exec
+        .route("/containers/:id/exec", post(exec::create))
+        .route("/exec/:id/start", post(exec::start))
+        .route("/exec/:id/json", get(exec::inspect))
+        .route("/exec/:id/resize", post(exec::resize))
         // Networks
         .route("/networks", get(networks::list))
         .route("/networks/create", post(networks::create))
@@ -45,6 +86,7 @@ pub fn router(state: AppState) -> Router {
         .route("/volumes/:name", axum_delete(volumes::delete))
         // Build
         .route("/build", post(build::handle))
+        .route("/commit", post(commit::handle))
         // Compose
         .route("/compose/up", post(compose::up))
         .route("/compose/down", post(compose::down))
@@ -1,10 +1,11 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use crate::api::filters::Filters;
 use crate::state::AppState;
 use furukawa_domain::network::NetworkRecord;
 use tracing::info;
@@ -53,8 +54,21 @@ fn record_to_response(r: NetworkRecord) -> NetworkResponse {
     }
 }
 
-/// GET /networks — List all networks
-pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
+#[derive(Debug, Deserialize)]
+pub struct ListNetworksParams {
+    pub filters: Option<String>,
+}
+
+/// GET /networks — List all networks, with `driver`, `id`, `label` and
+/// `name` filters (IDs and names match by prefix and substring, as in Docker).
+pub async fn list(State(state): State<AppState>, Query(params): Query<ListNetworksParams>) -> impl IntoResponse {
+    let filters = match Filters::parse(params.filters.as_deref()).and_then(|f| f.validate(&["driver", "id", "label", "name"]).map(|_| f)) {
+        Ok(filters) => filters,
+        Err(message) => {
+            return (axum::http::StatusCode::BAD_REQUEST, Json(serde_json::json!({ "message": message })));
+        }
+    };
+
     let mut networks = vec![
         builtin_network("bridge", "bridge", "bridge"),
         builtin_network("host0host0", "host", "host"),
@@ -65,7 +79,18 @@ pub async fn list(State(state): State<AppState>) -> impl IntoResponse {
         networks.extend(custom.into_iter().map(record_to_response));
     }
 
-    Json(networks)
+    let any = |key: &str, matches: &dyn Fn(&String) -> bool| {
+        let values = filters.get(key);
+        values.is_empty() || values.iter().any(matches)
+    };
+    networks.retain(|n| {
+        any("driver", &|d| *d == n.driver)
+            && any("id", &|id| n.id.starts_with(id.as_str()))
+            && any("name", &|name| n.name.contains(name.as_str()))
+            && filters.match_labels(&n.labels)
+    });
+
+    (axum::http::StatusCode::OK, Json(serde_json::to_value(networks).unwrap_or_default()))
 }
 
 /// GET /networks/{id} — Inspect a network
@@ -129,7 +154,7 @@ pub async fn create(
         );
     }
 
-    (axum::http::StatusCode::CREATED, Json(serde_json::json!({ "Id": id })))
+    (axum::http::StatusCode::CREATED, Json(serde_json::to_value(CreateNetworkResponse { id }).unwrap_or_default()))
 }
 
 /// DELETE /networks/{id} — Remove a network
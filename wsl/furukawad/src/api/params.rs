@@ -0,0 +1,60 @@
+//! Query parameter parsing shared by the handlers.
+
+use serde::{Deserialize, Deserializer};
+
+/// Docker's boolean query values: `1`, `true`, `yes`... are true; empty,
+/// `0`, `no`, `false` and `none` are false.
+pub fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    String::deserialize(deserializer).map(|value| is_true(&value))
+}
+
+/// A boolean query value, see [`flag`].
+pub fn is_true(value: &str) -> bool {
+    !matches!(value.to_ascii_lowercase().as_str(), "" | "0" | "no" | "false" | "none")
+}
+
+/// Parses a Docker timestamp parameter: Unix seconds with an optional
+/// fractional part (`1714557600.123456789`). `0` and empty mean "not set".
+pub fn unix_timestamp(value: &str) -> Result<Option<time::OffsetDateTime>, String> {
+    let invalid = || format!("invalid timestamp: {}", value);
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    let (secs, frac) = value.split_once('.').unwrap_or((value, ""));
+    let secs: i64 = secs.parse().map_err(|_| invalid())?;
+    let nanos: i64 = if frac.is_empty() {
+        0
+    } else if frac.len() <= 9 && frac.bytes().all(|b| b.is_ascii_digit()) {
+        format!("{:0<9}", frac).parse().map_err(|_| invalid())?
+    } else {
+        return Err(invalid());
+    };
+    if secs == 0 && nanos == 0 {
+        return Ok(None);
+    }
+
+    let t = time::OffsetDateTime::from_unix_timestamp(secs).map_err(|_| invalid())?;
+    Ok(Some(t + time::Duration::nanoseconds(nanos)))
+}
+
+/// Docker's default key sequence for detaching from a session.
+pub const DEFAULT_DETACH_KEYS: &str = "ctrl-p,ctrl-q";
+
+/// Parses a detach key sequence such as `ctrl-p,ctrl-q` or `ctrl-a,x`: a
+/// comma-separated list of single characters or `ctrl-<key>` with `<key>`
+/// one of `a`-`z`, `@`, `[`, `\`, `]`, `^` and `_`.
+pub fn detach_keys(spec: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Invalid detach keys ({}) provided", spec);
+    spec.split(',')
+        .map(|key| match key.strip_prefix("ctrl-") {
+            Some(ctrl) if ctrl.len() == 1 => match ctrl.as_bytes()[0].to_ascii_lowercase() {
+                c @ b'a'..=b'z' => Ok(c - b'a' + 1),
+                c @ (b'@' | b'[' | b'\\' | b']' | b'^' | b'_') => Ok(c - b'@'),
+                _ => Err(invalid()),
+            },
+            _ if key.len() == 1 => Ok(key.as_bytes()[0]),
+            _ => Err(invalid()),
+        })
+        .collect()
+}
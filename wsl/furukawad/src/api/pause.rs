@@ -0,0 +1,87 @@
+use axum::{extract::{Path, State}, response::IntoResponse, http::StatusCode, Json};
+use furukawa_domain::container::AnyContainer;
+use serde_json::json;
+use tracing::{info, error};
+use crate::state::AppState;
+use super::lookup;
+
+/// `POST /containers/:id/pause`: freezes every process of the container.
+pub async fn pause(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    info!(id = %id, "Received pause container request");
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status.into_response(),
+    };
+
+    let container = match state.container_store.get_any(&id).await {
+        Ok(Some(AnyContainer::Running(c))) => c,
+        Ok(Some(AnyContainer::Paused(_))) => return conflict(format!("Container {} is already paused", id)),
+        Ok(Some(_)) => return conflict(format!("Container {} is not running", id)),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let paused = match container.pause(state.runtime.as_ref()).await {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Failed to pause container: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "message": e.to_string() }))).into_response();
+        }
+    };
+
+    if let Err(e) = state.container_store.save_paused(&paused).await {
+        error!("Failed to save paused state: {}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    info!(id = %id, "Container paused");
+    StatusCode::NO_CONTENT.into_response()
+}
+
+/// `POST /containers/:id/unpause`: thaws a paused container.
+pub async fn unpause(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    info!(id = %id, "Received unpause container request");
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status.into_response(),
+    };
+
+    let container = match state.container_store.get_any(&id).await {
+        Ok(Some(AnyContainer::Paused(c))) => c,
+        Ok(Some(_)) => return conflict(format!("Container {} is not paused", id)),
+        Ok(None) => return StatusCode::NOT_FOUND.into_response(),
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let running = match container.unpause(state.runtime.as_ref()).await {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Failed to unpause container: {}", e);
+            return (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "message": e.to_string() }))).into_response();
+        }
+    };
+
+    if let Err(e) = state.container_store.save_running(&running).await {
+        error!("Failed to save running state: {}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    info!(id = %id, "Container unpaused");
+    StatusCode::NO_CONTENT.into_response()
+}
+
+fn conflict(message: String) -> axum::response::Response {
+    (StatusCode::CONFLICT, Json(json!({ "message": message }))).into_response()
+}
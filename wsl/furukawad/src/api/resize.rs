@@ -0,0 +1,43 @@
+use axum::{extract::{Path, Query, State}, response::IntoResponse, http::StatusCode};
+use furukawa_domain::container::AnyContainer;
+use serde::Deserialize;
+use tracing::error;
+use crate::state::AppState;
+use super::lookup;
+
+/// Terminal size of `POST /containers/:id/resize` and `/exec/:id/resize`.
+#[derive(Deserialize)]
+pub struct ResizeQueryParams {
+    pub h: u16,
+    pub w: u16,
+}
+
+pub async fn handle(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<ResizeQueryParams>,
+) -> impl IntoResponse {
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status,
+    };
+
+    // Only a running container has a terminal to resize.
+    let container = match state.container_store.get_any(&id).await {
+        Ok(Some(AnyContainer::Running(c))) => c,
+        Ok(Some(_)) => return StatusCode::CONFLICT,
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    match state.runtime.resize(&container, params.h, params.w).await {
+        Ok(()) => StatusCode::OK,
+        Err(e) => {
+            error!("Failed to resize container TTY: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use axum::{extract::{Path, Query, State}, response::IntoResponse, http::StatusCode};
+use furukawa_domain::container::runtime::StopOptions;
+use furukawa_common::diagnostic::Error;
+use furukawa_domain::container::{AnyContainer, Container, Stopped};
+use serde::Deserialize;
+use tracing::{info, error};
+use crate::state::AppState;
+use super::lookup;
+
+#[derive(Deserialize)]
+pub struct RestartQueryParams {
+    /// Seconds to wait before killing the container.
+    t: Option<i64>,
+    /// Signal to send instead of the container's StopSignal.
+    signal: Option<String>,
+}
+
+pub async fn handle(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<RestartQueryParams>,
+) -> impl IntoResponse {
+    info!(id = %id, t = ?params.t, signal = ?params.signal, "Received restart container request");
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status,
+    };
+
+    let container = match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => return StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    // 1. Stop first if running (or paused), exactly like POST /stop
+    let options = StopOptions {
+        signal: params.signal,
+        timeout: params.t,
+    };
+    let started = match container {
+        AnyContainer::Running(running) => {
+            let stopped = running.stop(state.runtime.as_ref(), &options).await;
+            match save_stopped(&state, &id, stopped).await {
+                Ok(stopped) => stopped.start(state.runtime.as_ref()).await,
+                Err(status) => return status,
+            }
+        }
+        AnyContainer::Paused(paused) => {
+            let stopped = paused.stop(state.runtime.as_ref(), &options).await;
+            match save_stopped(&state, &id, stopped).await {
+                Ok(stopped) => stopped.start(state.runtime.as_ref()).await,
+                Err(status) => return status,
+            }
+        }
+        AnyContainer::Stopped(stopped) => stopped.start(state.runtime.as_ref()).await,
+        AnyContainer::Created(created) => created.start(state.runtime.as_ref()).await,
+    };
+
+    // 2. Start again and persist
+    let running = match started {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Failed to start container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        }
+    };
+
+    if let Err(e) = state.container_store.save_running(&running).await {
+        error!("Failed to save running state: {}", e);
+        return StatusCode::INTERNAL_SERVER_ERROR;
+    }
+
+    info!(id = %id, pid = %running.state().pid, "Container restarted successfully");
+
+    StatusCode::NO_CONTENT
+}
+
+/// Records the container stopped for the restart.
+async fn save_stopped(
+    state: &AppState,
+    id: &str,
+    stopped: Result<Container<Stopped>, Error>,
+) -> Result<Container<Stopped>, StatusCode> {
+    let stopped = match stopped {
+        Ok(c) => c,
+        Err(e) if e.code() == "RUNTIME_INVALID_SIGNAL" => {
+            error!("Failed to stop container: {}", e);
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        Err(e) => {
+            error!("Failed to stop container: {}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+    if let Err(e) = state.container_store.save_stopped(&stopped).await {
+        error!("Failed to save stopped state: {}", e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    info!(id = %id, exit_code = %stopped.state().exit_code, "Container stopped for restart");
+    Ok(stopped)
+}
@@ -1,15 +1,21 @@
 use axum::{extract::{Path, State}, response::IntoResponse, http::StatusCode};
+use furukawa_domain::container::AnyContainer;
 use tracing::{info, error};
 use crate::state::AppState;
+use super::lookup;
 
 pub async fn handle(
     State(state): State<AppState>,
     Path(id): Path<String>,
 ) -> impl IntoResponse {
     info!(id = %id, "Received start container request");
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status,
+    };
 
-    // 1. Load Container (Created state)
-    let container = match state.container_store.get(&id).await {
+    // 1. Load Container. Docker answers 304 if it is already running.
+    let container = match state.container_store.get_any(&id).await {
         Ok(Some(c)) => c,
         Ok(None) => return StatusCode::NOT_FOUND,
         Err(e) => {
@@ -18,13 +24,19 @@ pub async fn handle(
         }
     };
 
-    // 2. Start via Runtime
-    let running_container = match container.start(state.runtime.as_ref()).await {
+    // 2. Start via Runtime (created or previously stopped)
+    let started = match container {
+        AnyContainer::Created(c) => c.start(state.runtime.as_ref()).await,
+        AnyContainer::Stopped(c) => c.start(state.runtime.as_ref()).await,
+        AnyContainer::Running(_) => return StatusCode::NOT_MODIFIED,
+        // Docker: "cannot start a paused container, try unpause instead"
+        AnyContainer::Paused(_) => return StatusCode::CONFLICT,
+    };
+    let running_container = match started {
         Ok(c) => c,
         Err(e) => {
             error!("Failed to start container: {}", e);
             return StatusCode::INTERNAL_SERVER_ERROR;
-            // TODO: Map to 304 if already started, 400 if error
         }
     };
 
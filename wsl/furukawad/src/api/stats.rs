@@ -0,0 +1,227 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use furukawa_infra_docker::v1_45::{
+    BlkioStatEntry, BlkioStats, ContainerStats, CpuStats, CpuUsage, MemoryStats, NetworkStats, PidsStats, ThrottlingData,
+};
+use furukawa_infra_runtime::stats::{self, Sample};
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tracing::error;
+use crate::state::AppState;
+use super::{logs::rfc3339_nano, lookup, params};
+
+/// Time between samples, as in Docker.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Go's zero time, Docker's `read`/`preread` when there is no sample.
+const ZERO_TIME: &str = "0001-01-01T00:00:00Z";
+
+fn default_stream() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+pub struct StatsQueryParams {
+    #[serde(default = "default_stream", deserialize_with = "params::flag")]
+    stream: bool,
+    /// With `stream=false`, answer at once instead of waiting for a second
+    /// sample to fill in `precpu_stats`.
+    #[serde(default, rename = "one-shot", deserialize_with = "params::flag")]
+    one_shot: bool,
+}
+
+/// `GET /containers/:id/stats`: a sample every second as newline-delimited
+/// JSON, or a single one with `stream=false`.
+pub async fn handle(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<StatsQueryParams>,
+) -> impl IntoResponse {
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status.into_response(),
+    };
+    if query.stream && query.one_shot {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "message": "cannot have stream=true and one-shot=true" }))).into_response();
+    }
+
+    let container = match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such container: {}", id) }))).into_response();
+        }
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let name = format!("/{}", container.name());
+
+    let (tx, rx) = mpsc::channel(4);
+    match container.pid() {
+        // A paused container still reports its (frozen) usage.
+        Some(pid) => {
+            let cgroup = state.cgroups.as_ref().map(|cgroups| cgroups.path(&id)).filter(|path| path.exists());
+            tokio::spawn(async move {
+                stream_stats(&state, &id, &name, pid, cgroup, &query, &tx).await;
+            });
+        }
+        // Nothing to measure: one empty sample, as Docker sends.
+        None => {
+            let stats = ContainerStats {
+                name,
+                id,
+                read: ZERO_TIME.to_string(),
+                preread: ZERO_TIME.to_string(),
+                storage_stats: json!({}),
+                ..Default::default()
+            };
+            let _ = tx.try_send(encode(&stats));
+        }
+    }
+
+    let body = Body::from_stream(ReceiverStream::new(rx).map(Ok::<_, Infallible>));
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+/// Sends samples until the container stops or the client goes away (or
+/// just one without `stream`).
+async fn stream_stats(
+    state: &AppState,
+    id: &str,
+    name: &str,
+    pid: u32,
+    cgroup: Option<PathBuf>,
+    query: &StatsQueryParams,
+    tx: &mpsc::Sender<Bytes>,
+) {
+    let sample = || {
+        let cgroup = cgroup.clone();
+        async move { tokio::task::spawn_blocking(move || stats::collect(pid, cgroup.as_deref())).await.ok() }
+    };
+
+    let mut previous = None;
+    if !query.stream && !query.one_shot {
+        previous = sample().await;
+        tokio::time::sleep(SAMPLE_INTERVAL).await;
+    }
+    loop {
+        let Some(current) = sample().await else { return };
+        if tx.send(encode(&to_docker(name, id, &current, previous.as_ref()))).await.is_err() || !query.stream {
+            return;
+        }
+        previous = Some(current);
+
+        tokio::select! {
+            _ = tokio::time::sleep(SAMPLE_INTERVAL) => {}
+            _ = tx.closed() => return,
+        }
+        if !matches!(state.container_store.get_status(id).await, Ok(Some(status)) if status == "running" || status == "paused") {
+            return;
+        }
+    }
+}
+
+fn encode(stats: &ContainerStats) -> Bytes {
+    let mut json = serde_json::to_vec(stats).unwrap_or_default();
+    json.push(b'\n');
+    Bytes::from(json)
+}
+
+fn to_docker(name: &str, id: &str, sample: &Sample, previous: Option<&Sample>) -> ContainerStats {
+    let entries = |value: fn(&stats::BlockIo) -> (u64, u64)| {
+        sample
+            .block_io
+            .iter()
+            .flat_map(|io| {
+                let (read, write) = value(io);
+                [("read", read), ("write", write)].map(|(op, value)| BlkioStatEntry {
+                    major: io.major,
+                    minor: io.minor,
+                    op: op.to_string(),
+                    value,
+                })
+            })
+            .collect()
+    };
+
+    ContainerStats {
+        name: name.to_string(),
+        id: id.to_string(),
+        read: rfc3339_nano(sample.read),
+        preread: previous.map_or_else(|| ZERO_TIME.to_string(), |p| rfc3339_nano(p.read)),
+        pids_stats: PidsStats {
+            current: sample.pids,
+            limit: sample.pids_limit,
+        },
+        blkio_stats: BlkioStats {
+            io_service_bytes_recursive: Some(entries(|io| (io.read_bytes, io.write_bytes))),
+            io_serviced_recursive: Some(entries(|io| (io.reads, io.writes))),
+            ..Default::default()
+        },
+        num_procs: 0,
+        storage_stats: json!({}),
+        cpu_stats: cpu_stats(Some(sample)),
+        precpu_stats: cpu_stats(previous),
+        memory_stats: MemoryStats {
+            usage: sample.memory.usage,
+            stats: Some(sample.memory.stats.clone()),
+            limit: sample.memory.limit,
+        },
+        networks: Some(
+            sample
+                .networks
+                .iter()
+                .map(|(name, n)| {
+                    (
+                        name.clone(),
+                        NetworkStats {
+                            rx_bytes: n.rx_bytes,
+                            rx_packets: n.rx_packets,
+                            rx_errors: n.rx_errors,
+                            rx_dropped: n.rx_dropped,
+                            tx_bytes: n.tx_bytes,
+                            tx_packets: n.tx_packets,
+                            tx_errors: n.tx_errors,
+                            tx_dropped: n.tx_dropped,
+                        },
+                    )
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn cpu_stats(sample: Option<&Sample>) -> CpuStats {
+    let Some(sample) = sample else {
+        return CpuStats::default();
+    };
+    CpuStats {
+        cpu_usage: CpuUsage {
+            total_usage: sample.cpu.total_ns,
+            usage_in_kernelmode: sample.cpu.system_ns,
+            usage_in_usermode: sample.cpu.user_ns,
+        },
+        system_cpu_usage: sample.system_cpu_ns,
+        online_cpus: Some(sample.online_cpus),
+        throttling_data: ThrottlingData {
+            periods: sample.cpu.periods,
+            throttled_periods: sample.cpu.throttled_periods,
+            throttled_time: sample.cpu.throttled_ns,
+        },
+    }
+}
@@ -0,0 +1,24 @@
+//! Docker's multiplexed stream format (`application/vnd.docker.multiplexed-stream`),
+//! used for logs and for attach/exec output of containers without a TTY.
+
+use bytes::Bytes;
+use furukawa_infra_runtime::logs::Stream;
+
+pub const MULTIPLEXED_STREAM: &str = "application/vnd.docker.multiplexed-stream";
+/// Output of TTY sessions is sent as is.
+pub const RAW_STREAM: &str = "application/vnd.docker.raw-stream";
+
+/// Encodes one frame: a header of the stream type (1 = stdout, 2 = stderr),
+/// three zero bytes and the payload length as a big-endian u32, followed by
+/// the payload.
+pub fn frame(stream: Stream, payload: &[u8]) -> Bytes {
+    let stream_type = match stream {
+        Stream::Stdout => 1,
+        Stream::Stderr => 2,
+    };
+    let mut frame = Vec::with_capacity(8 + payload.len());
+    frame.extend_from_slice(&[stream_type, 0, 0, 0]);
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    Bytes::from(frame)
+}
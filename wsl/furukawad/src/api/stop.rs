@@ -1,35 +1,62 @@
-use axum::{extract::{Path, State}, response::IntoResponse, http::StatusCode};
+use axum::{extract::{Path, Query, State}, response::IntoResponse, http::StatusCode};
+use furukawa_domain::container::runtime::StopOptions;
+use furukawa_domain::container::AnyContainer;
+use serde::Deserialize;
 use tracing::{info, error};
 use crate::state::AppState;
+use super::lookup;
+
+#[derive(Deserialize)]
+pub struct StopQueryParams {
+    /// Seconds to wait before killing the container.
+    t: Option<i64>,
+    /// Signal to send instead of the container's StopSignal.
+    signal: Option<String>,
+}
 
 pub async fn handle(
     State(state): State<AppState>,
     Path(id): Path<String>,
+    Query(params): Query<StopQueryParams>,
 ) -> impl IntoResponse {
-    info!(id = %id, "Received stop container request");
-
-    // 1. Load Container (Running state)
-    // We specifically need a running container to stop it.
-    let container = match state.container_store.get_running(&id).await {
-        Ok(Some(c)) => c,
-        Ok(None) => {
-            // It might exist but be stopped or created.
-            // Ideally we check if it exists at all to return 304 or 404.
-            // For now, 404 if not running is acceptable for prototype.
-            // Strict Docker API returns 304 if already stopped.
-            // We'd need a generic get_any_state to differentiate.
-            // But let's stick to 404 for "Running container not found".
-            return StatusCode::NOT_FOUND;
+    info!(id = %id, t = ?params.t, signal = ?params.signal, "Received stop container request");
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status,
+    };
+
+    let options = StopOptions {
+        signal: params.signal,
+        timeout: params.t,
+    };
+
+    // 1. Load Container. Docker answers 304 if it is not running.
+    // 2. Stop via Runtime: StopSignal, grace period, then SIGKILL. A paused
+    //    container is thawed once signalled, so that it can handle it.
+    let stopped = match state.container_store.get_any(&id).await {
+        Ok(Some(AnyContainer::Running(c))) => c.stop(state.runtime.as_ref(), &options).await,
+        Ok(Some(AnyContainer::Paused(c))) => c.stop(state.runtime.as_ref(), &options).await,
+        // Waiting to be restarted by its policy: stopping cancels that.
+        Ok(Some(AnyContainer::Stopped(c))) if !c.state().manually_stopped => {
+            if let Err(e) = state.container_store.save_stopped(&c.cancel_restart()).await {
+                error!("Failed to save stopped state: {}", e);
+                return StatusCode::INTERNAL_SERVER_ERROR;
+            }
+            return StatusCode::NOT_MODIFIED;
         }
+        Ok(Some(_)) => return StatusCode::NOT_MODIFIED,
+        Ok(None) => return StatusCode::NOT_FOUND,
         Err(e) => {
             error!("Failed to load container: {}", e);
             return StatusCode::INTERNAL_SERVER_ERROR;
         }
     };
-
-    // 2. Stop via Runtime
-    let stopped_container = match container.stop(state.runtime.as_ref()).await {
+    let stopped_container = match stopped {
         Ok(c) => c,
+        Err(e) if e.code() == "RUNTIME_INVALID_SIGNAL" => {
+            error!("Failed to stop container: {}", e);
+            return StatusCode::BAD_REQUEST;
+        }
         Err(e) => {
             error!("Failed to stop container: {}", e);
             return StatusCode::INTERNAL_SERVER_ERROR;
@@ -46,3 +73,79 @@ pub async fn handle(
     
     StatusCode::NO_CONTENT
 }
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use furukawa_common::diagnostic::Error;
+    use furukawa_common::Result;
+    use furukawa_domain::container::runtime::{ContainerRuntime, StopOptions};
+    use furukawa_domain::container::store::ContainerStore;
+    use furukawa_domain::container::{AnyContainer, Config, Container, Created, Paused, Running};
+    use furukawa_infra_runtime::RuntimeError;
+    use std::sync::Arc;
+    use tower::ServiceExt;
+
+    /// Exits with 143 when stopped, as a process handling SIGTERM would.
+    /// Every other operation fails, so a handler taking another path shows up
+    /// as an error response rather than a panic.
+    struct FrozenRuntime;
+
+    fn unsupported<T>() -> Result<T> {
+        Err(Error::new(RuntimeError::PauseUnsupported("the test runtime only stops paused containers".to_string())))
+    }
+
+    #[async_trait]
+    impl ContainerRuntime for FrozenRuntime {
+        async fn start(&self, _container: &Container<Created>) -> Result<Running> {
+            unsupported()
+        }
+        async fn stop(&self, _container: &Container<Running>, _options: &StopOptions) -> Result<i32> {
+            unsupported()
+        }
+        async fn stop_paused(&self, _container: &Container<Paused>, _options: &StopOptions) -> Result<i32> {
+            Ok(143)
+        }
+        async fn kill(&self, _container: &Container<Running>, _signal: &str) -> Result<()> {
+            unsupported()
+        }
+        async fn kill_paused(&self, _container: &Container<Paused>, _signal: &str) -> Result<()> {
+            unsupported()
+        }
+        async fn pause(&self, _container: &Container<Running>) -> Result<()> {
+            unsupported()
+        }
+        async fn unpause(&self, _container: &Container<Paused>) -> Result<()> {
+            unsupported()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stop_paused_container() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let (state, store) = crate::api::testing::app_state(tmp.path(), Some(Arc::new(FrozenRuntime))).await;
+
+        let paused = Container::<Paused>::restore(
+            "c-0001".to_string(),
+            "frozen".to_string(),
+            Config::default(),
+            Paused { pid: 4242, started_at: time::OffsetDateTime::now_utc(), process_start: None },
+        );
+        store.save(&Container::new("c-0001".to_string(), "frozen".to_string(), Config::default())).await.unwrap();
+        store.save_paused(&paused).await.unwrap();
+
+        let request = Request::post("/containers/frozen/stop").body(Body::empty()).unwrap();
+        let response = crate::api::router(state).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+        match store.get_any("c-0001").await.unwrap() {
+            Some(AnyContainer::Stopped(c)) => {
+                assert_eq!(c.state().exit_code, 143);
+                assert!(c.state().manually_stopped);
+            }
+            other => panic!("Expected a stopped container, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Streaming tar archives written by blocking code as response bodies.
+
+use axum::body::Body;
+use bytes::Bytes;
+use std::convert::Infallible;
+use std::io::{self, Write};
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+
+/// Size of the chunks the body is sent in.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// A body fed by `write`, which runs on the blocking pool. Once the
+/// response has started, failures can only end it early, so `write`
+/// reports them itself.
+pub fn body<F>(write: F) -> Body
+where
+    F: FnOnce(io::BufWriter<ChannelWriter>) + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel::<Bytes>(16);
+    tokio::task::spawn_blocking(move || write(io::BufWriter::with_capacity(CHUNK_SIZE, ChannelWriter(tx))));
+    Body::from_stream(ReceiverStream::new(rx).map(Ok::<_, Infallible>))
+}
+
+/// Hands what is written to the response body; fails once the client has
+/// gone away, which ends the writing.
+pub struct ChannelWriter(mpsc::Sender<Bytes>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Bytes::copy_from_slice(buf))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "client went away"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
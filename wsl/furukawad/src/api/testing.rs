@@ -0,0 +1,35 @@
+//! Shared setup for handler tests.
+
+use furukawa_domain::container::runtime::ContainerRuntime;
+use furukawa_infra_db::SqliteStore;
+use furukawa_infra_fs::store::image::ImageStore;
+use furukawa_infra_runtime::{AttachHub, ExecManager, HealthMonitor, LogStore, ProcessRuntime, Supervisor};
+use std::path::Path;
+use std::sync::Arc;
+use crate::state::AppState;
+
+/// Builds an `AppState` whose stores and directories all live under `dir`,
+/// running containers with `runtime` or else the process runtime. The
+/// returned store is the one behind every store field of the state.
+pub async fn app_state(dir: &Path, runtime: Option<Arc<dyn ContainerRuntime>>) -> (AppState, Arc<SqliteStore>) {
+    let url = format!("sqlite://{}?mode=rwc", dir.join("rustker.db").display());
+    let store = Arc::new(SqliteStore::new(&url).await.unwrap());
+    let logs = Arc::new(LogStore::new(dir.join("logs"), Arc::new(AttachHub::default())));
+    let supervisor = Arc::new(Supervisor::new(store.clone(), logs.clone()));
+    let exec_runtime = Arc::new(ProcessRuntime { supervisor: supervisor.clone(), logs: logs.clone() });
+    let state = AppState {
+        container_store: store.clone(),
+        runtime: runtime.unwrap_or_else(|| exec_runtime.clone()),
+        supervisor: supervisor.clone(),
+        execs: Arc::new(ExecManager::new(exec_runtime.clone(), store.clone())),
+        health: Arc::new(HealthMonitor::new(store.clone(), exec_runtime, supervisor)),
+        logs,
+        cgroups: None,
+        registry: furukawa_infra_registry::RegistryClient::new(),
+        image_store: Arc::new(ImageStore::new(dir.join("data"))),
+        containers_root: dir.join("containers"),
+        image_metadata_store: store.clone(),
+        network_store: store.clone(),
+    };
+    (state, store)
+}
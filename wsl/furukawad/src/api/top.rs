@@ -0,0 +1,73 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use furukawa_infra_docker::v1_45::ContainerTopResponse;
+use furukawa_infra_runtime::top;
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{error, info};
+use crate::state::AppState;
+use super::lookup;
+
+#[derive(Deserialize)]
+pub struct TopQueryParams {
+    /// Arguments for `ps`, `-ef` by default.
+    #[serde(default)]
+    ps_args: String,
+}
+
+/// `GET /containers/:id/top`: the processes running in the container.
+pub async fn handle(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<TopQueryParams>,
+) -> impl IntoResponse {
+    info!(id = %id, ps_args = %params.ps_args, "Received container top request");
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status.into_response(),
+    };
+
+    // Paused containers are listed too, as in Docker.
+    let pid = match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => match c.pid() {
+            Some(pid) => pid,
+            None => {
+                return (StatusCode::CONFLICT, Json(json!({ "message": format!("Container {} is not running", id) }))).into_response();
+            }
+        },
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such container: {}", id) }))).into_response();
+        }
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+
+    let cgroup = state.cgroups.as_ref().map(|cgroups| cgroups.path(&id)).filter(|path| path.exists());
+    let listed = tokio::task::spawn_blocking(move || {
+        let pids = top::container_pids(pid, cgroup.as_deref());
+        top::ps(&params.ps_args, &pids)
+    })
+    .await;
+
+    match listed {
+        Ok(Ok(top)) => Json(ContainerTopResponse {
+            titles: top.titles,
+            processes: top.processes,
+        })
+        .into_response(),
+        Ok(Err(e)) => {
+            error!("Failed to list container processes: {}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({ "message": e.to_string() }))).into_response()
+        }
+        Err(e) => {
+            error!("Process listing task failed: {}", e);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     response::IntoResponse,
     Json,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use tokio::fs;
-use tracing::info;
+use tracing::{info, warn};
+use crate::api::filters::Filters;
 use crate::state::AppState;
 
 fn volumes_root() -> PathBuf {
@@ -14,6 +16,35 @@ fn volumes_root() -> PathBuf {
     PathBuf::from(data).join("rustker_data").join("volumes")
 }
 
+/// Labels are kept outside the volume directories, which containers see,
+/// as `<name>.json` per labelled volume.
+fn labels_root() -> PathBuf {
+    volumes_root().with_file_name("volume_labels")
+}
+
+/// Docker's rule for local volume names, `[a-zA-Z0-9][a-zA-Z0-9_.-]+`,
+/// which also keeps them to a single path component.
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && name.len() > 1
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+}
+
+async fn load_labels(name: &str) -> HashMap<String, String> {
+    match fs::read(labels_root().join(format!("{}.json", name))).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            warn!("Ignoring unreadable labels of volume '{}': {}", name, e);
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+async fn remove_labels(name: &str) {
+    let _ = fs::remove_file(labels_root().join(format!("{}.json", name))).await;
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "PascalCase")]
 pub struct VolumeInfo {
@@ -24,8 +55,20 @@ pub struct VolumeInfo {
     pub labels: std::collections::HashMap<String, String>,
 }
 
-/// GET /volumes
-pub async fn list(State(_state): State<AppState>) -> impl IntoResponse {
+#[derive(Debug, Deserialize)]
+pub struct ListVolumesParams {
+    pub filters: Option<String>,
+}
+
+/// GET /volumes, with `driver`, `label` and `name` filters
+pub async fn list(State(_state): State<AppState>, Query(params): Query<ListVolumesParams>) -> impl IntoResponse {
+    let filters = match Filters::parse(params.filters.as_deref()).and_then(|f| f.validate(&["driver", "label", "name"]).map(|_| f)) {
+        Ok(filters) => filters,
+        Err(message) => {
+            return (axum::http::StatusCode::BAD_REQUEST, Json(serde_json::json!({ "message": message })));
+        }
+    };
+
     let root = volumes_root();
     let _ = fs::create_dir_all(&root).await;
 
@@ -34,21 +77,33 @@ pub async fn list(State(_state): State<AppState>) -> impl IntoResponse {
         while let Ok(Some(entry)) = rd.next_entry().await {
             let name = entry.file_name().to_string_lossy().to_string();
             let mp = entry.path().to_string_lossy().to_string();
+            let labels = load_labels(&name).await;
+            let names = filters.get("name");
+            let drivers = filters.get("driver");
+            if !(names.is_empty() || names.iter().any(|n| name.contains(n.as_str())))
+                || !(drivers.is_empty() || drivers.iter().any(|d| d == "local"))
+                || !filters.match_labels(&labels)
+            {
+                continue;
+            }
             volumes.push(VolumeInfo {
                 name,
                 driver: "local".into(),
                 mountpoint: mp,
                 scope: "local".into(),
-                labels: Default::default(),
+                labels,
             });
         }
     }
 
     // Docker API wraps volumes in { "Volumes": [...], "Warnings": null }
-    Json(serde_json::json!({
-        "Volumes": volumes,
-        "Warnings": null
-    }))
+    (
+        axum::http::StatusCode::OK,
+        Json(serde_json::json!({
+            "Volumes": volumes,
+            "Warnings": null
+        })),
+    )
 }
 
 #[derive(Debug, Deserialize)]
@@ -56,6 +111,8 @@ pub async fn list(State(_state): State<AppState>) -> impl IntoResponse {
 pub struct CreateVolumeRequest {
     pub name: String,
     pub driver: Option<String>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
 }
 
 /// POST /volumes/create
@@ -63,6 +120,13 @@ pub async fn create(
     State(_state): State<AppState>,
     Json(body): Json<CreateVolumeRequest>,
 ) -> impl IntoResponse {
+    if !valid_name(&body.name) {
+        let message = format!(
+            "create {}: \"{}\" includes invalid characters for a local volume name, only \"[a-zA-Z0-9][a-zA-Z0-9_.-]\" are allowed",
+            body.name, body.name
+        );
+        return (axum::http::StatusCode::BAD_REQUEST, Json(serde_json::json!({ "message": message })));
+    }
     let root = volumes_root();
     let vol_path = root.join(&body.name);
 
@@ -73,6 +137,22 @@ pub async fn create(
         );
     }
 
+    if !body.labels.is_empty() {
+        let saved = match serde_json::to_vec(&body.labels) {
+            Ok(json) => match fs::create_dir_all(labels_root()).await {
+                Ok(()) => fs::write(labels_root().join(format!("{}.json", body.name)), json).await,
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = saved {
+            return (
+                axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+                Json(serde_json::json!({"message": format!("failed to save volume labels: {}", e)})),
+            );
+        }
+    }
+
     info!("Created volume '{}' at {:?}", body.name, vol_path);
 
     let mp = vol_path.to_string_lossy().to_string();
@@ -83,7 +163,7 @@ pub async fn create(
             "Driver": body.driver.unwrap_or_else(|| "local".into()),
             "Mountpoint": mp,
             "Scope": "local",
-            "Labels": {}
+            "Labels": body.labels,
         })),
     )
 }
@@ -96,13 +176,14 @@ pub async fn delete(
     let root = volumes_root();
     let vol_path = root.join(&name);
 
-    if vol_path.exists() {
+    if valid_name(&name) && vol_path.exists() {
         if let Err(e) = fs::remove_dir_all(&vol_path).await {
             return (
                 axum::http::StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({"message": e.to_string()})),
             );
         }
+        remove_labels(&name).await;
         info!("Deleted volume '{}'", name);
         (axum::http::StatusCode::NO_CONTENT, Json(serde_json::json!({})))
     } else {
@@ -121,6 +202,7 @@ pub async fn prune(State(_state): State<AppState>) -> impl IntoResponse {
         while let Ok(Some(entry)) = rd.next_entry().await {
             let name = entry.file_name().to_string_lossy().to_string();
             if fs::remove_dir_all(entry.path()).await.is_ok() {
+                remove_labels(&name).await;
                 pruned.push(name);
             }
         }
@@ -130,3 +212,19 @@ pub async fn prune(State(_state): State<AppState>) -> impl IntoResponse {
         "SpaceReclaimed": 0
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_name() {
+        assert!(valid_name("db-data"));
+        assert!(valid_name("app_1.cache"));
+        assert!(!valid_name("x"));
+        assert!(!valid_name(".."));
+        assert!(!valid_name("../../x"));
+        assert!(!valid_name("-data"));
+        assert!(!valid_name("a/b"));
+    }
+}
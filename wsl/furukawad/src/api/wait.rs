@@ -0,0 +1,112 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use bytes::Bytes;
+use furukawa_domain::container::AnyContainer;
+use furukawa_infra_docker::v1_45::ContainerWaitResponse;
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
+use tracing::{error, info};
+use crate::state::AppState;
+use super::lookup;
+
+#[derive(Deserialize)]
+pub struct WaitQueryParams {
+    /// `not-running` (default), `next-exit` or `removed`.
+    condition: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Condition {
+    NotRunning,
+    NextExit,
+    Removed,
+}
+
+/// `POST /containers/:id/wait`: blocks until the condition holds, then
+/// answers with the container's exit code.
+///
+/// The status line goes out at once so that clients (`docker run`) know the
+/// wait is in place before they start the container; the body follows when
+/// the container exits or is removed.
+pub async fn handle(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(params): Query<WaitQueryParams>,
+) -> impl IntoResponse {
+    let condition = match params.condition.as_deref() {
+        None | Some("") | Some("not-running") => Condition::NotRunning,
+        Some("next-exit") => Condition::NextExit,
+        Some("removed") => Condition::Removed,
+        Some(other) => {
+            return (StatusCode::BAD_REQUEST, Json(json!({ "message": format!("invalid condition: \"{}\"", other) }))).into_response();
+        }
+    };
+    info!(id = %id, ?condition, "Received wait container request");
+    let id = match lookup::container_id(&state, &id).await {
+        Ok(id) => id,
+        Err(status) => return status.into_response(),
+    };
+
+    // Subscribe before looking at the state, so an exit in between is not
+    // missed: it either shows in the state or arrives as a notice.
+    let mut notices = state.supervisor.notices(&id);
+    let seen = notices.borrow().exits;
+
+    let container = match state.container_store.get_any(&id).await {
+        Ok(Some(c)) => c,
+        Ok(None) => {
+            return (StatusCode::NOT_FOUND, Json(json!({ "message": format!("No such container: {}", id) }))).into_response();
+        }
+        Err(e) => {
+            error!("Failed to load container: {}", e);
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let last_exit_code = match &container {
+        AnyContainer::Stopped(c) => c.state().exit_code,
+        _ => 0,
+    };
+
+    let (tx, rx) = mpsc::channel(1);
+    if condition == Condition::NotRunning && container.pid().is_none() {
+        let _ = tx.try_send(encode(last_exit_code));
+    } else {
+        tokio::spawn(async move {
+            // Removal ends every wait: there is nothing left to exit.
+            let done = notices.wait_for(|notice| notice.removed || (condition != Condition::Removed && notice.exits > seen));
+            let exit_code = tokio::select! {
+                notice = done => match notice {
+                    Ok(notice) => notice.exit_code.unwrap_or(last_exit_code),
+                    Err(_) => last_exit_code,
+                },
+                // The client gave up.
+                _ = tx.closed() => return,
+            };
+            let _ = tx.send(encode(exit_code)).await;
+        });
+    }
+
+    let body = Body::from_stream(ReceiverStream::new(rx).map(Ok::<_, Infallible>));
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body)
+        .unwrap_or_else(|_| StatusCode::INTERNAL_SERVER_ERROR.into_response())
+}
+
+fn encode(exit_code: i32) -> Bytes {
+    let response = ContainerWaitResponse {
+        status_code: i64::from(exit_code),
+        error: None,
+    };
+    let mut json = serde_json::to_vec(&response).unwrap_or_default();
+    json.push(b'\n');
+    Bytes::from(json)
+}
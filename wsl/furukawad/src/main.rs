@@ -1,4 +1,6 @@
 use furukawa_common::telemetry;
+use furukawa_domain::container::runtime::ContainerRuntime;
+use furukawa_infra_runtime::ExecRuntime;
 use tracing::info;
 
 #[tokio::main]
@@ -19,7 +21,8 @@ async fn main() -> anyhow::Result<()> {
     let distro_name = std::env::var("RUSTKER_DISTRO")
         .unwrap_or_else(|_| "rustker-alpine".to_string());
     
-    let skip_wsl_setup = std::env::var("RUSTKER_SKIP_WSL_SETUP").is_ok();
+    // On a Linux host containers run natively, so there is no distro to set up.
+    let skip_wsl_setup = cfg!(target_os = "linux") || std::env::var("RUSTKER_SKIP_WSL_SETUP").is_ok();
     if !skip_wsl_setup {
         let wsl_manager = furukawa_infra_wsl::WslManager::new(
             distro_name.clone(),
@@ -54,21 +57,89 @@ async fn main() -> anyhow::Result<()> {
         rustker_data.clone()
     ));
     image_store.ensure_dirs().await?;
+    let containers_root = rustker_data.join("containers");
 
-    // ── Runtime (WSL2) ────────────────────────────────────────────────────────
-    let runtime = furukawa_infra_runtime::WslRuntime {
-        image_store: image_store.clone(),
-        metadata_store: store.clone(),
-        containers_root: rustker_data.join("containers"),
-        distro: distro_name,
+    // ── Container logs ───────────────────────────────────────────────────────
+    let attach = std::sync::Arc::new(furukawa_infra_runtime::AttachHub::default());
+    let logs = std::sync::Arc::new(furukawa_infra_runtime::LogStore::new(data_dir.join("logs"), attach));
+
+    // ── Supervisor (exit tracking for container processes) ──────────────────
+    let supervisor = std::sync::Arc::new(furukawa_infra_runtime::Supervisor::new(store.clone(), logs.clone()));
+    // Containers left "running" by a previous daemon: adopt or mark stopped.
+    if let Err(e) = supervisor.reconcile().await {
+        tracing::error!("Startup reconciliation failed: {}", e);
+    }
+
+    // ── Runtime (native namespaces on Linux, WSL2 elsewhere) ─────────────────
+    #[cfg(target_os = "linux")]
+    let cgroups = furukawa_infra_runtime::cgroup::Cgroups::init(std::path::Path::new(furukawa_infra_runtime::cgroup::MOUNT))
+        .map(std::sync::Arc::new);
+    #[cfg(not(target_os = "linux"))]
+    let cgroups = None;
+
+    #[cfg(target_os = "linux")]
+    let (runtime, exec_runtime): (std::sync::Arc<dyn ContainerRuntime>, std::sync::Arc<dyn ExecRuntime>) = {
+        info!("Using native Linux runtime (namespaces + pivot_root)");
+        let runtime = std::sync::Arc::new(furukawa_infra_runtime::LinuxRuntime {
+            image_store: image_store.clone(),
+            metadata_store: store.clone(),
+            containers_root: containers_root.clone(),
+            supervisor: supervisor.clone(),
+            logs: logs.clone(),
+            cgroups: cgroups.clone(),
+        });
+        (runtime.clone(), runtime)
+    };
+    #[cfg(not(target_os = "linux"))]
+    let (runtime, exec_runtime): (std::sync::Arc<dyn ContainerRuntime>, std::sync::Arc<dyn ExecRuntime>) = {
+        let runtime = std::sync::Arc::new(furukawa_infra_runtime::WslRuntime {
+            image_store: image_store.clone(),
+            metadata_store: store.clone(),
+            containers_root: containers_root.clone(),
+            distro: distro_name,
+            supervisor: supervisor.clone(),
+            logs: logs.clone(),
+        });
+        (runtime.clone(), runtime)
     };
 
+    // ── Exec instances ──────────────────────────────────────────────────────
+    let execs = std::sync::Arc::new(furukawa_infra_runtime::ExecManager::new(exec_runtime.clone(), store.clone()));
+
+    // ── Health checks ───────────────────────────────────────────────────────
+    let health = std::sync::Arc::new(furukawa_infra_runtime::HealthMonitor::new(
+        store.clone(),
+        exec_runtime,
+        supervisor.clone(),
+    ));
+    tokio::spawn(health.clone().run(supervisor.subscribe_starts()));
+    // Containers adopted above started before the monitor listened.
+    if let Err(e) = health.watch_running().await {
+        tracing::error!("Failed to start health checks: {}", e);
+    }
+
+    // ── Restart policies ────────────────────────────────────────────────────
+    let restart_manager = std::sync::Arc::new(furukawa_infra_runtime::RestartManager::new(
+        store.clone(),
+        runtime.clone(),
+    ));
+    tokio::spawn(restart_manager.clone().run(supervisor.subscribe()));
+    if let Err(e) = restart_manager.start_on_boot().await {
+        tracing::error!("Failed to start containers on boot: {}", e);
+    }
+
     // ── App State ───────────────────────────────────────────────────────────
     let state = state::AppState {
         container_store: store.clone(),
-        runtime: std::sync::Arc::new(runtime),
+        runtime,
+        supervisor,
+        execs,
+        health,
+        logs,
+        cgroups,
         registry,
         image_store,
+        containers_root,
         image_metadata_store: store.clone(),
         network_store: store,
     };
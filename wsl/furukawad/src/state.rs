@@ -4,14 +4,25 @@ use furukawa_domain::image::store::ImageMetadataStore;
 use furukawa_domain::container::store::ContainerStore;
 use furukawa_domain::container::runtime::ContainerRuntime;
 use furukawa_domain::network::NetworkStore;
+use furukawa_infra_runtime::cgroup::Cgroups;
+use furukawa_infra_runtime::{ExecManager, HealthMonitor, LogStore, Supervisor};
+use std::path::PathBuf;
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AppState {
     pub container_store: Arc<dyn ContainerStore>,
     pub runtime: Arc<dyn ContainerRuntime>,
+    pub supervisor: Arc<Supervisor>,
+    pub execs: Arc<ExecManager>,
+    pub health: Arc<HealthMonitor>,
+    pub logs: Arc<LogStore>,
+    /// `None` if resource limits cannot be enforced on this host.
+    pub cgroups: Option<Arc<Cgroups>>,
     pub registry: RegistryClient,
     pub image_store: Arc<ImageStore>,
+    /// Holds `<id>/rootfs` for every container that has been started.
+    pub containers_root: PathBuf,
     pub image_metadata_store: Arc<dyn ImageMetadataStore>,
     pub network_store: Arc<dyn NetworkStore>,
 }